@@ -11,29 +11,49 @@ pub fn init_tracing_subscriber() {
     tracing_subscriber::fmt().with_env_filter(env_filter).init();
 }
 
+/// The janus api the tests connect with, and where it receives the events from
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Transport {
+    WebSocket,
+    Restful,
+    /// Requests over HTTP, events over the WebSocket transport of the same janus
+    HybridWebSocket,
+    /// Requests over HTTP, events long-polled per session
+    HybridLongPoll,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum TestingEnv {
-    Multistream(JanusAPI),
-    Legacy(JanusAPI),
+    Multistream(Transport),
+    Legacy(Transport),
 }
 
 impl TestingEnv {
     pub fn url(&self) -> &'static str {
         match self {
-            Self::Multistream(JanusAPI::WebSocket) => "ws://localhost:8188/ws",
-            Self::Multistream(JanusAPI::Restful) => "http://localhost:8088",
-            Self::Legacy(JanusAPI::WebSocket) => "ws://localhost:9188/ws",
-            Self::Legacy(JanusAPI::Restful) => "http://localhost:9088",
-            #[cfg(all(not(target_family = "wasm"), feature = "socketio"))]
-            Self::Multistream(JanusAPI::SocketIo) | Self::Legacy(JanusAPI::SocketIo) => {
-                unimplemented!("Socket.IO transport is not covered by the e2e suite")
-            }
+            Self::Multistream(Transport::WebSocket) => "ws://localhost:8188/ws",
+            Self::Multistream(_) => "http://localhost:8088",
+            Self::Legacy(Transport::WebSocket) => "ws://localhost:9188/ws",
+            Self::Legacy(_) => "http://localhost:9088",
+        }
+    }
+
+    /// The url the hybrid janus api receives the events from, if not long-polling
+    pub fn events_url(&self) -> Option<&'static str> {
+        match self {
+            Self::Multistream(Transport::HybridWebSocket) => Some("ws://localhost:8188/ws"),
+            Self::Legacy(Transport::HybridWebSocket) => Some("ws://localhost:9188/ws"),
+            _ => None,
         }
     }
 
     pub fn api(&self) -> JanusAPI {
         match self {
-            Self::Multistream(api) | Self::Legacy(api) => *api,
+            Self::Multistream(transport) | Self::Legacy(transport) => match transport {
+                Transport::WebSocket => JanusAPI::WebSocket,
+                Transport::Restful => JanusAPI::Restful,
+                Transport::HybridWebSocket | Transport::HybridLongPoll => JanusAPI::Hybrid,
+            },
         }
     }
 
@@ -1,8 +1,8 @@
 #![allow(unused_labels)]
 
 use e2e::TestingEnv;
+use e2e::Transport;
 use jarust::core::jaconfig::JaConfig;
-use jarust::core::jaconfig::JanusAPI;
 use jarust::interface::tgenerator::RandomTransactionGenerator;
 use jarust::plugins::audio_bridge::common::AudioBridgeParticipant;
 use jarust::plugins::audio_bridge::events::AudioBridgeEvent;
@@ -24,16 +24,17 @@ use jarust::plugins::audio_bridge::params::AudioBridgeMuteParams;
 use jarust::plugins::audio_bridge::params::AudioBridgeMuteRoomParams;
 use jarust::plugins::common::U63;
 use jarust::plugins::JanusId;
+use jarust::rt::sync::mpsc::UnboundedReceiver;
 use rand::{thread_rng, Rng};
 use rstest::*;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc::UnboundedReceiver;
 
 #[rstest]
-#[case::multistream_ws(TestingEnv::Multistream(JanusAPI::WebSocket))]
-#[case::multistream_restful(TestingEnv::Multistream(JanusAPI::Restful))]
-#[case::legacy_ws(TestingEnv::Legacy(JanusAPI::WebSocket))]
-#[case::legacy_restful(TestingEnv::Legacy(JanusAPI::Restful))]
+#[case::multistream_ws(TestingEnv::Multistream(Transport::WebSocket))]
+#[case::multistream_restful(TestingEnv::Multistream(Transport::Restful))]
+#[case::legacy_ws(TestingEnv::Legacy(Transport::WebSocket))]
+#[case::legacy_restful(TestingEnv::Legacy(Transport::Restful))]
 #[tokio::test]
 async fn audiobridge_room_crud_e2e(#[case] testing_env: TestingEnv) {
     let default_timeout = Duration::from_secs(4);
@@ -170,10 +171,10 @@ async fn audiobridge_room_crud_e2e(#[case] testing_env: TestingEnv) {
 }
 
 #[rstest]
-#[case::multistream_ws(TestingEnv::Multistream(JanusAPI::WebSocket))]
-#[case::multistream_restful(TestingEnv::Multistream(JanusAPI::Restful))]
-#[case::legacy_ws(TestingEnv::Legacy(JanusAPI::WebSocket))]
-#[case::legacy_restful(TestingEnv::Legacy(JanusAPI::Restful))]
+#[case::multistream_ws(TestingEnv::Multistream(Transport::WebSocket))]
+#[case::multistream_restful(TestingEnv::Multistream(Transport::Restful))]
+#[case::legacy_ws(TestingEnv::Legacy(Transport::WebSocket))]
+#[case::legacy_restful(TestingEnv::Legacy(Transport::Restful))]
 #[tokio::test]
 async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
     let default_timeout = Duration::from_secs(4);
@@ -214,6 +215,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = alice_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -258,6 +260,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = bob_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -306,6 +309,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = eve_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -331,10 +335,12 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         let bob_joined = alice_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Alice failed to receive event");
         let eve_joined = alice_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Alice failed to receive event");
         assert_eq!(
             bob_joined,
@@ -354,6 +360,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         let eve_joined = bob_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Bob failed to receive event");
         assert_eq!(
             eve_joined,
@@ -381,6 +388,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = alice_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Alice failed to receive event")
         else {
             panic!("Alice received unexpected event")
@@ -401,6 +409,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = bob_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Bob failed to receive event")
         else {
             panic!("Bob received unexpected event")
@@ -421,6 +430,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = eve_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -452,6 +462,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = alice_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Alice failed to receive event")
         else {
             panic!("Alice received unexpected event")
@@ -472,6 +483,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = bob_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Bob failed to receive event")
         else {
             panic!("Bob received unexpected event")
@@ -492,6 +504,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = eve_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -528,6 +541,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = alice_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Alice failed to receive event")
         else {
             panic!("Alice received unexpected event")
@@ -548,6 +562,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = bob_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Bob failed to receive event")
         else {
             panic!("Bob received unexpected event")
@@ -566,6 +581,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             eve_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -587,6 +603,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             alice_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Alice failed to receive event")
         else {
             panic!("Alice received unexpected event")
@@ -598,6 +615,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             bob_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Bob failed to receive event")
         else {
             panic!("Bob received unexpected event")
@@ -609,6 +627,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             eve_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -630,6 +649,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             alice_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Alice failed to receive event")
         else {
             panic!("Alice received unexpected event")
@@ -641,6 +661,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             bob_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Bob failed to receive event")
         else {
             panic!("Bob received unexpected event")
@@ -652,6 +673,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             eve_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -695,6 +717,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             alice_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Alice failed to receive event")
         else {
             panic!("Alice received unexpected event")
@@ -706,6 +729,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             bob_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Bob failed to receive event")
         else {
             panic!("Bob received unexpected event")
@@ -717,6 +741,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             eve_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -738,6 +763,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         match bob_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Bob failed to receive event")
         {
             PluginEvent::AudioBridgeEvent(AudioBridgeEvent::RoomLeft { id, room }) => {
@@ -756,6 +782,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             alice_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Alice failed to receive event")
         else {
             panic!("Alice received unexpected event")
@@ -767,6 +794,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             eve_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -807,6 +835,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         let PluginEvent::AudioBridgeEvent(AudioBridgeEvent::RoomJoined { id, .. }) = bob_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Bob failed to receive event")
         else {
             panic!("Bob received unexpected event")
@@ -816,10 +845,12 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
         alice_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Alice failed to receive event");
         eve_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Eve failed to receive event");
 
         AudioBridgeParticipant {
@@ -856,6 +887,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             alice_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Alice failed to receive event")
         else {
             panic!("Alice received unexpected event")
@@ -867,6 +899,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
             eve_events
                 .recv()
                 .await
+                .map(Arc::unwrap_or_clone)
                 .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -902,6 +935,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
                 alice_events
                     .recv()
                     .await
+                    .map(Arc::unwrap_or_clone)
                     .expect("Alice failed to receive event")
             else {
                 panic!("Alice received unexpected event")
@@ -915,6 +949,7 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
                 eve_events
                     .recv()
                     .await
+                    .map(Arc::unwrap_or_clone)
                     .expect("Eve failed to receive event")
             else {
                 panic!("Eve received unexpected event")
@@ -928,12 +963,18 @@ async fn audiobridge_participants_e2e(#[case] testing_env: TestingEnv) {
 
 async fn make_audiobridge_attachment(
     testing_env: TestingEnv,
-) -> (AudioBridgeHandle, UnboundedReceiver<PluginEvent>) {
+) -> (AudioBridgeHandle, UnboundedReceiver<Arc<PluginEvent>>) {
     let config = JaConfig {
         url: testing_env.url().to_string(),
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: testing_env.events_url().map(String::from),
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection =
         jarust::core::connect(config, testing_env.api(), RandomTransactionGenerator)
@@ -1,9 +1,9 @@
 #![allow(unused_labels)]
 
 use e2e::TestingEnv;
+use e2e::Transport;
 use jarust::core::connect;
 use jarust::core::jaconfig::JaConfig;
-use jarust::core::jaconfig::JanusAPI;
 use jarust::core::prelude::Attach;
 use jarust::interface::error::Error::JanusError;
 use jarust::interface::japrotocol::GenericEvent;
@@ -14,10 +14,14 @@ use rstest::*;
 use std::time::Duration;
 
 #[rstest]
-#[case::multistream_ws(TestingEnv::Multistream(JanusAPI::WebSocket))]
-#[case::multistream_restful(TestingEnv::Multistream(JanusAPI::Restful))]
-#[case::legacy_ws(TestingEnv::Legacy(JanusAPI::WebSocket))]
-#[case::legacy_restful(TestingEnv::Legacy(JanusAPI::Restful))]
+#[case::multistream_ws(TestingEnv::Multistream(Transport::WebSocket))]
+#[case::multistream_restful(TestingEnv::Multistream(Transport::Restful))]
+#[case::legacy_ws(TestingEnv::Legacy(Transport::WebSocket))]
+#[case::legacy_restful(TestingEnv::Legacy(Transport::Restful))]
+#[case::multistream_hybrid_ws(TestingEnv::Multistream(Transport::HybridWebSocket))]
+#[case::multistream_hybrid_long_poll(TestingEnv::Multistream(Transport::HybridLongPoll))]
+#[case::legacy_hybrid_ws(TestingEnv::Legacy(Transport::HybridWebSocket))]
+#[case::legacy_hybrid_long_poll(TestingEnv::Legacy(Transport::HybridLongPoll))]
 #[tokio::test]
 async fn core_test(#[case] testing_env: TestingEnv) {
     let config = JaConfig {
@@ -25,6 +29,12 @@ async fn core_test(#[case] testing_env: TestingEnv) {
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: testing_env.events_url().map(String::from),
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection = connect(config, testing_env.api(), RandomTransactionGenerator)
         .await
@@ -1,6 +1,6 @@
 use e2e::TestingEnv;
+use e2e::Transport;
 use jarust::core::jaconfig::JaConfig;
-use jarust::core::jaconfig::JanusAPI;
 use jarust::interface::tgenerator::RandomTransactionGenerator;
 use jarust::plugins::echo_test::events::EchoTestEvent;
 use jarust::plugins::echo_test::events::PluginEvent;
@@ -10,10 +10,14 @@ use rstest::*;
 use std::time::Duration;
 
 #[rstest]
-#[case::multistream_ws(TestingEnv::Multistream(JanusAPI::WebSocket))]
-#[case::multistream_restful(TestingEnv::Multistream(JanusAPI::Restful))]
-#[case::legacy_ws(TestingEnv::Legacy(JanusAPI::WebSocket))]
-#[case::legacy_restful(TestingEnv::Legacy(JanusAPI::Restful))]
+#[case::multistream_ws(TestingEnv::Multistream(Transport::WebSocket))]
+#[case::multistream_restful(TestingEnv::Multistream(Transport::Restful))]
+#[case::legacy_ws(TestingEnv::Legacy(Transport::WebSocket))]
+#[case::legacy_restful(TestingEnv::Legacy(Transport::Restful))]
+#[case::multistream_hybrid_ws(TestingEnv::Multistream(Transport::HybridWebSocket))]
+#[case::multistream_hybrid_long_poll(TestingEnv::Multistream(Transport::HybridLongPoll))]
+#[case::legacy_hybrid_ws(TestingEnv::Legacy(Transport::HybridWebSocket))]
+#[case::legacy_hybrid_long_poll(TestingEnv::Legacy(Transport::HybridLongPoll))]
 #[tokio::test]
 async fn echotest_e2e(#[case] testing_env: TestingEnv) {
     let config = JaConfig {
@@ -21,6 +25,12 @@ async fn echotest_e2e(#[case] testing_env: TestingEnv) {
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: testing_env.events_url().map(String::from),
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection =
         jarust::core::connect(config, testing_env.api(), RandomTransactionGenerator)
@@ -44,8 +54,8 @@ async fn echotest_e2e(#[case] testing_env: TestingEnv) {
         .await
         .expect("Failed to send start message");
     assert_eq!(
-        event_receiver.recv().await,
-        Some(PluginEvent::EchoTestEvent(EchoTestEvent::Result {
+        event_receiver.recv().await.as_deref(),
+        Some(&PluginEvent::EchoTestEvent(EchoTestEvent::Result {
             echotest: "event".to_string(),
             result: "ok".to_string()
         }))
@@ -57,7 +67,7 @@ async fn echotest_e2e(#[case] testing_env: TestingEnv) {
         .await
         .expect("Failed to send start message");
     assert!(matches!(
-        event_receiver.recv().await,
+        event_receiver.recv().await.as_deref(),
         Some(PluginEvent::EchoTestEvent(EchoTestEvent::Error {
             error_code: _,
             error: _
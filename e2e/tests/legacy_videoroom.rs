@@ -1,8 +1,8 @@
 #![allow(unused_labels)]
 
 use e2e::TestingEnv;
+use e2e::Transport;
 use jarust::core::jaconfig::JaConfig;
-use jarust::core::jaconfig::JanusAPI;
 use jarust::interface::tgenerator::RandomTransactionGenerator;
 use jarust::plugins::common::U63;
 use jarust::plugins::legacy_video_room::events::PluginEvent;
@@ -11,14 +11,15 @@ use jarust::plugins::legacy_video_room::jahandle_ext::LegacyVideoRoom;
 use jarust::plugins::legacy_video_room::params::LegacyVideoRoomCreateParams;
 use jarust::plugins::legacy_video_room::params::LegacyVideoRoomExistsParams;
 use jarust::plugins::JanusId;
+use jarust::rt::sync::mpsc::UnboundedReceiver;
 use rand::{thread_rng, Rng};
 use rstest::*;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc::UnboundedReceiver;
 
 #[rstest]
-#[case::legacy_ws(TestingEnv::Legacy(JanusAPI::WebSocket))]
-#[case::legacy_restful(TestingEnv::Legacy(JanusAPI::Restful))]
+#[case::legacy_ws(TestingEnv::Legacy(Transport::WebSocket))]
+#[case::legacy_restful(TestingEnv::Legacy(Transport::Restful))]
 #[tokio::test]
 async fn legacy_videoroom_room_crud_e2e(#[case] testing_env: TestingEnv) {
     let default_timeout = Duration::from_secs(4);
@@ -66,12 +67,18 @@ async fn legacy_videoroom_room_crud_e2e(#[case] testing_env: TestingEnv) {
 
 async fn make_legacy_videoroom_attachment(
     testing_env: TestingEnv,
-) -> (LegacyVideoRoomHandle, UnboundedReceiver<PluginEvent>) {
+) -> (LegacyVideoRoomHandle, UnboundedReceiver<Arc<PluginEvent>>) {
     let config = JaConfig {
         url: testing_env.url().to_string(),
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: testing_env.events_url().map(String::from),
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection =
         jarust::core::connect(config, testing_env.api(), RandomTransactionGenerator)
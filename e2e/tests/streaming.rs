@@ -1,8 +1,8 @@
 #![allow(unused_labels)]
 
 use e2e::TestingEnv;
+use e2e::Transport;
 use jarust::core::jaconfig::JaConfig;
-use jarust::core::jaconfig::JanusAPI;
 use jarust::interface::tgenerator::RandomTransactionGenerator;
 use jarust::interface::Error;
 use jarust::plugins::common::U63;
@@ -11,14 +11,15 @@ use jarust::plugins::streaming::handle::StreamingHandle;
 use jarust::plugins::streaming::jahandle_ext::Streaming;
 use jarust::plugins::streaming::params::*;
 use jarust::plugins::JanusId;
+use jarust::rt::sync::mpsc::UnboundedReceiver;
 use rand::{thread_rng, Rng};
 use rstest::*;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc::UnboundedReceiver;
 
 #[rstest]
-#[case::multistream_ws(TestingEnv::Multistream(JanusAPI::WebSocket))]
-#[case::multistream_restful(TestingEnv::Multistream(JanusAPI::Restful))]
+#[case::multistream_ws(TestingEnv::Multistream(Transport::WebSocket))]
+#[case::multistream_restful(TestingEnv::Multistream(Transport::Restful))]
 #[tokio::test]
 async fn streaming_crud_e2e(#[case] testing_env: TestingEnv) {
     let default_timeout = Duration::from_secs(4);
@@ -111,12 +112,18 @@ async fn streaming_crud_e2e(#[case] testing_env: TestingEnv) {
 
 async fn make_streaming_attachment(
     testing_env: TestingEnv,
-) -> (StreamingHandle, UnboundedReceiver<PluginEvent>) {
+) -> (StreamingHandle, UnboundedReceiver<Arc<PluginEvent>>) {
     let config = JaConfig {
         url: testing_env.url().to_string(),
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: testing_env.events_url().map(String::from),
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection =
         jarust::core::connect(config, testing_env.api(), RandomTransactionGenerator)
@@ -1,8 +1,8 @@
 #![allow(unused_labels)]
 
 use e2e::TestingEnv;
+use e2e::Transport;
 use jarust::core::jaconfig::JaConfig;
-use jarust::core::jaconfig::JanusAPI;
 use jarust::interface::tgenerator::RandomTransactionGenerator;
 use jarust::plugins::common::U63;
 use jarust::plugins::video_room::events::PluginEvent;
@@ -19,14 +19,15 @@ use jarust::plugins::video_room::params::VideoRoomPublisherJoinParams;
 use jarust::plugins::video_room::params::VideoRoomPublisherJoinParamsOptional;
 use jarust::plugins::video_room::responses::VideoRoomParticipant;
 use jarust::plugins::JanusId;
+use jarust::rt::sync::mpsc::UnboundedReceiver;
 use rand::{thread_rng, Rng};
 use rstest::*;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc::UnboundedReceiver;
 
 #[rstest]
-#[case::multistream_ws(TestingEnv::Multistream(JanusAPI::WebSocket))]
-#[case::multistream_restful(TestingEnv::Multistream(JanusAPI::Restful))]
+#[case::multistream_ws(TestingEnv::Multistream(Transport::WebSocket))]
+#[case::multistream_restful(TestingEnv::Multistream(Transport::Restful))]
 #[tokio::test]
 async fn videoroom_room_crud_e2e(#[case] testing_env: TestingEnv) {
     let default_timeout = Duration::from_secs(4);
@@ -163,8 +164,8 @@ async fn videoroom_room_crud_e2e(#[case] testing_env: TestingEnv) {
 }
 
 #[rstest]
-#[case::multistream_ws(TestingEnv::Multistream(JanusAPI::WebSocket))]
-#[case::multistream_restful(TestingEnv::Multistream(JanusAPI::Restful))]
+#[case::multistream_ws(TestingEnv::Multistream(Transport::WebSocket))]
+#[case::multistream_restful(TestingEnv::Multistream(Transport::Restful))]
 #[tokio::test]
 async fn videoroom_participants_e2e(#[case] testing_env: TestingEnv) {
     let default_timeout = Duration::from_secs(4);
@@ -213,6 +214,7 @@ async fn videoroom_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = alice_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Alice failed to receive event")
         else {
             panic!("Alice received unexpected event")
@@ -262,6 +264,7 @@ async fn videoroom_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = bob_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Bob failed to receive event")
         else {
             panic!("Bob received unexpected event")
@@ -304,6 +307,7 @@ async fn videoroom_participants_e2e(#[case] testing_env: TestingEnv) {
         }) = eve_events
             .recv()
             .await
+            .map(Arc::unwrap_or_clone)
             .expect("Eve failed to receive event")
         else {
             panic!("Eve received unexpected event")
@@ -323,12 +327,18 @@ async fn videoroom_participants_e2e(#[case] testing_env: TestingEnv) {
 
 async fn make_videoroom_attachment(
     testing_env: TestingEnv,
-) -> (VideoRoomHandle, UnboundedReceiver<PluginEvent>) {
+) -> (VideoRoomHandle, UnboundedReceiver<Arc<PluginEvent>>) {
     let config = JaConfig {
         url: testing_env.url().to_string(),
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: testing_env.events_url().map(String::from),
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection =
         jarust::core::connect(config, testing_env.api(), RandomTransactionGenerator)
@@ -28,6 +28,12 @@ async fn main() -> anyhow::Result<()> {
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: None,
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection = connect(config, JanusAPI::WebSocket, RandomTransactionGenerator).await?;
     let session = connection
@@ -74,7 +80,9 @@ async fn main() -> anyhow::Result<()> {
 
     use jarust::plugins::audio_bridge::events::AudioBridgeEvent as ABE;
     use jarust::plugins::audio_bridge::events::PluginEvent as PE;
-    if let Some(PE::AudioBridgeEvent(ABE::RoomJoined { id, room, .. })) = events.recv().await {
+    if let Some(PE::AudioBridgeEvent(ABE::RoomJoined { id, room, .. })) =
+        events.recv().await.as_deref()
+    {
         handle
             .mute(AudioBridgeMuteParams {
                 id: id.clone(),
@@ -28,6 +28,12 @@ async fn main() -> anyhow::Result<()> {
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: None,
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection = connect(config, JanusAPI::WebSocket, RandomTransactionGenerator).await?;
     let timeout = Duration::from_secs(10);
@@ -47,6 +53,11 @@ async fn main() -> anyhow::Result<()> {
                 sdp: "".to_string(),
                 trickle: Some(false),
                 jsep_type: JsepType::Offer,
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             },
             std::time::Duration::from_secs(5),
         )
@@ -55,7 +66,7 @@ async fn main() -> anyhow::Result<()> {
     tracing::debug!("rsp: {rsp:#?}");
 
     while let Some(event) = event_receiver.recv().await {
-        match event {
+        match &*event {
             PluginEvent::EchoTestEvent(EchoTestEvent::Result { result, .. }) => {
                 tracing::info!("result: {result}");
             }
@@ -16,6 +16,12 @@ async fn main() -> anyhow::Result<()> {
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: None,
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let tgenerator = || uuid::Uuid::new_v4().to_string();
     let mut connection = connect(config, JanusAPI::WebSocket, tgenerator).await?;
@@ -24,6 +24,12 @@ async fn main() -> anyhow::Result<()> {
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: None,
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection = connect(config, JanusAPI::Restful, RandomTransactionGenerator).await?;
     let timeout = Duration::from_secs(10);
@@ -61,6 +67,11 @@ async fn main() -> anyhow::Result<()> {
                         sdp: "".to_string(),
                         trickle: Some(false),
                         jsep_type: JsepType::Offer,
+                        e2ee: None,
+                        simulcast: None,
+                        svc: None,
+                        update: None,
+                        extra: Default::default(),
                     },
                 )
                 .await
@@ -25,6 +25,12 @@ async fn main() -> anyhow::Result<()> {
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: None,
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection = connect(config, JanusAPI::WebSocket, RandomTransactionGenerator).await?;
     let timeout = Duration::from_secs(10);
@@ -69,6 +75,11 @@ async fn main() -> anyhow::Result<()> {
                         sdp: "".to_string(),
                         trickle: Some(false),
                         jsep_type: JsepType::Offer,
+                        e2ee: None,
+                        simulcast: None,
+                        svc: None,
+                        update: None,
+                        extra: Default::default(),
                     },
                 )
                 .await
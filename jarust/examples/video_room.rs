@@ -28,6 +28,12 @@ async fn main() -> anyhow::Result<()> {
         apisecret: None,
         server_root: "janus".to_string(),
         capacity: 32,
+        events_url: None,
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+        retry: None,
     };
     let mut connection = connect(config, JanusAPI::WebSocket, RandomTransactionGenerator).await?;
     let session = connection
@@ -162,6 +168,11 @@ async fn main() -> anyhow::Result<()> {
                 jsep_type: JsepType::Offer,
                 trickle: Some(false),
                 sdp: EXAMPLE_SDP_OFFER.to_string(),
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             },
             timeout,
         )
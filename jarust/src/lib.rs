@@ -1,3 +1,4 @@
 pub use jarust_core as core;
 pub use jarust_interface as interface;
 pub use jarust_plugins as plugins;
+pub use jarust_rt as rt;
@@ -6,6 +6,9 @@ mod tests {
     use crate::mocks::mock_generate_transaction::MockGenerateTransaction;
     use crate::mocks::mock_interface::MockInterface;
     use jarust::core::custom_connect;
+    use jarust::core::jakeepalive::SessionEvent;
+    use jarust::core::jastate::HandlePhase;
+    use jarust::core::prelude::Attach;
     use jarust::core::prelude::JaResponse;
     use jarust::interface::janus_interface::ConnectionParams;
     use jarust::interface::janus_interface::JanusInterface;
@@ -14,9 +17,116 @@ mod tests {
     use jarust::interface::japrotocol::JaSuccessProtocol;
     use jarust::interface::japrotocol::ResponseType;
     use jarust::interface::japrotocol::ServerInfoRsp;
+    use jarust::interface::layer::Call;
+    use jarust::interface::layer::LayerExt;
+    use jarust::interface::layer::Middleware;
+    use jarust::interface::layer::Reply;
+    use jarust::interface::layer::Retry;
+    use jarust::interface::layer::RetryPolicy;
+    use jarust::interface::Error;
+    use serde_json::json;
     use std::collections::HashMap;
+    use std::collections::HashSet;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::sync::Mutex;
     use std::time::Duration;
 
+    fn server_info() -> ServerInfoRsp {
+        ServerInfoRsp {
+            name: "Mock server name".to_string(),
+            version: 0,
+            version_string: "0.1.0".to_string(),
+            author: "John Doe".to_string(),
+            commit_hash: "abc123".to_string(),
+            compile_time: "2021-01-01".to_string(),
+            log_to_stdout: true,
+            log_to_file: true,
+            data_channels: true,
+            accepting_new_sessions: true,
+            session_timeout: 90,
+            reclaim_session_timeout: 60,
+            candidates_timeout: 60,
+            server_name: "Mock server".to_string(),
+            local_ip: "127.0.0.1".to_string(),
+            ipv6: true,
+            ice_lite: true,
+            ice_tcp: true,
+            ice_nomination: "".to_string(),
+            ice_keepalive_conncheck: true,
+            full_trickle: true,
+            mdns_enabled: true,
+            min_nack_queue: 10,
+            twcc_period: 60,
+            dtls_mtu: 1300,
+            static_event_loops: 10,
+            api_secret: false,
+            auth_token: false,
+            event_handlers: true,
+            opaqueid_in_api: true,
+            dependencies: HashMap::new(),
+            transports: HashMap::new(),
+            plugins: HashMap::new(),
+        }
+    }
+
+    /// Records the name of every call.
+    #[derive(Debug, Default)]
+    struct Recorder {
+        calls: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Middleware for Recorder {
+        async fn call(&self, call: Call, inner: &dyn JanusInterface) -> Result<Reply, Error> {
+            self.calls.lock().unwrap().push(call.name());
+            call.dispatch(inner).await
+        }
+    }
+
+    /// Fails the server info requests without reaching the interface.
+    #[derive(Debug)]
+    struct Unavailable;
+
+    #[async_trait::async_trait]
+    impl Middleware for Unavailable {
+        async fn call(&self, call: Call, inner: &dyn JanusInterface) -> Result<Reply, Error> {
+            match call {
+                Call::ServerInfo { .. } => Err(Error::JanusError {
+                    code: 503,
+                    reason: "Unavailable".to_string(),
+                }),
+                call => call.dispatch(inner).await,
+            }
+        }
+    }
+
+    /// Fails the first `failures` calls with a transport specific janus error.
+    #[derive(Debug)]
+    struct Flaky {
+        failures: Arc<AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl Middleware for Flaky {
+        /// Fails once the request was sent, as a transport error while waiting on the reply.
+        async fn call(&self, call: Call, inner: &dyn JanusInterface) -> Result<Reply, Error> {
+            let reply = call.dispatch(inner).await;
+            if self
+                .failures
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(Error::JanusError {
+                    code: 490,
+                    reason: "Transport error".to_string(),
+                });
+            }
+            reply
+        }
+    }
+
     #[tokio::test]
     async fn it_successfully_connects() {
         let conn_params = ConnectionParams {
@@ -24,6 +134,11 @@ mod tests {
             capacity: 10,
             apisecret: None,
             server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
         };
         let transaction_generator = MockGenerateTransaction::new();
         let interface = MockInterface::make_interface(conn_params, transaction_generator)
@@ -40,6 +155,11 @@ mod tests {
             capacity: 10,
             apisecret: None,
             server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
         };
         let transaction_generator = MockGenerateTransaction::new();
         let interface = MockInterface::make_interface(conn_params, transaction_generator)
@@ -71,6 +191,11 @@ mod tests {
             capacity: 10,
             apisecret: None,
             server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
         };
         let transaction_generator = MockGenerateTransaction::new();
         let interface = MockInterface::make_interface(conn_params, transaction_generator)
@@ -78,43 +203,7 @@ mod tests {
             .unwrap();
         let connection = custom_connect(interface.clone()).await.unwrap();
 
-        let server_info = ServerInfoRsp {
-            name: "Mock server name".to_string(),
-            version: 0,
-            version_string: "0.1.0".to_string(),
-            author: "John Doe".to_string(),
-            commit_hash: "abc123".to_string(),
-            compile_time: "2021-01-01".to_string(),
-            log_to_stdout: true,
-            log_to_file: true,
-            data_channels: true,
-            accepting_new_sessions: true,
-            session_timeout: 90,
-            reclaim_session_timeout: 60,
-            candidates_timeout: 60,
-            server_name: "Mock server".to_string(),
-            local_ip: "127.0.0.1".to_string(),
-            ipv6: true,
-            ice_lite: true,
-            ice_tcp: true,
-            ice_nomination: "".to_string(),
-            ice_keepalive_conncheck: true,
-            full_trickle: true,
-            mdns_enabled: true,
-            min_nack_queue: 10,
-            twcc_period: 60,
-            dtls_mtu: 1300,
-            static_event_loops: 10,
-            api_secret: false,
-            auth_token: false,
-            event_handlers: true,
-            opaqueid_in_api: true,
-            dependencies: HashMap::new(),
-            transports: HashMap::new(),
-            plugins: HashMap::new(),
-        };
-
-        interface.mocker_server_info_rsp(server_info).await;
+        interface.mocker_server_info_rsp(server_info()).await;
 
         let rsp = connection.server_info(Duration::from_secs(5)).await;
 
@@ -128,6 +217,11 @@ mod tests {
             capacity: 10,
             apisecret: None,
             server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
         };
         let transaction_generator = MockGenerateTransaction::new();
         let interface = MockInterface::make_interface(conn_params, transaction_generator)
@@ -157,4 +251,374 @@ mod tests {
             jarust::interface::Error::JanusError { .. }
         ))
     }
+
+    #[tokio::test]
+    async fn it_drains_session_keep_alives_on_shutdown() {
+        let conn_params = ConnectionParams {
+            url: "mock://some.janus.com".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let transaction_generator = MockGenerateTransaction::new();
+        let interface = MockInterface::make_interface(conn_params, transaction_generator)
+            .await
+            .unwrap();
+        let mut connection = custom_connect(interface.clone()).await.unwrap();
+
+        let response = JaResponse {
+            janus: ResponseType::Success(JaSuccessProtocol::Data {
+                data: JaData { id: 73 },
+            }),
+            transaction: Some("abc123".to_string()),
+            session_id: None,
+            sender: None,
+            jsep: None,
+        };
+        interface.mock_create_rsp(response).await;
+        let _session = connection
+            .create_session(10, Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let shutdown = connection.shutdown(Duration::from_secs(1)).await;
+
+        assert!(shutdown.is_drained());
+    }
+
+    #[tokio::test]
+    async fn it_keeps_sessions_alive_and_reports_dead_ones() {
+        let conn_params = ConnectionParams {
+            url: "mock://some.janus.com".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let transaction_generator = MockGenerateTransaction::new();
+        let interface = MockInterface::make_interface(conn_params, transaction_generator)
+            .await
+            .unwrap();
+        let mut connection = custom_connect(interface.clone()).await.unwrap();
+        let mut session_events = connection.session_events();
+
+        let mut sessions = vec![];
+        for session_id in [73, 74] {
+            let response = JaResponse {
+                janus: ResponseType::Success(JaSuccessProtocol::Data {
+                    data: JaData { id: session_id },
+                }),
+                transaction: Some("abc123".to_string()),
+                session_id: None,
+                sender: None,
+                jsep: None,
+            };
+            interface.mock_create_rsp(response).await;
+            let session = connection
+                .create_session(1, Duration::from_secs(10))
+                .await
+                .unwrap();
+            sessions.push(session);
+        }
+        interface.expire_session(74).await;
+
+        let event = tokio::time::timeout(Duration::from_secs(5), session_events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        assert!(matches!(*event, SessionEvent::Dead { session_id: 74, .. }));
+        let keep_alives = interface.keep_alives().await;
+        assert_eq!(keep_alives.iter().filter(|id| **id == 74).count(), 1);
+        assert!(keep_alives.iter().filter(|id| **id == 73).count() >= 2);
+    }
+
+    #[tokio::test]
+    async fn it_keeps_sessions_alive_while_another_keep_alive_stalls() {
+        let conn_params = ConnectionParams {
+            url: "mock://some.janus.com".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let transaction_generator = MockGenerateTransaction::new();
+        let interface = MockInterface::make_interface(conn_params, transaction_generator)
+            .await
+            .unwrap();
+        let mut connection = custom_connect(interface.clone()).await.unwrap();
+
+        let mut sessions = vec![];
+        for session_id in [73, 74] {
+            let response = JaResponse {
+                janus: ResponseType::Success(JaSuccessProtocol::Data {
+                    data: JaData { id: session_id },
+                }),
+                transaction: Some("abc123".to_string()),
+                session_id: None,
+                sender: None,
+                jsep: None,
+            };
+            interface.mock_create_rsp(response).await;
+            let session = connection
+                .create_session(1, Duration::from_secs(10))
+                .await
+                .unwrap();
+            sessions.push(session);
+        }
+        interface.stall_session(74).await;
+
+        tokio::time::sleep(Duration::from_millis(3500)).await;
+
+        // Every ~1s, not held back by the stalled keep-alives of the other session
+        let keep_alives = interface.keep_alives().await;
+        assert!(keep_alives.iter().filter(|id| **id == 73).count() >= 3);
+    }
+
+    #[tokio::test]
+    async fn it_detaches_handles_and_destroys_sessions_on_close() {
+        let conn_params = ConnectionParams {
+            url: "mock://some.janus.com".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let transaction_generator = MockGenerateTransaction::new();
+        let interface = MockInterface::make_interface(conn_params, transaction_generator)
+            .await
+            .unwrap();
+        let mut connection = custom_connect(interface.clone()).await.unwrap();
+
+        let response = JaResponse {
+            janus: ResponseType::Success(JaSuccessProtocol::Data {
+                data: JaData { id: 73 },
+            }),
+            transaction: Some("abc123".to_string()),
+            session_id: None,
+            sender: None,
+            jsep: None,
+        };
+        interface.mock_create_rsp(response).await;
+        let session = connection
+            .create_session(10, Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let mut handles = vec![];
+        for handle_id in [3, 4] {
+            let response = JaResponse {
+                janus: ResponseType::Success(JaSuccessProtocol::Data {
+                    data: JaData { id: handle_id },
+                }),
+                transaction: Some("abc123".to_string()),
+                session_id: Some(73),
+                sender: None,
+                jsep: None,
+            };
+            interface.mock_attach_rsp(response).await;
+            let (handle, _) = session
+                .attach("mock.plugin.test".to_string(), Duration::from_secs(5))
+                .await
+                .unwrap();
+            if handle_id == 4 {
+                handle.detach().await.unwrap();
+            }
+            handles.push(handle);
+        }
+
+        connection.close(Duration::from_secs(1)).await.unwrap();
+
+        for handle in &handles {
+            assert_eq!(handle.state().phase, HandlePhase::Detached);
+        }
+
+        let detach = json!({"janus": "detach"});
+        assert_eq!(
+            interface.handle_requests().await,
+            vec![(73, 4, detach.clone()), (73, 3, detach)]
+        );
+        assert_eq!(interface.destroyed_sessions().await, vec![73]);
+        assert!(interface.is_closed().await);
+    }
+
+    #[tokio::test]
+    async fn it_closes_within_the_timeout_whatever_the_number_of_sessions() {
+        let conn_params = ConnectionParams {
+            url: "mock://some.janus.com".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let transaction_generator = MockGenerateTransaction::new();
+        let interface = MockInterface::make_interface(conn_params, transaction_generator)
+            .await
+            .unwrap();
+        let mut connection = custom_connect(interface.clone()).await.unwrap();
+
+        let mut sessions = vec![];
+        for session_id in [73, 74, 75] {
+            let response = JaResponse {
+                janus: ResponseType::Success(JaSuccessProtocol::Data {
+                    data: JaData { id: session_id },
+                }),
+                transaction: Some("abc123".to_string()),
+                session_id: None,
+                sender: None,
+                jsep: None,
+            };
+            interface.mock_create_rsp(response).await;
+            let session = connection
+                .create_session(10, Duration::from_secs(10))
+                .await
+                .unwrap();
+            interface.stall_session(session_id).await;
+            sessions.push(session);
+        }
+
+        let started = tokio::time::Instant::now();
+        let result = connection.close(Duration::from_secs(1)).await;
+
+        assert!(started.elapsed() < Duration::from_millis(1500));
+        assert!(matches!(result, Err(Error::RequestTimeout)));
+        assert!(interface.is_closed().await);
+    }
+
+    #[tokio::test]
+    async fn it_runs_calls_through_the_stacked_layers() {
+        let conn_params = ConnectionParams {
+            url: "mock://some.janus.com".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let transaction_generator = MockGenerateTransaction::new();
+        let interface = MockInterface::make_interface(conn_params, transaction_generator)
+            .await
+            .unwrap();
+        let recorder = Recorder::default();
+        let calls = recorder.calls.clone();
+        let layered = interface.clone().layer(Unavailable).layer(recorder);
+        let mut connection = custom_connect(layered).await.unwrap();
+
+        let response = JaResponse {
+            janus: ResponseType::Success(JaSuccessProtocol::Data {
+                data: JaData { id: 73 },
+            }),
+            transaction: Some("abc123".to_string()),
+            session_id: None,
+            sender: None,
+            jsep: None,
+        };
+        interface.mock_create_rsp(response).await;
+        let session = connection
+            .create_session(10, Duration::from_secs(10))
+            .await
+            .unwrap();
+        let server_info = connection.server_info(Duration::from_secs(5)).await;
+        session.destroy(Duration::from_secs(5)).await.unwrap();
+
+        assert!(matches!(
+            server_info,
+            Err(Error::JanusError { code: 503, .. })
+        ));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["create", "server_info", "destroy"]
+        );
+        assert_eq!(interface.destroyed_sessions().await, vec![73]);
+    }
+
+    #[tokio::test]
+    async fn it_retries_idempotent_calls_failing_with_transient_errors() {
+        let conn_params = ConnectionParams {
+            url: "mock://some.janus.com".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let transaction_generator = MockGenerateTransaction::new();
+        let interface = MockInterface::make_interface(conn_params, transaction_generator)
+            .await
+            .unwrap();
+        let recorder = Recorder::default();
+        let calls = recorder.calls.clone();
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let failures = Arc::new(AtomicU32::new(2));
+        let flaky = Flaky {
+            failures: failures.clone(),
+        };
+        let layered = interface
+            .clone()
+            .layer(flaky)
+            .layer(recorder)
+            .layer(Retry::new(policy));
+        let mut connection = custom_connect(layered).await.unwrap();
+        interface.mocker_server_info_rsp(server_info()).await;
+
+        // Succeeds on the third and last attempt
+        let info = connection.server_info(Duration::from_secs(5)).await;
+        assert!(info.is_ok());
+        assert_eq!(*calls.lock().unwrap(), vec!["server_info"; 3]);
+        let transactions = interface.server_info_transactions().await;
+        let unique = transactions.iter().collect::<HashSet<_>>();
+        assert_eq!(
+            unique.len(),
+            3,
+            "Every attempt is a new request: {transactions:?}"
+        );
+
+        // Sessions are never created twice
+        calls.lock().unwrap().clear();
+        failures.store(1, Ordering::Relaxed);
+        let response = JaResponse {
+            janus: ResponseType::Success(JaSuccessProtocol::Data {
+                data: JaData { id: 73 },
+            }),
+            transaction: Some("abc123".to_string()),
+            session_id: None,
+            sender: None,
+            jsep: None,
+        };
+        interface.mock_create_rsp(response).await;
+        let flaky = connection.create_session(10, Duration::from_secs(5)).await;
+        assert!(matches!(flaky, Err(Error::JanusError { code: 490, .. })));
+        assert_eq!(*calls.lock().unwrap(), vec!["create"]);
+    }
 }
@@ -6,15 +6,29 @@ mod tests {
     use crate::mocks::mock_generate_transaction::MockGenerateTransaction;
     use crate::mocks::mock_interface::MockInterface;
     use jarust::core::custom_connect;
+    use jarust::core::jastate::HandlePhase;
+    use jarust::core::jastate::HandleState;
     use jarust::core::prelude::Attach;
     use jarust::core::prelude::JaResponse;
+    use jarust::core::prelude::PluginSubscribe;
     use jarust::interface::janus_interface::ConnectionParams;
     use jarust::interface::janus_interface::JanusInterface;
+    use jarust::interface::japrotocol::Candidate;
     use jarust::interface::japrotocol::GenericEvent;
     use jarust::interface::japrotocol::JaData;
     use jarust::interface::japrotocol::JaHandleEvent;
     use jarust::interface::japrotocol::JaSuccessProtocol;
+    use jarust::interface::japrotocol::Jsep;
+    use jarust::interface::japrotocol::JsepType;
+    use jarust::interface::japrotocol::PluginData;
+    use jarust::interface::japrotocol::PluginInnerData;
     use jarust::interface::japrotocol::ResponseType;
+    use jarust::interface::japrotocol::TrickleCandidate;
+    use jarust::plugins::echo_test::events::EchoTestEvent;
+    use jarust::plugins::echo_test::events::PluginEvent;
+    use jarust::plugins::echo_test::jahandle_ext::EchoTest;
+    use serde_json::json;
+    use std::sync::Arc;
     use std::time::Duration;
 
     #[tokio::test]
@@ -24,6 +38,11 @@ mod tests {
             capacity: 10,
             apisecret: None,
             server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
         };
         let transaction_generator = MockGenerateTransaction::new();
         let interface = MockInterface::make_interface(conn_params, transaction_generator)
@@ -84,4 +103,428 @@ mod tests {
             ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Detached))
         );
     }
+
+    #[tokio::test]
+    async fn it_streams_trickled_candidates() {
+        let (interface, session_id, handle, mut stream) = attach_mock_handle().await;
+        let mut candidates = handle.ice_candidates().await;
+
+        let candidate = Candidate {
+            sdp_mid: "0".to_string(),
+            sdp_mline_index: 0,
+            candidate: "candidate:1 1 udp 2015363327 10.0.0.1 45124 typ host".to_string(),
+            username_fragment: Some("abcd".to_string()),
+        };
+        for candidate in [
+            TrickleCandidate::Candidate(candidate.clone()),
+            TrickleCandidate::Completed { completed: true },
+        ] {
+            interface
+                .mock_event(
+                    handle.id(),
+                    JaResponse {
+                        janus: ResponseType::Event(JaHandleEvent::GenericEvent(
+                            GenericEvent::Trickle { candidate },
+                        )),
+                        transaction: None,
+                        session_id: Some(session_id),
+                        sender: Some(handle.id()),
+                        jsep: None,
+                    },
+                )
+                .await;
+        }
+
+        assert_eq!(
+            candidates.recv().await.unwrap(),
+            TrickleCandidate::Candidate(candidate)
+        );
+        assert_eq!(
+            candidates.recv().await.unwrap(),
+            TrickleCandidate::Completed { completed: true }
+        );
+        // The events are still delivered to the handle stream
+        assert!(matches!(
+            stream.recv().await.unwrap().janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Trickle { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_batches_outgoing_candidates() {
+        let (interface, session_id, handle, _stream) = attach_mock_handle().await;
+        let trickle = handle.trickle_sender(Duration::from_millis(50), Duration::from_secs(5));
+
+        let candidate = |index: u32| Candidate {
+            sdp_mid: index.to_string(),
+            sdp_mline_index: index,
+            candidate: format!("candidate:{index} 1 udp 2015363327 10.0.0.1 45124 typ host"),
+            username_fragment: None,
+        };
+        trickle.send(candidate(0)).unwrap();
+        trickle.send(candidate(1)).unwrap();
+        trickle.complete().unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let requests = interface.handle_requests().await;
+        assert_eq!(
+            requests,
+            vec![
+                (
+                    session_id,
+                    handle.id(),
+                    json!({
+                        "janus": "trickle",
+                        "candidates": [candidate(0), candidate(1)]
+                    })
+                ),
+                (
+                    session_id,
+                    handle.id(),
+                    json!({
+                        "janus": "trickle",
+                        "candidate": { "completed": true }
+                    })
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_resolves_the_event_carrying_the_request_transaction() {
+        let (interface, session_id, handle, mut stream) = attach_mock_handle().await;
+        let event = JaResponse {
+            janus: ResponseType::Event(JaHandleEvent::PluginEvent {
+                plugin_data: PluginData {
+                    plugin: "mock.plugin.test".to_string(),
+                    data: PluginInnerData::Data(json!({ "result": "ok" })),
+                },
+            }),
+            transaction: Some("mock-msg-transaction".to_string()),
+            session_id: Some(session_id),
+            sender: Some(handle.id()),
+            jsep: None,
+        };
+        interface.mock_msg_event(event.clone()).await;
+
+        let response = handle
+            .send_waiton_event(json!({ "request": "start" }), Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(response, event);
+
+        // The event resolved the request instead of reaching the stream
+        interface
+            .mock_event(
+                handle.id(),
+                JaResponse {
+                    janus: ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::WebrtcUp)),
+                    transaction: None,
+                    session_id: Some(session_id),
+                    sender: Some(handle.id()),
+                    jsep: None,
+                },
+            )
+            .await;
+        assert_eq!(
+            stream.recv().await.unwrap().janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::WebrtcUp))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_keeps_delivering_events_while_waiting_on_an_ack() {
+        let (interface, session_id, handle, mut stream) = attach_mock_handle().await;
+        let event = JaResponse {
+            janus: ResponseType::Event(JaHandleEvent::PluginEvent {
+                plugin_data: PluginData {
+                    plugin: "mock.plugin.test".to_string(),
+                    data: PluginInnerData::Data(json!({ "result": "ok" })),
+                },
+            }),
+            transaction: Some("mock-msg-transaction".to_string()),
+            session_id: Some(session_id),
+            sender: Some(handle.id()),
+            jsep: None,
+        };
+        interface.mock_msg_event(event.clone()).await;
+        interface.mock_ack_delay(Duration::from_millis(500)).await;
+
+        let request = tokio::spawn({
+            let handle = handle.clone();
+            async move {
+                handle
+                    .send_waiton_event(json!({ "request": "start" }), Duration::from_secs(1))
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        interface
+            .mock_event(
+                handle.id(),
+                JaResponse {
+                    janus: ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::WebrtcUp)),
+                    transaction: None,
+                    session_id: Some(session_id),
+                    sender: Some(handle.id()),
+                    jsep: None,
+                },
+            )
+            .await;
+
+        // The events behind the held one don't wait on the ack
+        let next = tokio::time::timeout(Duration::from_millis(200), stream.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            next.janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::WebrtcUp))
+        );
+        // The event received before its ack still resolves the request
+        assert_eq!(request.await.unwrap().unwrap(), event);
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_plugin_error_answering_the_request() {
+        let (interface, session_id, handle, _stream) = attach_mock_handle().await;
+        interface
+            .mock_msg_event(JaResponse {
+                janus: ResponseType::Event(JaHandleEvent::PluginEvent {
+                    plugin_data: PluginData {
+                        plugin: "mock.plugin.test".to_string(),
+                        data: PluginInnerData::Error {
+                            error_code: 426,
+                            error: "No such room".to_string(),
+                        },
+                    },
+                }),
+                transaction: Some("mock-msg-transaction".to_string()),
+                session_id: Some(session_id),
+                sender: Some(handle.id()),
+                jsep: None,
+            })
+            .await;
+
+        let result = handle
+            .send_waiton_event(json!({ "request": "join" }), Duration::from_secs(1))
+            .await;
+        assert!(matches!(
+            result,
+            Err(jarust::interface::Error::PluginResponseError {
+                error_code: 426,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_fans_plugin_events_out_to_subscribers() {
+        let (interface, session_id, session) = mock_session().await;
+        let (handle, mut receiver) = session
+            .attach_echo_test(Duration::from_secs(5))
+            .await
+            .unwrap();
+        let mut all = handle.subscribe();
+        let mut results = handle.subscribe_filtered(|event| {
+            matches!(
+                event,
+                PluginEvent::EchoTestEvent(EchoTestEvent::Result { .. })
+            )
+        });
+
+        let events = [
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::WebrtcUp)),
+            ResponseType::Event(JaHandleEvent::PluginEvent {
+                plugin_data: PluginData {
+                    plugin: "janus.plugin.echotest".to_string(),
+                    data: PluginInnerData::Data(json!({ "echotest": "event", "result": "ok" })),
+                },
+            }),
+        ];
+        for janus in events {
+            interface
+                .mock_event(
+                    handle.id(),
+                    JaResponse {
+                        janus,
+                        transaction: None,
+                        session_id: Some(session_id),
+                        sender: Some(handle.id()),
+                        jsep: None,
+                    },
+                )
+                .await;
+        }
+
+        let webrtc_up = PluginEvent::GenericEvent(GenericEvent::WebrtcUp);
+        let result = PluginEvent::EchoTestEvent(EchoTestEvent::Result {
+            echotest: "event".to_string(),
+            result: "ok".to_string(),
+        });
+        assert_eq!(*receiver.recv().await.unwrap(), webrtc_up);
+        let shared = receiver.recv().await.unwrap();
+        assert_eq!(*shared, result);
+        assert_eq!(*all.recv().await.unwrap(), webrtc_up);
+        // The receiver and the subscribers share the same event instead of a copy each
+        assert!(Arc::ptr_eq(&shared, &all.recv().await.unwrap()));
+        assert!(Arc::ptr_eq(&shared, &results.recv().await.unwrap()));
+
+        drop(all);
+        drop(results);
+        interface
+            .mock_event(
+                handle.id(),
+                JaResponse {
+                    janus: ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Detached)),
+                    transaction: None,
+                    session_id: Some(session_id),
+                    sender: Some(handle.id()),
+                    jsep: None,
+                },
+            )
+            .await;
+        receiver.recv().await.unwrap();
+        assert!(handle.subscribers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_tracks_the_handle_lifecycle() {
+        let (interface, session_id, handle, _stream) = attach_mock_handle().await;
+        assert_eq!(handle.state(), HandleState::default());
+        let mut state = handle.watch_state();
+
+        let event = |janus, jsep| JaResponse {
+            janus,
+            transaction: None,
+            session_id: Some(session_id),
+            sender: Some(handle.id()),
+            jsep,
+        };
+        let answer = Jsep {
+            jsep_type: JsepType::Answer,
+            sdp: "v=0".to_string(),
+            trickle: None,
+            e2ee: None,
+            simulcast: None,
+            svc: None,
+            update: None,
+            extra: Default::default(),
+        };
+        let events = [
+            event(
+                ResponseType::Event(JaHandleEvent::PluginEvent {
+                    plugin_data: PluginData {
+                        plugin: "mock.plugin.test".to_string(),
+                        data: PluginInnerData::Data(json!({ "result": "ok" })),
+                    },
+                }),
+                Some(answer),
+            ),
+            event(
+                ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::WebrtcUp)),
+                None,
+            ),
+            event(
+                ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Media {
+                    mid: Some("0".to_string()),
+                    media: "video".to_string(),
+                    receiving: true,
+                    seconds: None,
+                })),
+                None,
+            ),
+        ];
+        for event in events {
+            interface.mock_event(handle.id(), event).await;
+        }
+
+        let live = handle.wait_for_state(HandleState::is_live).await.unwrap();
+        assert_eq!(live.phase, HandlePhase::WebrtcUp);
+        assert!(live.is_receiving("0"));
+        assert!(state.has_changed());
+
+        interface
+            .mock_event(
+                handle.id(),
+                event(
+                    ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Hangup {
+                        reason: Some("Close PC".to_string()),
+                    })),
+                    None,
+                ),
+            )
+            .await;
+        let hung_up = state
+            .wait_for(|state| state.phase == HandlePhase::HungUp)
+            .await
+            .unwrap();
+        assert!(hung_up.receiving.is_empty());
+
+        handle.detach().await.unwrap();
+        assert_eq!(handle.state().phase, HandlePhase::Detached);
+    }
+
+    async fn attach_mock_handle() -> (
+        MockInterface,
+        u64,
+        jarust::core::jahandle::JaHandle,
+        jarust::rt::sync::mpsc::UnboundedReceiver<JaResponse>,
+    ) {
+        let (interface, session_id, session) = mock_session().await;
+        let (handle, stream) = session
+            .attach("mock.plugin.test".to_string(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        (interface, session_id, handle, stream)
+    }
+
+    async fn mock_session() -> (MockInterface, u64, jarust::core::jasession::JaSession) {
+        let conn_params = ConnectionParams {
+            url: "mock://some.janus.com".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let transaction_generator = MockGenerateTransaction::new();
+        let interface = MockInterface::make_interface(conn_params, transaction_generator)
+            .await
+            .unwrap();
+        let mut connection = custom_connect(interface.clone()).await.unwrap();
+
+        let session_id = 73;
+        interface
+            .mock_create_rsp(JaResponse {
+                janus: ResponseType::Success(JaSuccessProtocol::Data {
+                    data: JaData { id: session_id },
+                }),
+                transaction: Some("abc123".to_string()),
+                session_id: None,
+                sender: None,
+                jsep: None,
+            })
+            .await;
+        let session = connection
+            .create_session(10, Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        interface
+            .mock_attach_rsp(JaResponse {
+                janus: ResponseType::Success(JaSuccessProtocol::Data {
+                    data: JaData { id: 77 },
+                }),
+                transaction: Some("mock-attach-plugin-transaction".to_string()),
+                session_id: Some(session_id),
+                sender: None,
+                jsep: None,
+            })
+            .await;
+        (interface, session_id, session)
+    }
 }
@@ -9,13 +9,15 @@ use jarust::interface::janus_interface::JanusInterface;
 use jarust::interface::japrotocol::JaSuccessProtocol;
 use jarust::interface::japrotocol::ResponseType;
 use jarust::interface::japrotocol::ServerInfoRsp;
+use jarust::rt::sync::mpsc;
+use jarust::rt::sync::mpsc::UnboundedSender;
+use jarust::rt::sync::Mutex;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::sync::mpsc::UnboundedSender;
-use tokio::sync::Mutex;
 
 #[derive(Debug, Default)]
 pub struct Exclusive {
@@ -23,6 +25,22 @@ pub struct Exclusive {
     attach_rsp: Option<JaResponse>,
     server_info_rsp: Option<ServerInfoRsp>,
     handles_rx: HashMap<u64, UnboundedSender<JaResponse>>,
+    destroyed_sessions: Vec<u64>,
+    /// The session ids of the keep-alives, in order.
+    keep_alives: Vec<u64>,
+    /// The sessions janus doesn't know anymore.
+    expired_sessions: HashSet<u64>,
+    /// The sessions whose keep-alives and destroy requests are never answered.
+    stalled_sessions: HashSet<u64>,
+    /// `(session_id, handle_id, body)` of the top-level handle requests.
+    handle_requests: Vec<(u64, u64, Value)>,
+    /// The event janus answers plugin messages with, delivered before their ack.
+    msg_event: Option<JaResponse>,
+    /// How long janus takes to ack plugin messages, after their event.
+    ack_delay: Option<Duration>,
+    /// The transactions of the server info requests, one per request.
+    server_info_transactions: Vec<String>,
+    closed: bool,
 }
 
 #[derive(Debug, Default)]
@@ -49,6 +67,57 @@ impl MockInterface {
         self.inner.exclusive.lock().await.server_info_rsp = Some(rsp);
     }
 
+    pub async fn destroyed_sessions(&self) -> Vec<u64> {
+        self.inner.exclusive.lock().await.destroyed_sessions.clone()
+    }
+
+    pub async fn server_info_transactions(&self) -> Vec<String> {
+        self.inner
+            .exclusive
+            .lock()
+            .await
+            .server_info_transactions
+            .clone()
+    }
+
+    pub async fn keep_alives(&self) -> Vec<u64> {
+        self.inner.exclusive.lock().await.keep_alives.clone()
+    }
+
+    pub async fn expire_session(&self, session_id: u64) {
+        self.inner
+            .exclusive
+            .lock()
+            .await
+            .expired_sessions
+            .insert(session_id);
+    }
+
+    pub async fn stall_session(&self, session_id: u64) {
+        self.inner
+            .exclusive
+            .lock()
+            .await
+            .stalled_sessions
+            .insert(session_id);
+    }
+
+    pub async fn handle_requests(&self) -> Vec<(u64, u64, Value)> {
+        self.inner.exclusive.lock().await.handle_requests.clone()
+    }
+
+    pub async fn is_closed(&self) -> bool {
+        self.inner.exclusive.lock().await.closed
+    }
+
+    pub async fn mock_msg_event(&self, rsp: JaResponse) {
+        self.inner.exclusive.lock().await.msg_event = Some(rsp);
+    }
+
+    pub async fn mock_ack_delay(&self, delay: Duration) {
+        self.inner.exclusive.lock().await.ack_delay = Some(delay);
+    }
+
     pub async fn mock_event(&self, handle_id: u64, rsp: JaResponse) {
         if let Some(tx) = self.inner.exclusive.lock().await.handles_rx.get(&handle_id) {
             tx.send(rsp).unwrap();
@@ -98,9 +167,13 @@ impl JanusInterface for MockInterface {
         &self,
         _timeout: Duration,
     ) -> Result<ServerInfoRsp, jarust::interface::Error> {
-        let Some(rsp) = self.inner.exclusive.lock().await.server_info_rsp.clone() else {
+        let mut exclusive = self.inner.exclusive.lock().await;
+        let Some(rsp) = exclusive.server_info_rsp.clone() else {
             panic!("Server info response is not set");
         };
+        // Every request gets a fresh transaction, as the real interfaces do
+        let transaction = format!("server-info-{}", exclusive.server_info_transactions.len());
+        exclusive.server_info_transactions.push(transaction);
         Ok(rsp)
     }
 
@@ -144,18 +217,38 @@ impl JanusInterface for MockInterface {
 
     async fn keep_alive(
         &self,
-        _session_id: u64,
-        _timeout: Duration,
+        session_id: u64,
+        timeout: Duration,
     ) -> Result<(), jarust::interface::Error> {
-        todo!("Keep alive is not implemented");
+        let mut exclusive = self.inner.exclusive.lock().await;
+        exclusive.keep_alives.push(session_id);
+        if exclusive.stalled_sessions.contains(&session_id) {
+            drop(exclusive);
+            tokio::time::sleep(timeout).await;
+            return Err(Error::RequestTimeout);
+        }
+        if exclusive.expired_sessions.contains(&session_id) {
+            return Err(Error::JanusError {
+                code: 458,
+                reason: "No such session".to_string(),
+            });
+        }
+        Ok(())
     }
 
     async fn destroy(
         &self,
-        _session_id: u64,
-        _timeout: Duration,
+        session_id: u64,
+        timeout: Duration,
     ) -> Result<(), jarust::interface::Error> {
-        todo!("Destroy is not implemented");
+        let mut exclusive = self.inner.exclusive.lock().await;
+        if exclusive.stalled_sessions.contains(&session_id) {
+            drop(exclusive);
+            tokio::time::sleep(timeout).await;
+            return Err(Error::RequestTimeout);
+        }
+        exclusive.destroyed_sessions.push(session_id);
+        Ok(())
     }
 
     async fn fire_and_forget_msg(
@@ -167,10 +260,23 @@ impl JanusInterface for MockInterface {
 
     async fn send_msg_waiton_ack(
         &self,
-        _message: HandleMessage,
+        message: HandleMessage,
         _timeout: Duration,
     ) -> Result<String, jarust::interface::Error> {
-        todo!("Send message wait on ack is not implemented");
+        let guard = self.inner.exclusive.lock().await;
+        let Some(event) = guard.msg_event.clone() else {
+            todo!("Send message wait on ack is only implemented with a mocked event");
+        };
+        let transaction = event.transaction.clone().unwrap_or_default();
+        if let Some(tx) = guard.handles_rx.get(&message.handle_id) {
+            tx.send(event).unwrap();
+        }
+        let ack_delay = guard.ack_delay;
+        drop(guard);
+        if let Some(delay) = ack_delay {
+            tokio::time::sleep(delay).await;
+        }
+        Ok(transaction)
     }
 
     async fn internal_send_msg_waiton_rsp(
@@ -196,15 +302,30 @@ impl JanusInterface for MockInterface {
         todo!("Send message wait on ack with jsep is not implemented");
     }
 
-    async fn send_handle_request(&self, _request: HandleMessage) -> Result<(), Error> {
-        todo!("Send handle request is not implemented");
+    async fn send_handle_request(&self, request: HandleMessage) -> Result<(), Error> {
+        self.inner.exclusive.lock().await.handle_requests.push((
+            request.session_id,
+            request.handle_id,
+            request.body,
+        ));
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        self.inner.exclusive.lock().await.closed = true;
+        Ok(())
     }
 
     async fn send_handle_request_waiton_ack(
         &self,
-        _request: HandleMessage,
+        request: HandleMessage,
         _timeout: Duration,
     ) -> Result<String, Error> {
-        todo!("Send handle request and waiting on ack is not implemented");
+        self.inner.exclusive.lock().await.handle_requests.push((
+            request.session_id,
+            request.handle_id,
+            request.body,
+        ));
+        Ok("mock-ack-transaction".to_string())
     }
 }
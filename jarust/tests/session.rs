@@ -23,6 +23,11 @@ mod tests {
             capacity: 10,
             apisecret: None,
             server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
         };
         let transaction_generator = MockGenerateTransaction::new();
         let interface = MockInterface::make_interface(conn_params, transaction_generator)
@@ -72,6 +77,11 @@ mod tests {
             capacity: 10,
             apisecret: None,
             server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
         };
         let transaction_generator = MockGenerateTransaction::new();
         let interface = MockInterface::make_interface(conn_params, transaction_generator)
@@ -119,4 +129,78 @@ mod tests {
             Err(jarust::interface::Error::JanusError { .. })
         ));
     }
+
+    #[tokio::test]
+    async fn it_tracks_sessions_and_handles_in_the_registry() {
+        let conn_params = ConnectionParams {
+            url: "mock://some.janus.com".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "mock".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let transaction_generator = MockGenerateTransaction::new();
+        let interface = MockInterface::make_interface(conn_params, transaction_generator)
+            .await
+            .unwrap();
+        let mut connection = custom_connect(interface.clone()).await.unwrap();
+
+        let session_id = 73;
+
+        let response = JaResponse {
+            janus: ResponseType::Success(JaSuccessProtocol::Data {
+                data: JaData { id: session_id },
+            }),
+            transaction: Some("abc123".to_string()),
+            session_id: None,
+            sender: None,
+            jsep: None,
+        };
+        interface.mock_create_rsp(response).await;
+
+        let session = connection
+            .create_session(10, Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let response = JaResponse {
+            janus: ResponseType::Success(JaSuccessProtocol::Data {
+                data: JaData { id: 3 },
+            }),
+            transaction: Some("mock-attach-plugin-transaction".to_string()),
+            session_id: Some(session_id),
+            sender: None,
+            jsep: None,
+        };
+        interface.mock_attach_rsp(response).await;
+
+        let (handle, _) = session
+            .attach_with_opaque_id(
+                "mock.plugin.test".to_string(),
+                "opaque".to_string(),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(handle.id(), 3);
+        assert_eq!(handle.session_id(), session_id);
+
+        let sessions = connection.registry().sessions().await;
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, session_id);
+
+        let info = connection.registry().handle(3).await.unwrap();
+        assert_eq!(info.session_id, session_id);
+        assert_eq!(info.plugin, "mock.plugin.test");
+        assert_eq!(info.opaque_id.as_deref(), Some("opaque"));
+        assert_eq!(session.handles().await, vec![info]);
+
+        handle.detach().await.unwrap();
+        assert!(handle.info().await.is_none());
+        assert!(connection.registry().session(session_id).await.is_some());
+    }
 }
@@ -1,3 +1,9 @@
+use jarust_interface::janus_interface::HttpClientParams;
+use jarust_interface::janus_interface::LongPollParams;
+use jarust_interface::janus_interface::WebSocketParams;
+use jarust_interface::layer::RetryPolicy;
+use jarust_interface::parsing::ParseMode;
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct JaConfig {
     /// Url to janus server
@@ -9,12 +15,29 @@ pub struct JaConfig {
     pub server_root: String,
     /// Ring buffer capacity, used when picking WebSocket janus api
     pub capacity: usize,
+    /// Url to janus WebSocket transport, used by the hybrid janus api to receive events over a
+    /// single shared connection. When `None`, the hybrid janus api long-polls each session instead
+    pub events_url: Option<String>,
+    /// Long-polling parameters, used when picking Restful or Hybrid janus api
+    pub long_poll: LongPollParams,
+    /// HTTP client parameters (proxy, headers, TLS, pooling), used when picking Restful or Hybrid janus api
+    pub http_client: HttpClientParams,
+    /// WebSocket connection parameters (subprotocol, headers, TLS, proxy), used when picking WebSocket or Hybrid janus api
+    pub websocket: WebSocketParams,
+    /// What happens to the messages that fail to parse, lenient mode delivers them as unparsed events
+    pub parse_mode: ParseMode,
+    /// Retries the idempotent requests failing with a transient error (timeout, dropped
+    /// connection, janus 490), requests are one-shot when `None`
+    pub retry: Option<RetryPolicy>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum JanusAPI {
     WebSocket,
     Restful,
+    /// Requests over HTTP, events over a shared WebSocket or a long-poll per session
+    #[cfg(not(target_family = "wasm"))]
+    Hybrid,
     #[cfg(all(not(target_family = "wasm"), feature = "socketio"))]
     SocketIo,
 }
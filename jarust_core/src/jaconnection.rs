@@ -1,13 +1,40 @@
+use crate::jakeepalive::JaKeepAlive;
+use crate::jakeepalive::SessionEvent;
+use crate::jaregistry::JaRegistry;
 use crate::jasession::JaSession;
 use crate::jasession::NewSessionParams;
+use crate::jastate::HandlePhase;
+use crate::jastate::HandleState;
+use futures_util::future::join_all;
+use jarust_interface::handle_msg::HandleMessage;
 use jarust_interface::janus_interface::JanusInterface;
 use jarust_interface::janus_interface::JanusInterfaceImpl;
 use jarust_interface::japrotocol::ServerInfoRsp;
+use jarust_interface::parsing::Parsing;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::watch;
+use jarust_rt::sync::Mutex;
+use jarust_rt::task_group::Shutdown;
+use jarust_rt::task_group::TaskEvent;
+use jarust_rt::JaTaskGroup;
+use serde_json::json;
+use std::sync::Arc;
 use std::time::Duration;
+use web_time::Instant;
+
+/// The keep-alive interval used when janus' `session_timeout` couldn't be retrieved.
+const FALLBACK_KA_INTERVAL: Duration = Duration::from_secs(25);
 
 #[derive(Clone, Debug)]
 pub struct JaConnection {
     interface: JanusInterfaceImpl,
+    /// The background tasks of the sessions (keep-alive, handles events).
+    tasks: JaTaskGroup,
+    registry: JaRegistry,
+    parsing: Parsing,
+    keep_alive: JaKeepAlive,
+    /// The keep-alive interval derived from janus' `session_timeout`, once retrieved.
+    derived_ka_interval: Arc<Mutex<Option<Option<Duration>>>>,
 }
 
 impl JaConnection {
@@ -15,12 +42,23 @@ impl JaConnection {
         interface: impl JanusInterface,
     ) -> Result<Self, jarust_interface::Error> {
         tracing::info!("Creating new connection");
+        let parsing = interface.parsing();
+        let interface = JanusInterfaceImpl::new(interface);
+        let tasks = JaTaskGroup::new("Connection");
         Ok(Self {
-            interface: JanusInterfaceImpl::new(interface),
+            keep_alive: JaKeepAlive::new(interface.clone(), tasks.clone()),
+            interface,
+            tasks,
+            registry: JaRegistry::default(),
+            parsing,
+            derived_ka_interval: Arc::default(),
         })
     }
 
     /// Creates a new session with janus server.
+    ///
+    /// The session is kept alive every `ka_interval` seconds by the keep-alive scheduler of the
+    /// connection. A `ka_interval` of 0 derives the interval from janus' `session_timeout`.
     #[tracing::instrument(level = tracing::Level::DEBUG, skip_all)]
     pub async fn create_session(
         &mut self,
@@ -28,17 +66,42 @@ impl JaConnection {
         timeout: Duration,
     ) -> Result<JaSession, jarust_interface::Error> {
         tracing::info!("Creating new session");
+        let ka_interval = match ka_interval {
+            0 => self.derive_ka_interval(timeout).await,
+            secs => Some(Duration::from_secs(secs.into())),
+        };
         let session_id = self.interface.create(timeout).await?;
         let session = JaSession::new(NewSessionParams {
             session_id,
             ka_interval,
             interface: self.interface.clone(),
+            tasks: self.tasks.clone(),
+            keep_alive: self.keep_alive.clone(),
+            registry: self.registry.clone(),
+            parsing: self.parsing.clone(),
         })
         .await;
         tracing::info!(id = session_id, "Session created");
         Ok(session)
     }
 
+    /// Returns the registry of the sessions and handles created through this connection.
+    pub fn registry(&self) -> &JaRegistry {
+        &self.registry
+    }
+
+    /// Subscribes to the events of the sessions kept alive by the connection, e.g. a session
+    /// reported dead after repeated keep-alive failures.
+    pub fn session_events(&self) -> mpsc::UnboundedReceiver<Arc<SessionEvent>> {
+        self.keep_alive.events()
+    }
+
+    /// Returns the number of messages that failed to parse, whether they were dropped or
+    /// delivered as unparsed events.
+    pub fn parse_failures(&self) -> u64 {
+        self.parsing.failures()
+    }
+
     /// Retrieve Janus server info
     #[tracing::instrument(level = tracing::Level::DEBUG, skip_all)]
     pub async fn server_info(
@@ -48,4 +111,139 @@ impl JaConnection {
         let res = self.interface.server_info(timeout).await?;
         Ok(res)
     }
+
+    /// Derives the keep-alive interval from janus' `session_timeout`, half of it so a late
+    /// keep-alive doesn't let the session expire. `None` if sessions never time out.
+    async fn derive_ka_interval(&self, timeout: Duration) -> Option<Duration> {
+        if !self.interface.has_keep_alive() {
+            return None;
+        }
+        let mut derived = self.derived_ka_interval.lock().await;
+        if let Some(ka_interval) = *derived {
+            return ka_interval;
+        }
+        match self.interface.server_info(timeout).await {
+            Ok(info) => {
+                let ka_interval = match info.session_timeout {
+                    0 => None,
+                    secs => Some(Duration::from_secs(secs.div_ceil(2))),
+                };
+                tracing::debug!(?ka_interval, "Derived keep-alive interval");
+                *derived = Some(ka_interval);
+                ka_interval
+            }
+            Err(err) => {
+                tracing::warn!("Failed to retrieve the session timeout: {err}");
+                Some(FALLBACK_KA_INTERVAL)
+            }
+        }
+    }
+
+    /// Subscribes to the failures (errors and panics) of the background tasks of the
+    /// connection, its sessions and its interface.
+    pub fn task_events(&self) -> mpsc::UnboundedReceiver<TaskEvent> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.tasks.subscribe(sender.clone());
+        if let Some(tasks) = self.interface.task_group() {
+            tasks.subscribe(sender);
+        }
+        receiver
+    }
+
+    /// Gracefully shuts the connection down, e.g. on SIGTERM.
+    ///
+    /// Stops the keep-alives of the sessions and the background tasks of the interface, waiting
+    /// for them up to `deadline` before aborting the remaining ones. Sessions aren't destroyed
+    /// and the connection can't create new ones afterwards.
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip_all)]
+    pub async fn shutdown(&self, deadline: Duration) -> Shutdown {
+        tracing::info!("Shutting down connection");
+        let interface_tasks = self.interface.task_group();
+        let (sessions, interface) =
+            futures_util::future::join(self.tasks.shutdown(deadline), async {
+                match interface_tasks {
+                    Some(tasks) => tasks.shutdown(deadline).await,
+                    None => Shutdown::default(),
+                }
+            })
+            .await;
+        let shutdown = sessions.merge(interface);
+        if !shutdown.is_drained() {
+            tracing::warn!(aborted = ?shutdown.aborted, "Connection shut down with aborted tasks");
+        }
+        shutdown
+    }
+
+    /// Gracefully closes the connection.
+    ///
+    /// Detaches the handles and destroys the sessions created through this connection, all at
+    /// once, closes the transport (with a Close frame for WebSockets) then
+    /// [`shutdown`](Self::shutdown)s the background tasks with the time left. The whole call is
+    /// bounded by `timeout`. It goes through every step even if one fails and returns the first
+    /// error.
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip_all)]
+    pub async fn close(&self, timeout: Duration) -> Result<(), jarust_interface::Error> {
+        tracing::info!("Closing connection");
+        let deadline = Instant::now() + timeout;
+        let remaining = || deadline.saturating_duration_since(Instant::now());
+
+        let sessions = self.registry.drain().await;
+        let closing = join_all(
+            sessions
+                .into_iter()
+                .map(|(session_id, handles)| self.close_session(session_id, handles, remaining())),
+        );
+        let mut result = match jarust_rt::timeout(remaining(), closing).await {
+            Ok(results) => results.into_iter().collect::<Result<(), _>>(),
+            Err(()) => {
+                tracing::warn!("Timed out detaching handles and destroying sessions");
+                Err(jarust_interface::Error::RequestTimeout)
+            }
+        };
+        let closed = jarust_rt::timeout(remaining(), self.interface.close())
+            .await
+            .unwrap_or(Err(jarust_interface::Error::RequestTimeout));
+        if let Err(err) = closed {
+            tracing::warn!("Failed to close the transport: {err}");
+            result = result.and(Err(err));
+        }
+        self.shutdown(remaining()).await;
+        result
+    }
+
+    /// Detaches the handles of a drained session, then destroys it.
+    async fn close_session(
+        &self,
+        session_id: u64,
+        handles: Vec<(u64, watch::Sender<HandleState>)>,
+        timeout: Duration,
+    ) -> Result<(), jarust_interface::Error> {
+        let detaching = handles.iter().map(|(handle_id, _)| async move {
+            let request = HandleMessage {
+                session_id,
+                handle_id: *handle_id,
+                body: json!({
+                    "janus": "detach"
+                }),
+            };
+            let result = self.interface.send_handle_request(request).await;
+            if let Err(err) = &result {
+                tracing::warn!(session_id, handle_id, "Failed to detach handle: {err}");
+            }
+            result
+        });
+        let mut result = join_all(detaching)
+            .await
+            .into_iter()
+            .collect::<Result<(), _>>();
+        if let Err(err) = self.interface.destroy(session_id, timeout).await {
+            tracing::warn!(session_id, "Failed to destroy session: {err}");
+            result = result.and(Err(err));
+        }
+        // Either detached or gone with their session
+        for (_, state) in handles {
+            state.send_if_modified(|state| state.enter(HandlePhase::Detached));
+        }
+        result
+    }
 }
@@ -0,0 +1,241 @@
+//! Dispatching of the events of a handle.
+//!
+//! Every event updates the state of the handle, the events answering a request sent with
+//! [`JaHandle::send_waiton_event`] resolve it, the trickled candidates are copied to the
+//! candidate subscribers and every other event reaches the events stream of the handle.
+//!
+//! The transaction of a request is only known once it's sent, so while requests are being sent
+//! the events carrying an unknown transaction are held for a short window, in case they answer
+//! one of them. The other events aren't held, an unclaimed held event reaches the stream late.
+//!
+//! [`JaHandle::send_waiton_event`]: crate::jahandle::JaHandle::send_waiton_event
+
+use crate::jastate::HandleState;
+use crate::jatrickle::CandidateSubscribers;
+use futures_util::future::select;
+use futures_util::future::Either;
+use jarust_interface::japrotocol::GenericEvent;
+use jarust_interface::japrotocol::JaHandleEvent;
+use jarust_interface::japrotocol::JaResponse;
+use jarust_interface::japrotocol::ResponseType;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::oneshot;
+use jarust_rt::sync::watch;
+use jarust_rt::sync::Event;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::Duration;
+use web_time::Instant;
+
+/// How long an event carrying an unknown transaction is held while requests are being sent.
+const HOLD_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct Waiters {
+    /// The requests waiting on their event, by transaction.
+    waiting: HashMap<String, oneshot::Sender<JaResponse>>,
+    /// The requests being sent, whose transaction isn't known yet.
+    sending: usize,
+    /// The events held for the requests being sent, with the time they're released at.
+    held: VecDeque<(Instant, JaResponse)>,
+}
+
+/// The requests waiting on their event.
+#[derive(Default)]
+pub(crate) struct EventWaiters {
+    waiters: Mutex<Waiters>,
+    /// Notified once a request is sent, so the held events are released early.
+    sent: Event,
+}
+
+impl EventWaiters {
+    fn lock(&self) -> MutexGuard<'_, Waiters> {
+        // The waiters are only updated in place, a panic can't leave them halfway
+        self.waiters.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Starts waiting on the event of a request about to be sent.
+    pub(crate) fn waiter(self: &Arc<Self>) -> EventWaiter {
+        self.lock().sending += 1;
+        EventWaiter {
+            waiters: self.clone(),
+            sending: true,
+            transaction: None,
+        }
+    }
+}
+
+/// A request waiting on its event, dropping it stops waiting.
+pub(crate) struct EventWaiter {
+    waiters: Arc<EventWaiters>,
+    sending: bool,
+    transaction: Option<String>,
+}
+
+impl EventWaiter {
+    /// Waits on the event carrying the transaction of the sent request, possibly already held.
+    pub(crate) fn register(&mut self, transaction: String) -> oneshot::Receiver<JaResponse> {
+        let (sender, receiver) = oneshot::channel();
+        let mut waiters = self.waiters.lock();
+        let held = waiters
+            .held
+            .iter()
+            .position(|(_, event)| event.transaction.as_ref() == Some(&transaction));
+        match held.and_then(|index| waiters.held.remove(index)) {
+            Some((_, event)) => _ = sender.send(event),
+            None => {
+                waiters.waiting.insert(transaction.clone(), sender);
+                self.transaction = Some(transaction);
+            }
+        }
+        waiters.sending -= 1;
+        self.sending = false;
+        drop(waiters);
+        self.waiters.sent.notify(usize::MAX);
+        receiver
+    }
+}
+
+impl Drop for EventWaiter {
+    fn drop(&mut self) {
+        let mut waiters = self.waiters.lock();
+        if let Some(transaction) = &self.transaction {
+            waiters.waiting.remove(transaction);
+        }
+        if self.sending {
+            waiters.sending -= 1;
+            drop(waiters);
+            self.waiters.sent.notify(usize::MAX);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct HandleEvents {
+    pub(crate) candidates: CandidateSubscribers,
+    pub(crate) waiters: Arc<EventWaiters>,
+    pub(crate) state: watch::Sender<HandleState>,
+}
+
+impl Default for HandleEvents {
+    fn default() -> Self {
+        Self {
+            candidates: CandidateSubscribers::default(),
+            waiters: Arc::default(),
+            state: watch::channel(HandleState::default()).0,
+        }
+    }
+}
+
+/// Closes the state once the events stop, even when the forwarding task is cancelled.
+struct CloseOnDrop(watch::Sender<HandleState>);
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Forwards the events of a handle to its stream, its waiters and its candidates subscribers.
+pub(crate) async fn forward_events(
+    mut events: mpsc::UnboundedReceiver<JaResponse>,
+    sender: mpsc::UnboundedSender<JaResponse>,
+    dispatch: HandleEvents,
+) {
+    let _closing = CloseOnDrop(dispatch.state.clone());
+    loop {
+        let sent = dispatch.waiters.sent.listen();
+        let (released, next_release) = release_held(&dispatch.waiters, false);
+        for event in released {
+            deliver(event, &sender, &dispatch).await;
+        }
+        let event = match next_release {
+            Some(at) => {
+                let release = pin!(jarust_rt::sleep(
+                    at.saturating_duration_since(Instant::now())
+                ));
+                let wait = select(release, sent);
+                match select(pin!(events.recv()), wait).await {
+                    Either::Left((event, _)) => event,
+                    Either::Right(_) => continue,
+                }
+            }
+            None => events.recv().await,
+        };
+        let Some(event) = event else {
+            break;
+        };
+        dispatch.state.send_if_modified(|state| state.apply(&event));
+        if let Some(event) = claim(&dispatch.waiters, event) {
+            deliver(event, &sender, &dispatch).await;
+        }
+    }
+
+    let (released, _) = release_held(&dispatch.waiters, true);
+    for event in released {
+        deliver(event, &sender, &dispatch).await;
+    }
+    // The events stopped, i.e. the connection is gone
+    dispatch.waiters.lock().waiting.clear();
+}
+
+/// Hands the event to its waiter or holds it, returns it when it reaches the stream right away.
+fn claim(waiters: &EventWaiters, event: JaResponse) -> Option<JaResponse> {
+    let mut guard = waiters.lock();
+    let waiter = match &event.transaction {
+        Some(transaction) => guard.waiting.remove(transaction),
+        None => None,
+    };
+    let event = match waiter {
+        Some(waiter) => match waiter.send(event) {
+            Ok(()) => return None,
+            // The waiter gave up, the event still reaches the stream
+            Err(unclaimed) => unclaimed,
+        },
+        None if event.transaction.is_some() && guard.sending > 0 => {
+            guard.held.push_back((Instant::now() + HOLD_WINDOW, event));
+            return None;
+        }
+        None => event,
+    };
+    Some(event)
+}
+
+/// Releases the held events that can't be claimed anymore (or all of them), returns them with
+/// the time the next one is released at.
+fn release_held(waiters: &EventWaiters, all: bool) -> (Vec<JaResponse>, Option<Instant>) {
+    let mut guard = waiters.lock();
+    let now = Instant::now();
+    let mut released = vec![];
+    while let Some((at, _)) = guard.held.front() {
+        if !all && guard.sending > 0 && *at > now {
+            return (released, Some(*at));
+        }
+        if let Some((_, event)) = guard.held.pop_front() {
+            released.push(event);
+        }
+    }
+    (released, None)
+}
+
+async fn deliver(
+    event: JaResponse,
+    sender: &mpsc::UnboundedSender<JaResponse>,
+    dispatch: &HandleEvents,
+) {
+    if let ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Trickle { candidate })) =
+        &event.janus
+    {
+        dispatch
+            .candidates
+            .lock()
+            .await
+            .retain(|subscriber| subscriber.send(candidate.clone()).is_ok());
+    }
+    // The candidates subscribers may outlive the events receiver
+    _ = sender.send(event);
+}
@@ -1,44 +1,156 @@
+use crate::jaevents::HandleEvents;
+use crate::jaregistry::HandleInfo;
+use crate::jaregistry::JaRegistry;
+use crate::jastate::HandlePhase;
+use crate::jastate::HandleState;
+use crate::jatrickle::TrickleSender;
 use jarust_interface::handle_msg::HandleMessage;
 use jarust_interface::handle_msg::HandleMessageWithJsep;
 use jarust_interface::janus_interface::JanusInterfaceImpl;
 use jarust_interface::japrotocol::Candidate;
+use jarust_interface::japrotocol::JaHandleEvent;
+use jarust_interface::japrotocol::JaResponse;
 use jarust_interface::japrotocol::Jsep;
+use jarust_interface::japrotocol::PluginInnerData;
+use jarust_interface::japrotocol::ResponseType;
+use jarust_interface::japrotocol::TrickleCandidate;
+use jarust_interface::parsing::Parsing;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::watch;
+use jarust_rt::JaTaskGroup;
 use serde::de::DeserializeOwned;
 use serde_json::json;
 use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
 struct InnerHandle {
     id: u64,
     session_id: u64,
     interface: JanusInterfaceImpl,
+    registry: JaRegistry,
+    tasks: JaTaskGroup,
+    events: HandleEvents,
+    parsing: Parsing,
 }
 
+#[derive(Clone)]
 pub struct JaHandle {
-    inner: InnerHandle,
+    inner: Arc<InnerHandle>,
 }
 
 pub struct NewHandleParams {
     pub handle_id: u64,
     pub session_id: u64,
     pub interface: JanusInterfaceImpl,
+    pub registry: JaRegistry,
+    /// The group of the connection, supervising the background tasks of the handle.
+    pub tasks: JaTaskGroup,
+    /// The parse mode of the connection and its count of parse failures.
+    pub parsing: Parsing,
 }
 
 impl JaHandle {
     pub(crate) async fn new(params: NewHandleParams) -> Self {
         Self {
-            inner: InnerHandle {
+            inner: Arc::new(InnerHandle {
                 id: params.handle_id,
                 session_id: params.session_id,
                 interface: params.interface,
-            },
+                registry: params.registry,
+                tasks: params.tasks,
+                events: HandleEvents::default(),
+                parsing: params.parsing,
+            }),
         }
     }
 
+    /// Returns the handle id.
+    pub fn id(&self) -> u64 {
+        self.inner.id
+    }
+
+    /// Returns the id of the session the handle is attached to.
+    pub fn session_id(&self) -> u64 {
+        self.inner.session_id
+    }
+
+    /// Returns the handle as tracked by the registry of the connection, `None` once detached.
+    pub async fn info(&self) -> Option<HandleInfo> {
+        self.inner.registry.handle(self.inner.id).await
+    }
+
+    /// Subscribes to the ICE candidates janus trickles for this handle (with full-trickle),
+    /// the last one being [`TrickleCandidate::Completed`].
+    pub async fn ice_candidates(&self) -> mpsc::UnboundedReceiver<TrickleCandidate> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.inner.events.candidates.lock().await.push(sender);
+        receiver
+    }
+
+    /// Returns a sender coalescing the candidates gathered within `window` into single
+    /// [`trickle_candidates`](Self::trickle_candidates) requests.
+    pub fn trickle_sender(&self, window: Duration, timeout: Duration) -> TrickleSender {
+        TrickleSender::new(self.clone(), window, timeout)
+    }
+
+    /// Returns the current state of the handle.
+    pub fn state(&self) -> HandleState {
+        self.inner.events.state.borrow().clone()
+    }
+
+    /// Subscribes to the state of the handle, the channel closes once its events stop.
+    pub fn watch_state(&self) -> watch::Receiver<HandleState> {
+        self.inner.events.state.subscribe()
+    }
+
+    /// Waits for a state matching the predicate (possibly the current one), e.g.
+    /// [`HandleState::is_live`].
+    pub async fn wait_for_state(
+        &self,
+        predicate: impl FnMut(&HandleState) -> bool,
+    ) -> Result<HandleState, jarust_interface::Error> {
+        self.watch_state()
+            .wait_for(predicate)
+            .await
+            .map_err(|_| jarust_interface::Error::TransportNotOpened)
+    }
+
+    fn enter(&self, phase: HandlePhase) {
+        self.inner
+            .events
+            .state
+            .send_if_modified(|state| state.enter(phase));
+    }
+
+    /// Returns the parse mode of the connection, used to convert the events of the handle.
+    pub fn parsing(&self) -> &Parsing {
+        &self.inner.parsing
+    }
+
+    /// Returns the task group of the connection, e.g. to spawn the listeners of the handle so
+    /// their panics are reported and the shutdown of the connection drains them.
+    pub fn tasks(&self) -> &JaTaskGroup {
+        &self.inner.tasks
+    }
+
+    pub(crate) fn events(&self) -> HandleEvents {
+        self.inner.events.clone()
+    }
+
+    async fn touch(&self) {
+        self.inner
+            .registry
+            .touch(self.inner.session_id, Some(self.inner.id))
+            .await;
+    }
+
     /// Send a one-shot message
     #[tracing::instrument(level = tracing::Level::DEBUG, skip_all, fields(session_id = self.inner.session_id, handle_id = self.inner.id))]
     pub async fn fire_and_forget(&self, body: Value) -> Result<String, jarust_interface::Error> {
         tracing::debug!("Sending one-shot message");
+        self.touch().await;
         self.inner
             .interface
             .fire_and_forget_msg(HandleMessage {
@@ -60,6 +172,7 @@ impl JaHandle {
         R: DeserializeOwned,
     {
         tracing::debug!("Sending message and waiting for response");
+        self.touch().await;
         let res = self
             .inner
             .interface
@@ -83,6 +196,7 @@ impl JaHandle {
         timeout: Duration,
     ) -> Result<String, jarust_interface::Error> {
         tracing::debug!("Sending message and waiting for acknowledgement");
+        self.touch().await;
         let ack = self
             .inner
             .interface
@@ -107,6 +221,7 @@ impl JaHandle {
         timeout: Duration,
     ) -> Result<String, jarust_interface::Error> {
         tracing::debug!("Sending message with jsep and waiting for acknowledgement");
+        self.touch().await;
         let ack = self
             .inner
             .interface
@@ -120,9 +235,81 @@ impl JaHandle {
                 timeout,
             )
             .await?;
+        self.inner
+            .events
+            .state
+            .send_if_modified(HandleState::negotiate);
         Ok(ack)
     }
 
+    /// Send a message and wait for the plugin event answering it, i.e. the event carrying the
+    /// transaction of the request
+    ///
+    /// The event resolves the request instead of reaching the events stream of the handle,
+    /// a plugin error is returned as [`PluginResponseError`](jarust_interface::Error::PluginResponseError).
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip_all, fields(session_id = self.inner.session_id, handle_id = self.inner.id))]
+    pub async fn send_waiton_event(
+        &self,
+        body: Value,
+        timeout: Duration,
+    ) -> Result<JaResponse, jarust_interface::Error> {
+        tracing::debug!("Sending message and waiting for its event");
+        self.waiton_event(self.send_waiton_ack(body, timeout), timeout)
+            .await
+    }
+
+    /// Send a message with a jsep and wait for the plugin event answering it
+    ///
+    /// See [`send_waiton_event`](Self::send_waiton_event)
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip_all, fields(session_id = self.inner.session_id, handle_id = self.inner.id))]
+    pub async fn send_waiton_event_with_jsep(
+        &self,
+        body: Value,
+        jsep: Jsep,
+        timeout: Duration,
+    ) -> Result<JaResponse, jarust_interface::Error> {
+        tracing::debug!("Sending message with jsep and waiting for its event");
+        self.waiton_event(self.send_waiton_ack_with_jsep(body, jsep, timeout), timeout)
+            .await
+    }
+
+    async fn waiton_event(
+        &self,
+        send: impl Future<Output = Result<String, jarust_interface::Error>>,
+        timeout: Duration,
+    ) -> Result<JaResponse, jarust_interface::Error> {
+        // Waiting before sending, so an event received before its ack is held for the request
+        let mut waiter = self.inner.events.waiters.waiter();
+        // A single deadline for the ack and the event
+        let response = jarust_rt::timeout(timeout, async move {
+            let transaction = send.await?;
+            let receiver = waiter.register(transaction);
+            // The events of the handle stopped, i.e. the connection is gone
+            let response = receiver
+                .await
+                .map_err(|_| jarust_interface::Error::TransportNotOpened)?;
+            Ok::<_, jarust_interface::Error>(response)
+        })
+        .await;
+        let response = match response {
+            Ok(response) => response?,
+            Err(_) => {
+                tracing::error!("Timed out waiting for the event");
+                return Err(jarust_interface::Error::RequestTimeout);
+            }
+        };
+        if let ResponseType::Event(JaHandleEvent::PluginEvent { plugin_data }) = &response.janus {
+            if let PluginInnerData::Error { error_code, error } = &plugin_data.data {
+                tracing::error!("Plugin response error: {{ code: {error_code}, error: {error} }}");
+                return Err(jarust_interface::Error::PluginResponseError {
+                    error_code: *error_code,
+                    error: error.clone(),
+                });
+            }
+        }
+        Ok(response)
+    }
+
     /// Send a one-shot message with a jsep
     #[tracing::instrument(level = tracing::Level::DEBUG, skip_all, fields(session_id = self.inner.session_id, handle_id = self.inner.id))]
     pub async fn fire_and_forget_with_jsep(
@@ -131,6 +318,7 @@ impl JaHandle {
         jsep: Jsep,
     ) -> Result<(), jarust_interface::Error> {
         tracing::debug!("Sending a one-shot message with jsep");
+        self.touch().await;
         self.inner
             .interface
             .fire_and_forget_msg_with_jsep(HandleMessageWithJsep {
@@ -140,11 +328,16 @@ impl JaHandle {
                 jsep,
             })
             .await?;
+        self.inner
+            .events
+            .state
+            .send_if_modified(HandleState::negotiate);
         Ok(())
     }
 
     async fn send_handle_request(&self, body: Value) -> Result<(), jarust_interface::Error> {
         tracing::debug!("Sending a handle request");
+        self.touch().await;
         self.inner
             .interface
             .send_handle_request(HandleMessage {
@@ -161,6 +354,7 @@ impl JaHandle {
         timeout: Duration,
     ) -> Result<String, jarust_interface::Error> {
         tracing::debug!("Sending a handle request");
+        self.touch().await;
         self.inner
             .interface
             .send_handle_request_waiton_ack(
@@ -184,6 +378,7 @@ impl JaHandle {
             "janus": "hangup"
         });
         self.send_handle_request(request).await?;
+        self.enter(HandlePhase::HungUp);
         Ok(())
     }
 
@@ -197,6 +392,11 @@ impl JaHandle {
             "janus": "detach"
         });
         self.send_handle_request(request).await?;
+        self.inner
+            .registry
+            .remove_handle(self.inner.session_id, self.inner.id)
+            .await;
+        self.enter(HandlePhase::Detached);
         Ok(())
     }
 
@@ -210,6 +410,11 @@ impl JaHandle {
             "janus": "detach"
         });
         self.send_handle_request(request).await?;
+        self.inner
+            .registry
+            .remove_handle(self.inner.session_id, self.inner.id)
+            .await;
+        self.enter(HandlePhase::Detached);
         Ok(())
     }
 
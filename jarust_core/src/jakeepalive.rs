@@ -1,35 +1,236 @@
+//! Keeps the sessions of a connection alive from a single task.
+//!
+//! Sessions are queued by due time, with jitter so the keep-alives of sessions created together
+//! spread out over time. A session failing repeatedly, or unknown to janus, is reported dead
+//! and isn't kept alive anymore.
+
+use crate::jasubscribers::JaSubscribers;
+use futures_util::future::select;
+use futures_util::stream::FuturesUnordered;
+use futures_util::StreamExt;
 use jarust_interface::janus_interface::JanusInterfaceImpl;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::Event;
+use jarust_rt::JaTaskGroup;
+use rand::Rng;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::Weak;
 use std::time::Duration;
+use web_time::Instant;
 
-pub struct JaKeepAlive {
+/// Consecutive failed keep-alives after which a session is considered dead.
+const MAX_FAILURES: u32 = 3;
+/// Keep-alives in flight at once, so thousands of sessions due together don't flood the transport.
+const CONCURRENCY: usize = 64;
+/// Upper bound of a keep-alive's timeout, which is also kept well below the interval so a
+/// stalled keep-alive leaves room for the next ones before janus reclaims the session.
+const MAX_TIMEOUT: Duration = Duration::from_secs(10);
+/// Janus error code for an unknown session.
+const NO_SUCH_SESSION: u16 = 458;
+/// Upper bound of the idle sleep, the scheduler is woken up on registration anyway.
+const MAX_IDLE: Duration = Duration::from_secs(3600);
+
+/// The events of the sessions kept alive by a connection.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SessionEvent {
+    /// The session stopped answering keep-alives and isn't kept alive anymore, janus reclaims
+    /// it (and its handles) once its `session_timeout` elapses.
+    Dead { session_id: u64, error: String },
+}
+
+#[derive(Debug)]
+struct Scheduled {
+    interval: Duration,
+    due: Instant,
+    failures: u32,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    sessions: HashMap<u64, Scheduled>,
+    /// The due times of the sessions, entries not matching [`Scheduled::due`] are stale.
+    queue: BinaryHeap<Reverse<(Instant, u64)>>,
+    running: bool,
+}
+
+#[derive(Debug)]
+struct Inner {
     interface: JanusInterfaceImpl,
-    session_id: u64,
-    ka_interval: u32,
+    tasks: JaTaskGroup,
+    state: Mutex<State>,
+    wake: Event,
+    events: JaSubscribers<SessionEvent>,
+}
+
+/// The keep-alive scheduler of a connection.
+#[derive(Clone, Debug)]
+pub struct JaKeepAlive {
+    inner: Arc<Inner>,
 }
 
 impl JaKeepAlive {
-    pub fn new(interface: JanusInterfaceImpl, session_id: u64, ka_interval: u32) -> Self {
+    pub(crate) fn new(interface: JanusInterfaceImpl, tasks: JaTaskGroup) -> Self {
         Self {
-            interface,
+            inner: Arc::new(Inner {
+                interface,
+                tasks,
+                state: Mutex::default(),
+                wake: Event::new(),
+                events: JaSubscribers::default(),
+            }),
+        }
+    }
+
+    /// Keeps the session alive every `interval` (give or take the jitter).
+    pub(crate) fn register(&self, session_id: u64, interval: Duration) {
+        if !self.inner.interface.has_keep_alive() {
+            tracing::debug!(session_id, "Keep-alive not supported");
+            return;
+        }
+        let mut state = self.inner.lock();
+        let due = Instant::now() + jittered(interval);
+        state.sessions.insert(
             session_id,
-            ka_interval,
+            Scheduled {
+                interval,
+                due,
+                failures: 0,
+            },
+        );
+        state.queue.push(Reverse((due, session_id)));
+        let start = !std::mem::replace(&mut state.running, true);
+        drop(state);
+
+        if start {
+            let signal = self.inner.tasks.shutdown_signal();
+            let scheduler = Arc::downgrade(&self.inner);
+            self.inner.tasks.spawn("KeepAlive scheduler", async move {
+                signal.until(run(scheduler)).await;
+            });
+        } else {
+            self.inner.wake.notify(1);
+        }
+    }
+
+    pub(crate) fn unregister(&self, session_id: u64) {
+        if self.inner.lock().sessions.remove(&session_id).is_some() {
+            // Lets the scheduler stop once it has no session left
+            self.inner.wake.notify(1);
         }
     }
 
-    #[tracing::instrument(level = tracing::Level::DEBUG, skip_all, fields(session_id = self.session_id))]
-    pub async fn start(&self) -> Result<(), jarust_interface::Error> {
-        if !self.interface.has_keep_alive() {
-            tracing::debug!("Keep-alive not supported");
-            return Ok(());
+    pub(crate) fn events(&self) -> mpsc::UnboundedReceiver<Arc<SessionEvent>> {
+        self.inner.events.subscribe()
+    }
+}
+
+impl Inner {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state is only mutated in place without user code, a panic can't leave it halfway
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip(self))]
+    async fn keep_alive(self: Arc<Self>, session_id: u64) {
+        let Some(interval) = self.lock().sessions.get(&session_id).map(|s| s.interval) else {
+            return;
+        };
+        tracing::debug!("Sending keep-alive");
+        let timeout = (interval / 4).min(MAX_TIMEOUT);
+        let result = self.interface.keep_alive(session_id, timeout).await;
+
+        let mut state = self.lock();
+        let Some(scheduled) = state.sessions.get_mut(&session_id) else {
+            return;
+        };
+        match result {
+            Ok(()) => scheduled.failures = 0,
+            Err(err) => {
+                scheduled.failures += 1;
+                tracing::warn!(failures = scheduled.failures, "Keep-alive failed: {err}");
+                let unknown = matches!(
+                    err,
+                    jarust_interface::Error::JanusError {
+                        code: NO_SUCH_SESSION,
+                        ..
+                    }
+                );
+                if unknown || scheduled.failures >= MAX_FAILURES {
+                    state.sessions.remove(&session_id);
+                    drop(state);
+                    tracing::error!("Session is dead");
+                    self.events.publish(SessionEvent::Dead {
+                        session_id,
+                        error: err.to_string(),
+                    });
+                    return;
+                }
+            }
         }
-        let duration = Duration::from_secs(self.ka_interval.into());
-        loop {
-            jarust_rt::sleep(duration).await;
-            tracing::debug!("Sending keep-alive");
-            match self.interface.keep_alive(self.session_id, duration).await {
-                Ok(_) => tracing::debug!("Keep-alive success"),
-                Err(e) => tracing::error!("Keep-alive failed: {:?}", e),
-            };
+        let due = Instant::now() + jittered(scheduled.interval);
+        scheduled.due = due;
+        state.queue.push(Reverse((due, session_id)));
+    }
+}
+
+/// The scheduler task, it stops once there's no session left to keep alive.
+///
+/// The keep-alives are polled next to the sleep, so one stalling doesn't hold back the others.
+async fn run(scheduler: Weak<Inner>) {
+    let mut in_flight = FuturesUnordered::new();
+    loop {
+        let Some(inner) = scheduler.upgrade() else {
+            return;
+        };
+        // Listening before checking the queue, so a registration in between isn't missed
+        let wake = inner.wake.listen();
+        let now = Instant::now();
+        let next = {
+            let mut state = inner.lock();
+            if state.sessions.is_empty() {
+                state.running = false;
+                return;
+            }
+            let mut next = None;
+            while let Some(Reverse((at, session_id))) = state.queue.peek().copied() {
+                let current = state.sessions.get(&session_id).map(|s| s.due);
+                if current != Some(at) {
+                    state.queue.pop();
+                } else if at > now {
+                    next = Some(at);
+                    break;
+                } else if in_flight.len() < CONCURRENCY {
+                    state.queue.pop();
+                    in_flight.push(inner.clone().keep_alive(session_id));
+                } else {
+                    // Due but throttled, picked up once a keep-alive completes
+                    break;
+                }
+            }
+            next
+        };
+
+        // Not holding the scheduler while idle, so dropping the connection stops the task
+        // once the keep-alives in flight are done
+        drop(inner);
+        let idle = next.map_or(MAX_IDLE, |at| at.saturating_duration_since(now));
+        let sleep = pin!(jarust_rt::sleep(idle));
+        let timer = select(sleep, wake);
+        if in_flight.is_empty() {
+            timer.await;
+        } else {
+            select(timer, in_flight.next()).await;
         }
     }
 }
+
+/// Spreads the interval by ±10%.
+fn jittered(interval: Duration) -> Duration {
+    interval.mul_f64(rand::thread_rng().gen_range(0.9..1.1))
+}
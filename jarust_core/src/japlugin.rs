@@ -1,13 +1,34 @@
+use crate::jasubscribers::JaSubscribers;
 use crate::prelude::*;
-use jarust_rt::JaTask;
+use jarust_rt::sync::mpsc;
+use jarust_rt::GroupTask;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
 
 pub trait PluginTask {
-    fn assign_task(&mut self, task: JaTask);
+    fn assign_task(&mut self, task: GroupTask);
     fn cancel_task(&mut self);
 }
 
+/// Plugin handles fanning their events out to subscribers, so several tasks can observe the
+/// same handle.
+pub trait PluginSubscribe<E: Clone> {
+    fn subscribers(&self) -> &JaSubscribers<E>;
+
+    /// Returns an independent stream of the events of the handle.
+    fn subscribe(&self) -> mpsc::UnboundedReceiver<Arc<E>> {
+        self.subscribers().subscribe()
+    }
+
+    /// Returns an independent stream of the events of the handle matching the filter.
+    fn subscribe_filtered(
+        &self,
+        filter: impl Fn(&E) -> bool + Send + Sync + 'static,
+    ) -> mpsc::UnboundedReceiver<Arc<E>> {
+        self.subscribers().subscribe_filtered(filter)
+    }
+}
+
 #[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
 #[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
 pub trait Attach {
@@ -16,4 +37,13 @@ pub trait Attach {
         plugin_id: String,
         timeout: Duration,
     ) -> Result<(JaHandle, mpsc::UnboundedReceiver<JaResponse>), jarust_interface::Error>;
+
+    /// Attaches a plugin, tagging the handle with an opaque id (reported by janus to event
+    /// handlers and the admin API).
+    async fn attach_with_opaque_id(
+        &self,
+        plugin_id: String,
+        opaque_id: String,
+        timeout: Duration,
+    ) -> Result<(JaHandle, mpsc::UnboundedReceiver<JaResponse>), jarust_interface::Error>;
 }
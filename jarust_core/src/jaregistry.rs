@@ -0,0 +1,171 @@
+use crate::jastate::HandleState;
+use jarust_rt::sync::watch;
+use jarust_rt::sync::Mutex;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use web_time::SystemTime;
+
+/// A live session, as tracked by the [`JaRegistry`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionInfo {
+    pub id: u64,
+    pub created_at: SystemTime,
+    /// The last request sent through the session or one of its handles.
+    pub last_activity: SystemTime,
+    pub handles: Vec<HandleInfo>,
+}
+
+/// A live handle, as tracked by the [`JaRegistry`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandleInfo {
+    pub id: u64,
+    pub session_id: u64,
+    pub plugin: String,
+    pub opaque_id: Option<String>,
+    pub created_at: SystemTime,
+    /// The last request sent through the handle.
+    pub last_activity: SystemTime,
+}
+
+#[derive(Debug)]
+struct HandleEntry {
+    info: HandleInfo,
+    /// The state of the handle, marked detached when the connection drains the registry.
+    state: watch::Sender<HandleState>,
+}
+
+#[derive(Debug)]
+struct SessionEntry {
+    created_at: SystemTime,
+    last_activity: SystemTime,
+    handles: BTreeMap<u64, HandleEntry>,
+}
+
+impl SessionEntry {
+    fn info(&self, id: u64) -> SessionInfo {
+        SessionInfo {
+            id,
+            created_at: self.created_at,
+            last_activity: self.last_activity,
+            handles: self.handles.values().map(|h| h.info.clone()).collect(),
+        }
+    }
+}
+
+/// Keeps track of the sessions created through a connection and of their handles, until
+/// they're destroyed or detached.
+///
+/// Dropping a session or a handle doesn't remove it from the registry, as it's still alive
+/// on the server, which makes the registry useful to spot leaked handles.
+#[derive(Clone, Debug, Default)]
+pub struct JaRegistry {
+    sessions: Arc<Mutex<BTreeMap<u64, SessionEntry>>>,
+}
+
+impl JaRegistry {
+    /// Returns the live sessions with their handles, ordered by id.
+    pub async fn sessions(&self) -> Vec<SessionInfo> {
+        self.sessions
+            .lock()
+            .await
+            .iter()
+            .map(|(id, entry)| entry.info(*id))
+            .collect()
+    }
+
+    /// Looks a live session up.
+    pub async fn session(&self, session_id: u64) -> Option<SessionInfo> {
+        self.sessions
+            .lock()
+            .await
+            .get(&session_id)
+            .map(|entry| entry.info(session_id))
+    }
+
+    /// Looks a live handle up.
+    pub async fn handle(&self, handle_id: u64) -> Option<HandleInfo> {
+        self.sessions
+            .lock()
+            .await
+            .values()
+            .find_map(|entry| entry.handles.get(&handle_id).map(|h| h.info.clone()))
+    }
+
+    pub(crate) async fn insert_session(&self, session_id: u64) {
+        let now = SystemTime::now();
+        self.sessions
+            .lock()
+            .await
+            .entry(session_id)
+            .or_insert_with(|| SessionEntry {
+                created_at: now,
+                last_activity: now,
+                handles: BTreeMap::new(),
+            });
+    }
+
+    /// Removes the session along with its handles, which are detached by janus.
+    pub(crate) async fn remove_session(&self, session_id: u64) {
+        self.sessions.lock().await.remove(&session_id);
+    }
+
+    pub(crate) async fn insert_handle(
+        &self,
+        session_id: u64,
+        handle_id: u64,
+        plugin: String,
+        opaque_id: Option<String>,
+        state: watch::Sender<HandleState>,
+    ) {
+        let now = SystemTime::now();
+        let mut sessions = self.sessions.lock().await;
+        let Some(entry) = sessions.get_mut(&session_id) else {
+            return;
+        };
+        entry.last_activity = now;
+        entry.handles.insert(
+            handle_id,
+            HandleEntry {
+                info: HandleInfo {
+                    id: handle_id,
+                    session_id,
+                    plugin,
+                    opaque_id,
+                    created_at: now,
+                    last_activity: now,
+                },
+                state,
+            },
+        );
+    }
+
+    pub(crate) async fn remove_handle(&self, session_id: u64, handle_id: u64) {
+        if let Some(entry) = self.sessions.lock().await.get_mut(&session_id) {
+            entry.handles.remove(&handle_id);
+        }
+    }
+
+    /// Records activity on the session and, if any, on one of its handles.
+    pub(crate) async fn touch(&self, session_id: u64, handle_id: Option<u64>) {
+        let now = SystemTime::now();
+        let mut sessions = self.sessions.lock().await;
+        let Some(entry) = sessions.get_mut(&session_id) else {
+            return;
+        };
+        entry.last_activity = now;
+        if let Some(handle) = handle_id.and_then(|id| entry.handles.get_mut(&id)) {
+            handle.info.last_activity = now;
+        }
+    }
+
+    /// Empties the registry, returning every session with its handles and their state.
+    pub(crate) async fn drain(&self) -> Vec<(u64, Vec<(u64, watch::Sender<HandleState>)>)> {
+        std::mem::take(&mut *self.sessions.lock().await)
+            .into_iter()
+            .map(|(session_id, entry)| {
+                let handles = entry.handles.into_iter();
+                (session_id, handles.map(|(id, h)| (id, h.state)).collect())
+            })
+            .collect()
+    }
+}
@@ -1,29 +1,30 @@
+use crate::jaevents;
 use crate::jahandle::JaHandle;
 use crate::jahandle::NewHandleParams;
 use crate::jakeepalive::JaKeepAlive;
+use crate::jaregistry::HandleInfo;
+use crate::jaregistry::JaRegistry;
 use crate::prelude::*;
 use jarust_interface::janus_interface::JanusInterfaceImpl;
-use jarust_rt::JaTask;
+use jarust_interface::parsing::Parsing;
+use jarust_rt::sync::mpsc;
+use jarust_rt::JaTaskGroup;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::sync::Mutex;
 
 #[derive(Debug)]
 pub struct Shared {
     id: u64,
     interface: JanusInterfaceImpl,
-}
-
-#[derive(Debug, Default)]
-pub struct Exclusive {
-    task: Option<JaTask>,
+    tasks: JaTaskGroup,
+    registry: JaRegistry,
+    parsing: Parsing,
+    keep_alive: JaKeepAlive,
 }
 
 #[derive(Debug)]
 struct InnerSession {
     shared: Shared,
-    exclusive: Mutex<Exclusive>,
 }
 
 #[derive(Clone, Debug)]
@@ -33,33 +34,113 @@ pub struct JaSession {
 
 pub struct NewSessionParams {
     pub session_id: u64,
-    pub ka_interval: u32,
+    /// The keep-alive interval, `None` if the session doesn't need keep-alives.
+    pub ka_interval: Option<Duration>,
     pub interface: JanusInterfaceImpl,
+    /// The group of the connection, supervising the handles tasks.
+    pub tasks: JaTaskGroup,
+    /// The keep-alive scheduler of the connection.
+    pub keep_alive: JaKeepAlive,
+    /// The registry of the connection, tracking the session and its handles.
+    pub registry: JaRegistry,
+    /// The parse mode of the connection and its count of parse failures.
+    pub parsing: Parsing,
 }
 
 impl JaSession {
     pub(crate) async fn new(params: NewSessionParams) -> Self {
         let shared = Shared {
             id: params.session_id,
-            interface: params.interface.clone(),
+            interface: params.interface,
+            tasks: params.tasks,
+            registry: params.registry.clone(),
+            parsing: params.parsing,
+            keep_alive: params.keep_alive.clone(),
         };
-        let exclusive = Mutex::new(Exclusive::default());
         let session = Self {
-            inner: Arc::new(InnerSession { shared, exclusive }),
+            inner: Arc::new(InnerSession { shared }),
         };
 
-        let jakeepalive = JaKeepAlive::new(params.interface, params.session_id, params.ka_interval);
-
-        let keepalive_task =
-            jarust_rt::spawn("KeepAlive task", async move { jakeepalive.start().await });
-
-        session.inner.exclusive.lock().await.task = Some(keepalive_task);
+        params.registry.insert_session(params.session_id).await;
+        if let Some(ka_interval) = params.ka_interval {
+            params.keep_alive.register(params.session_id, ka_interval);
+        }
 
         session
     }
 }
 
 impl JaSession {
+    /// Returns the session id.
+    pub fn id(&self) -> u64 {
+        self.inner.shared.id
+    }
+
+    /// Returns the live handles of the session, including the dropped ones that weren't detached.
+    pub async fn handles(&self) -> Vec<HandleInfo> {
+        self.inner
+            .shared
+            .registry
+            .session(self.inner.shared.id)
+            .await
+            .map(|session| session.handles)
+            .unwrap_or_default()
+    }
+
+    async fn attach_plugin(
+        &self,
+        plugin_id: String,
+        opaque_id: Option<String>,
+        timeout: Duration,
+    ) -> Result<(JaHandle, mpsc::UnboundedReceiver<JaResponse>), jarust_interface::Error> {
+        tracing::info!(plugin = &plugin_id, "Attaching new handle");
+        let session_id = self.inner.shared.id;
+        let interface = &self.inner.shared.interface;
+        let (handle_id, event_receiver) = match opaque_id.clone() {
+            Some(opaque_id) => {
+                interface
+                    .attach_with_opaque_id(session_id, plugin_id.clone(), opaque_id, timeout)
+                    .await?
+            }
+            None => {
+                interface
+                    .attach(session_id, plugin_id.clone(), timeout)
+                    .await?
+            }
+        };
+
+        let tasks = &self.inner.shared.tasks;
+        let handle = JaHandle::new(NewHandleParams {
+            handle_id,
+            session_id,
+            interface: interface.clone(),
+            registry: self.inner.shared.registry.clone(),
+            tasks: tasks.clone(),
+            parsing: self.inner.shared.parsing.clone(),
+        })
+        .await;
+        self.inner
+            .shared
+            .registry
+            .insert_handle(
+                session_id,
+                handle_id,
+                plugin_id,
+                opaque_id,
+                handle.events().state,
+            )
+            .await;
+
+        let (sender, events) = mpsc::unbounded_channel();
+        let signal = tasks.shutdown_signal();
+        let forwarding = jaevents::forward_events(event_receiver, sender, handle.events());
+        tasks.spawn("Handle events forwarding task", async move {
+            signal.until(forwarding).await;
+        });
+        tracing::info!(id = handle_id, "Handle created");
+        Ok((handle, events))
+    }
+
     /// Destroy the current session
     ///
     /// Similar to [`destroy`](Self::destroy) but it borrows the session instead of consuming it
@@ -72,6 +153,8 @@ impl JaSession {
             .interface
             .destroy(session_id, timeout)
             .await?;
+        self.inner.shared.keep_alive.unregister(session_id);
+        self.inner.shared.registry.remove_session(session_id).await;
         Ok(())
     }
 
@@ -87,6 +170,8 @@ impl JaSession {
             .interface
             .destroy(session_id, timeout)
             .await?;
+        self.inner.shared.keep_alive.unregister(session_id);
+        self.inner.shared.registry.remove_session(session_id).await;
         Ok(())
     }
 }
@@ -101,30 +186,24 @@ impl Attach for JaSession {
         plugin_id: String,
         timeout: Duration,
     ) -> Result<(JaHandle, mpsc::UnboundedReceiver<JaResponse>), jarust_interface::Error> {
-        tracing::info!(plugin = &plugin_id, "Attaching new handle");
-        let session_id = self.inner.shared.id;
-        let (handle_id, event_receiver) = self
-            .inner
-            .shared
-            .interface
-            .attach(session_id, plugin_id, timeout)
-            .await?;
+        self.attach_plugin(plugin_id, None, timeout).await
+    }
 
-        let handle = JaHandle::new(NewHandleParams {
-            handle_id,
-            session_id,
-            interface: self.inner.shared.interface.clone(),
-        })
-        .await;
-        tracing::info!(id = handle_id, "Handle created");
-        Ok((handle, event_receiver))
+    /// Attach a plugin to the current session with an opaque id
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip_all, fields(session_id = self.inner.shared.id))]
+    async fn attach_with_opaque_id(
+        &self,
+        plugin_id: String,
+        opaque_id: String,
+        timeout: Duration,
+    ) -> Result<(JaHandle, mpsc::UnboundedReceiver<JaResponse>), jarust_interface::Error> {
+        self.attach_plugin(plugin_id, Some(opaque_id), timeout)
+            .await
     }
 }
 
-impl Drop for Exclusive {
+impl Drop for InnerSession {
     fn drop(&mut self) {
-        if let Some(task) = self.task.take() {
-            task.cancel()
-        }
+        self.shared.keep_alive.unregister(self.shared.id);
     }
 }
@@ -0,0 +1,93 @@
+//! The lifecycle of a handle, tracked from the requests it sends and the events it receives.
+
+use jarust_interface::japrotocol::GenericEvent;
+use jarust_interface::japrotocol::JaHandleEvent;
+use jarust_interface::japrotocol::JaResponse;
+use jarust_interface::japrotocol::ResponseType;
+use std::collections::BTreeSet;
+
+/// The phase of the PeerConnection of a handle.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum HandlePhase {
+    /// The handle is attached, no PeerConnection was negotiated yet.
+    #[default]
+    Attached,
+    /// An offer or answer was exchanged, ICE and DTLS are in progress.
+    Negotiating,
+    /// ICE and DTLS succeeded, janus established the PeerConnection.
+    WebrtcUp,
+    /// The PeerConnection was closed, a new one can be negotiated.
+    HungUp,
+    /// The handle was detached, it can't be used anymore.
+    Detached,
+}
+
+/// The state of a handle.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct HandleState {
+    pub phase: HandlePhase,
+    /// The streams janus is receiving media on, by mid (or by media type before Janus 1.0).
+    pub receiving: BTreeSet<String>,
+}
+
+impl HandleState {
+    /// Whether the PeerConnection is up with media flowing, e.g. a publisher is live.
+    pub fn is_live(&self) -> bool {
+        self.phase == HandlePhase::WebrtcUp && !self.receiving.is_empty()
+    }
+
+    /// Whether janus is receiving media on the stream.
+    pub fn is_receiving(&self, mid: &str) -> bool {
+        self.receiving.contains(mid)
+    }
+
+    /// Applies a jsep exchange, returning whether the state changed.
+    pub(crate) fn negotiate(&mut self) -> bool {
+        match self.phase {
+            HandlePhase::Attached | HandlePhase::HungUp => {
+                self.phase = HandlePhase::Negotiating;
+                true
+            }
+            HandlePhase::Negotiating | HandlePhase::WebrtcUp | HandlePhase::Detached => false,
+        }
+    }
+
+    /// Moves to `phase`, returning whether the state changed.
+    pub(crate) fn enter(&mut self, phase: HandlePhase) -> bool {
+        if self.phase == HandlePhase::Detached || self.phase == phase {
+            return false;
+        }
+        self.phase = phase;
+        if matches!(phase, HandlePhase::HungUp | HandlePhase::Detached) {
+            self.receiving.clear();
+        }
+        true
+    }
+
+    /// Applies an incoming event, returning whether the state changed.
+    pub(crate) fn apply(&mut self, response: &JaResponse) -> bool {
+        let jsep = response.jsep.is_some() && self.negotiate();
+        let ResponseType::Event(JaHandleEvent::GenericEvent(event)) = &response.janus else {
+            return jsep;
+        };
+        let changed = match event {
+            GenericEvent::WebrtcUp => self.enter(HandlePhase::WebrtcUp),
+            GenericEvent::Hangup { .. } => self.enter(HandlePhase::HungUp),
+            GenericEvent::Detached => self.enter(HandlePhase::Detached),
+            GenericEvent::Media {
+                mid,
+                media,
+                receiving,
+                ..
+            } if self.phase != HandlePhase::Detached => {
+                let stream = mid.as_ref().unwrap_or(media);
+                match receiving {
+                    true => self.receiving.insert(stream.clone()),
+                    false => self.receiving.remove(stream),
+                }
+            }
+            _ => false,
+        };
+        jsep || changed
+    }
+}
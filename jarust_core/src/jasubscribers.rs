@@ -0,0 +1,93 @@
+//! Fan-out of the events of a plugin handle to independent subscribers.
+
+use jarust_rt::sync::mpsc;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+type Filter<E> = Box<dyn Fn(&E) -> bool + Send + Sync>;
+
+struct Subscriber<E> {
+    filter: Option<Filter<E>>,
+    sender: mpsc::UnboundedSender<Arc<E>>,
+}
+
+/// The subscribers to the events of a plugin handle.
+///
+/// Every subscription is an independent [`Stream`](futures_util::Stream) receiving each
+/// (matching) event published after it was made, dropping it unsubscribes. The subscribers
+/// share the events behind an [`Arc`] instead of receiving a copy each.
+pub struct JaSubscribers<E> {
+    inner: Arc<Mutex<Vec<Subscriber<E>>>>,
+}
+
+impl<E: Clone> JaSubscribers<E> {
+    /// Subscribes to every event.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<Arc<E>> {
+        self.push(None)
+    }
+
+    /// Subscribes to the events matching the filter, e.g. a single event kind.
+    pub fn subscribe_filtered(
+        &self,
+        filter: impl Fn(&E) -> bool + Send + Sync + 'static,
+    ) -> mpsc::UnboundedReceiver<Arc<E>> {
+        self.push(Some(Box::new(filter)))
+    }
+
+    /// Sends the event to the matching subscribers, forgetting the dropped ones, and returns
+    /// it back to be shared with the primary receiver of the handle. It's never copied.
+    pub fn publish(&self, event: E) -> Arc<E> {
+        let event = Arc::new(event);
+        self.lock().retain(|subscriber| match &subscriber.filter {
+            Some(filter) if !filter(&event) => !subscriber.sender.is_closed(),
+            _ => subscriber.sender.send(event.clone()).is_ok(),
+        });
+        event
+    }
+
+    /// Returns the number of subscriptions, including the dropped ones not forgotten yet.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push(&self, filter: Option<Filter<E>>) -> mpsc::UnboundedReceiver<Arc<E>> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.lock().push(Subscriber { filter, sender });
+        receiver
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Subscriber<E>>> {
+        // Publishing can't panic midway, the subscribers are always consistent
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<E> Default for JaSubscribers<E> {
+    fn default() -> Self {
+        Self {
+            inner: Arc::default(),
+        }
+    }
+}
+
+impl<E> Clone for JaSubscribers<E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<E> fmt::Debug for JaSubscribers<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let len = self.inner.lock().map(|s| s.len()).unwrap_or_default();
+        f.debug_struct("JaSubscribers")
+            .field("subscribers", &len)
+            .finish()
+    }
+}
@@ -0,0 +1,91 @@
+//! Trickle ICE helpers: the subscribers to the candidates janus trickles for a handle and a
+//! batching sender for the local ones.
+
+use crate::jahandle::JaHandle;
+use jarust_interface::japrotocol::Candidate;
+use jarust_interface::japrotocol::TrickleCandidate;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub(crate) type CandidateSubscribers = Arc<Mutex<Vec<mpsc::UnboundedSender<TrickleCandidate>>>>;
+
+#[derive(Debug)]
+enum Outgoing {
+    Candidate(Candidate),
+    Completed,
+}
+
+/// Coalesces the candidates gathered within a window into single `trickle` requests.
+///
+/// Created with [`JaHandle::trickle_sender`], failed requests are logged.
+#[derive(Clone, Debug)]
+pub struct TrickleSender {
+    sender: mpsc::UnboundedSender<Outgoing>,
+}
+
+impl TrickleSender {
+    pub(crate) fn new(handle: JaHandle, window: Duration, timeout: Duration) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        handle.tasks().clone().spawn(
+            "Trickle batching task",
+            batch_candidates(handle, receiver, window, timeout),
+        );
+        Self { sender }
+    }
+
+    /// Queues a candidate, sent with the others gathered within the window.
+    #[allow(clippy::result_large_err)]
+    pub fn send(&self, candidate: Candidate) -> Result<(), jarust_interface::Error> {
+        self.sender
+            .send(Outgoing::Candidate(candidate))
+            .map_err(|_| jarust_interface::Error::SendError)
+    }
+
+    /// Flushes the queued candidates then tells janus all the candidates were gathered.
+    #[allow(clippy::result_large_err)]
+    pub fn complete(&self) -> Result<(), jarust_interface::Error> {
+        self.sender
+            .send(Outgoing::Completed)
+            .map_err(|_| jarust_interface::Error::SendError)
+    }
+}
+
+#[tracing::instrument(level = tracing::Level::DEBUG, skip_all, fields(handle_id = handle.id()))]
+async fn batch_candidates(
+    handle: JaHandle,
+    mut receiver: mpsc::UnboundedReceiver<Outgoing>,
+    window: Duration,
+    timeout: Duration,
+) {
+    while let Some(first) = receiver.recv().await {
+        let mut candidates = Vec::new();
+        let mut completed = false;
+        let mut queue = |outgoing| match outgoing {
+            Outgoing::Candidate(candidate) => candidates.push(candidate),
+            Outgoing::Completed => completed = true,
+        };
+
+        let wait = matches!(first, Outgoing::Candidate(_));
+        queue(first);
+        if wait {
+            jarust_rt::sleep(window).await;
+        }
+        while let Ok(outgoing) = receiver.try_recv() {
+            queue(outgoing);
+        }
+
+        if !candidates.is_empty() {
+            tracing::debug!("Trickling {} candidates", candidates.len());
+            if let Err(err) = handle.trickle_candidates(candidates, timeout).await {
+                tracing::warn!("Failed to trickle candidates: {err}");
+            }
+        }
+        if completed {
+            if let Err(err) = handle.complete_trickle(timeout).await {
+                tracing::warn!("Failed to complete trickle: {err}");
+            }
+        }
+    }
+}
@@ -15,7 +15,14 @@
 //!
 //! ## Runtime
 //!
-//! We currently only support the Tokio runtime and are planning to support more runtimes in the future. For that, we've abstracted the runtime-specific code in the [`jarust_rt`] crate.
+//! The runtime-specific code is abstracted in the [`jarust_rt`] crate, the runtime is picked with
+//! a feature:
+//!
+//! - `tokio-rt`: Tokio (default).
+//! - `smol-rt`: smol.
+//!
+//! Note that the built-in WebSocket and HTTP transports are built on tokio IO types, under smol
+//! they still need a tokio reactor (e.g. through `async-compat`).
 //!
 //! ## Plugins
 //!
@@ -24,10 +31,15 @@
 
 pub mod jaconfig;
 pub mod jaconnection;
+mod jaevents;
 pub mod jahandle;
-mod jakeepalive;
+pub mod jakeepalive;
 pub mod japlugin;
+pub mod jaregistry;
 pub mod jasession;
+pub mod jastate;
+pub mod jasubscribers;
+pub mod jatrickle;
 pub mod prelude;
 
 pub use jarust_interface::tgenerator::GenerateTransaction;
@@ -35,8 +47,13 @@ pub use jarust_interface::tgenerator::GenerateTransaction;
 use jaconfig::JaConfig;
 use jaconfig::JanusAPI;
 use jaconnection::JaConnection;
+#[cfg(not(target_family = "wasm"))]
+use jarust_interface::hybrid::HybridInterface;
 use jarust_interface::janus_interface::ConnectionParams;
 use jarust_interface::janus_interface::JanusInterface;
+use jarust_interface::layer::LayerExt;
+use jarust_interface::layer::Retry;
+use jarust_interface::layer::RetryPolicy;
 use jarust_interface::restful::RestfulInterface;
 #[cfg(all(not(target_family = "wasm"), feature = "socketio"))]
 use jarust_interface::socketio::SocketIoInterface;
@@ -65,24 +82,40 @@ pub async fn connect(
         capacity: jaconfig.capacity,
         apisecret: jaconfig.apisecret,
         server_root: jaconfig.server_root,
+        events_url: jaconfig.events_url,
+        long_poll: jaconfig.long_poll,
+        http_client: jaconfig.http_client,
+        websocket: jaconfig.websocket,
+        parse_mode: jaconfig.parse_mode,
     };
+    let retry = jaconfig.retry;
     match api_interface {
         JanusAPI::WebSocket => {
-            custom_connect(
+            connect_with_retry(
                 WebSocketInterface::make_interface(conn_params, transaction_generator).await?,
+                retry,
             )
             .await
         }
         JanusAPI::Restful => {
-            custom_connect(
+            connect_with_retry(
                 RestfulInterface::make_interface(conn_params, transaction_generator).await?,
+                retry,
+            )
+            .await
+        }
+        JanusAPI::Hybrid => {
+            connect_with_retry(
+                HybridInterface::make_interface(conn_params, transaction_generator).await?,
+                retry,
             )
             .await
         }
         #[cfg(all(not(target_family = "wasm"), feature = "socketio"))]
         JanusAPI::SocketIo => {
-            custom_connect(
+            connect_with_retry(
                 SocketIoInterface::make_interface(conn_params, transaction_generator).await?,
+                retry,
             )
             .await
         }
@@ -101,23 +134,42 @@ pub async fn connect(
         capacity: jaconfig.capacity,
         apisecret: jaconfig.apisecret,
         server_root: jaconfig.server_root,
+        events_url: jaconfig.events_url,
+        long_poll: jaconfig.long_poll,
+        http_client: jaconfig.http_client,
+        websocket: jaconfig.websocket,
+        parse_mode: jaconfig.parse_mode,
     };
+    let retry = jaconfig.retry;
     match api_interface {
         JanusAPI::WebSocket => {
-            custom_connect(
+            connect_with_retry(
                 WebSocketInterface::make_interface(conn_params, transaction_generator).await?,
+                retry,
             )
             .await
         }
         JanusAPI::Restful => {
-            custom_connect(
+            connect_with_retry(
                 RestfulInterface::make_interface(conn_params, transaction_generator).await?,
+                retry,
             )
             .await
         }
     }
 }
 
+/// Connects over the interface, retrying the idempotent requests when a policy is set.
+async fn connect_with_retry(
+    interface: impl JanusInterface,
+    retry: Option<RetryPolicy>,
+) -> Result<JaConnection, jarust_interface::Error> {
+    match retry {
+        Some(policy) => custom_connect(interface.layer(Retry::new(policy))).await,
+        None => custom_connect(interface).await,
+    }
+}
+
 /// Creates a new customized connection with janus servers.
 #[tracing::instrument(level = Level::TRACE, skip_all)]
 pub async fn custom_connect(
@@ -1,5 +1,6 @@
 pub use crate::jahandle::JaHandle;
 pub use crate::japlugin::Attach;
+pub use crate::japlugin::PluginSubscribe;
 pub use crate::japlugin::PluginTask;
 pub use crate::jasession::JaSession;
 
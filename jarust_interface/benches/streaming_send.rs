@@ -0,0 +1,141 @@
+//! Throughput and latency of concurrent requests over a [`StreamingJanusInterface`].
+//!
+//! Many clients share a single connection, as rooms do in production, over a loopback transport
+//! whose writes cost a few microseconds (like a socket write and flush). Run it with
+//! `cargo bench -p jarust_interface --bench streaming_send`.
+
+use bytes::Bytes;
+use jarust_interface::janus_interface::ConnectionParams;
+use jarust_interface::janus_interface::JanusInterface;
+use jarust_interface::streaming::MessageTransport;
+use jarust_interface::streaming::StreamingJanusInterface;
+use jarust_interface::tgenerator::RandomTransactionGenerator;
+use jarust_interface::Error;
+use jarust_rt::sync::mpsc;
+use serde_json::json;
+use serde_json::Value;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+const CLIENTS: usize = 256;
+const REQUESTS_PER_CLIENT: usize = 200;
+/// The cost of a single write, paid once per batch by the flush.
+const WRITE_COST: Duration = Duration::from_micros(50);
+
+/// Answers every request with a success, after spinning for the write cost.
+#[derive(Default)]
+struct LoopbackTransport {
+    inbound: Option<mpsc::UnboundedSender<Bytes>>,
+}
+
+impl LoopbackTransport {
+    #[allow(clippy::result_large_err)]
+    fn reply(&self, data: &[u8]) -> Result<(), Error> {
+        let request: Value = serde_json::from_slice(data)?;
+        let response = json!({
+            "janus": "success",
+            "transaction": request["transaction"],
+            "data": { "id": 4242 }
+        });
+        let inbound = self.inbound.as_ref().ok_or(Error::TransportNotOpened)?;
+        inbound
+            .send(response.to_string().into())
+            .map_err(|_| Error::SendError)
+    }
+}
+
+/// The number of writes, a batch being a single write.
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+fn write() {
+    WRITES.fetch_add(1, Ordering::Relaxed);
+    let start = Instant::now();
+    while start.elapsed() < WRITE_COST {
+        std::hint::spin_loop();
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageTransport for LoopbackTransport {
+    async fn connect(
+        &mut self,
+        _: &ConnectionParams,
+    ) -> Result<mpsc::UnboundedReceiver<Bytes>, Error> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.inbound = Some(tx);
+        Ok(rx)
+    }
+
+    async fn send(&mut self, data: &[u8], _: &str) -> Result<(), Error> {
+        write();
+        self.reply(data)
+    }
+
+    async fn send_batch(&mut self, batch: &[(Bytes, String)]) -> Result<(), Error> {
+        write();
+        for (data, _) in batch {
+            self.reply(data)?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<(), Error> {
+        self.inbound = None;
+        Ok(())
+    }
+}
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() {
+    let conn_params = ConnectionParams {
+        url: "loopback".to_string(),
+        capacity: 1024,
+        apisecret: None,
+        server_root: "janus".to_string(),
+        events_url: None,
+        long_poll: Default::default(),
+        http_client: Default::default(),
+        websocket: Default::default(),
+        parse_mode: Default::default(),
+    };
+    let interface = StreamingJanusInterface::<LoopbackTransport>::make_interface(
+        conn_params,
+        RandomTransactionGenerator,
+    )
+    .await
+    .unwrap();
+
+    let start = Instant::now();
+    let clients = (0..CLIENTS)
+        .map(|_| {
+            let interface = interface.clone();
+            tokio::spawn(async move {
+                let mut latencies = Vec::with_capacity(REQUESTS_PER_CLIENT);
+                for _ in 0..REQUESTS_PER_CLIENT {
+                    let sent = Instant::now();
+                    interface.create(Duration::from_secs(10)).await.unwrap();
+                    latencies.push(sent.elapsed());
+                }
+                latencies
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut latencies = vec![];
+    for client in clients {
+        latencies.extend(client.await.unwrap());
+    }
+    let elapsed = start.elapsed();
+
+    latencies.sort();
+    let percentile = |p: usize| latencies[(latencies.len() * p / 100).min(latencies.len() - 1)];
+    println!(
+        "{} requests ({} writes) from {CLIENTS} clients in {elapsed:?}: {:.0} req/s, p50 {:?}, p99 {:?}",
+        latencies.len(),
+        WRITES.load(Ordering::Relaxed),
+        latencies.len() as f64 / elapsed.as_secs_f64(),
+        percentile(50),
+        percentile(99),
+    );
+}
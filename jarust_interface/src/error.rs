@@ -29,6 +29,12 @@ pub enum Error {
     IncompletePacket,
     #[error("Transport is not opened")]
     TransportNotOpened,
+    #[error("Invalid header {{ name: {name} }}")]
+    InvalidHeader { name: String },
+    #[error("Invalid TLS configuration {{ reason: {reason} }}")]
+    InvalidTlsConfig { reason: String },
+    #[error("Proxy error {{ reason: {reason} }}")]
+    Proxy { reason: String },
     #[error("Invalid Janus request {{ reason: {reason} }}")]
     InvalidJanusRequest { reason: String },
     #[error("Can't send data in closed channel")]
@@ -41,4 +47,8 @@ pub enum Error {
     PluginResponseError { error_code: u16, error: String },
     #[error("Request timeout")]
     RequestTimeout,
+    #[error("Unsupported operation {{ reason: {reason} }}")]
+    Unsupported { reason: String },
+    #[error("Invalid SDP {{ line: {line}, reason: {reason} }}")]
+    InvalidSdp { line: usize, reason: String },
 }
@@ -2,6 +2,7 @@ use crate::japrotocol::Jsep;
 use serde_json::json;
 use serde_json::Value;
 
+#[derive(Debug, Clone)]
 pub struct HandleMessage {
     pub session_id: u64,
     pub handle_id: u64,
@@ -30,6 +31,7 @@ impl HandleMessage {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct HandleMessageWithJsep {
     pub session_id: u64,
     pub handle_id: u64,
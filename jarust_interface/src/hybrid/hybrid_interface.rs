@@ -0,0 +1,553 @@
+use crate::handle_msg::HandleMessage;
+use crate::handle_msg::HandleMessageWithJsep;
+use crate::janus_interface::ConnectionParams;
+use crate::janus_interface::JanusInterface;
+use crate::japrotocol::JaResponse;
+use crate::japrotocol::ResponseType;
+use crate::japrotocol::ServerInfoRsp;
+use crate::parsing::Parsing;
+use crate::protocol::Expect;
+use crate::protocol::JanusProtocol;
+use crate::protocol::ProtocolParams;
+use crate::restful::RestfulInterface;
+use crate::streaming::MessageTransport;
+use crate::tgenerator::GenerateTransaction;
+use crate::tgenerator::TransactionGenerator;
+use crate::transport::demuxer::Demuxer;
+use crate::transport::driver::ProtocolDriver;
+use crate::transport::router::Router;
+use crate::websocket::native::websocket_client::WebSocketClient;
+use crate::Error;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::Mutex;
+use jarust_rt::JaTaskGroup;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug)]
+struct Shared {
+    rest: RestfulInterface,
+    /// Drives the `claim` requests, only present when events are received over WebSocket.
+    driver: Option<ProtocolDriver>,
+}
+
+/// Receives the events of every session over a single WebSocket connection, on which the
+/// sessions are claimed.
+#[derive(Debug)]
+struct WebSocketEvents {
+    router: Router,
+    ws: WebSocketClient,
+}
+
+#[derive(Debug)]
+struct Exclusive {
+    /// `None` when the sessions are long-polled by the restful interface.
+    events: Option<WebSocketEvents>,
+}
+
+#[derive(Debug)]
+struct InnerHybridInterface {
+    shared: Shared,
+    exclusive: Mutex<Exclusive>,
+}
+
+/// Sends requests over HTTP and receives events over a single shared WebSocket or a
+/// single long-poll per session, demultiplexed to the handles by `sender`.
+///
+/// Useful when the WebSocket can't be used on the request path (e.g. behind some proxies),
+/// while still avoiding one long-poll per handle.
+#[derive(Debug, Clone)]
+pub struct HybridInterface {
+    inner: Arc<InnerHybridInterface>,
+}
+
+impl HybridInterface {
+    /// Claims the session on the events WebSocket, so janus delivers its events there.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip(self, timeout))]
+    async fn claim(&self, session_id: u64, timeout: Duration) -> Result<(), Error> {
+        let Some(driver) = &self.inner.shared.driver else {
+            return Ok(());
+        };
+        let (request, reply) = driver.send_waiton(
+            json!({
+                "janus": "claim",
+                "session_id": session_id
+            }),
+            Expect::Response,
+        );
+
+        if let Some(events) = &mut self.inner.exclusive.lock().await.events {
+            events.ws.send(&request.data, &request.path).await?;
+        }
+
+        tracing::trace!("Claiming session");
+        reply.wait(timeout).await?;
+        Ok(())
+    }
+
+    /// Attaches a plugin over HTTP and routes its events from the events WebSocket, if any.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn attach_plugin(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: Option<String>,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        let rest = &self.inner.shared.rest;
+        if self.inner.shared.driver.is_none() {
+            return match opaque_id {
+                Some(opaque_id) => {
+                    rest.attach_with_opaque_id(session_id, plugin_id, opaque_id, timeout)
+                        .await
+                }
+                None => rest.attach(session_id, plugin_id, timeout).await,
+            };
+        }
+        let handle_id = rest
+            .attach_handle(session_id, plugin_id, opaque_id, timeout)
+            .await?;
+        let mut guard = self.inner.exclusive.lock().await;
+        let Some(events) = &mut guard.events else {
+            return Err(Error::TransportNotOpened);
+        };
+        let receiver = events
+            .router
+            .add_subroute(&format!("{session_id}/{handle_id}"))
+            .await;
+        Ok((handle_id, receiver))
+    }
+}
+
+#[async_trait::async_trait]
+impl JanusInterface for HybridInterface {
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn make_interface(
+        conn_params: ConnectionParams,
+        transaction_generator: impl GenerateTransaction,
+    ) -> Result<Self, Error> {
+        tracing::debug!("Creating Hybrid Interface");
+        let capacity = conn_params.capacity;
+        let apisecret = conn_params.apisecret.clone();
+        let events_url = conn_params.events_url.clone();
+        let server_root = conn_params.server_root.clone();
+        let websocket = conn_params.websocket.clone();
+
+        // The generator is shared with the restful interface carrying the requests, so
+        // transactions stay unique across both transports.
+        let transaction_generator = Arc::new(TransactionGenerator::new(transaction_generator));
+        let rest = RestfulInterface::make_interface(conn_params, {
+            let generator = transaction_generator.clone();
+            move || generator.generate_transaction()
+        })
+        .await?;
+
+        // The WebSocket tasks join the long-poll tasks of the restful interface, so a single
+        // group supervises the interface.
+        let tasks = rest.tasks().clone();
+        let (events, driver) = match events_url {
+            Some(events_url) => {
+                let router = Router::new(&server_root);
+                let mut ws = WebSocketClient::new();
+                let receiver = ws.open(&events_url, &websocket).await?;
+                // Claims are answered with a response, acks are never awaited on this socket.
+                let protocol = JanusProtocol::new(
+                    ProtocolParams {
+                        server_root,
+                        apisecret,
+                        capacity,
+                        parsing: rest.parsing(),
+                    },
+                    TransactionGenerator::new({
+                        let generator = transaction_generator.clone();
+                        move || generator.generate_transaction()
+                    }),
+                );
+                let driver = ProtocolDriver::new(protocol);
+
+                tasks.spawn_fallible("Demultiplexing task", {
+                    let signal = tasks.shutdown_signal();
+                    let demuxer = Demuxer {
+                        inbound_stream: receiver,
+                        router: router.clone(),
+                        driver: driver.clone(),
+                    };
+                    async move { signal.until(demuxer.start()).await.unwrap_or(Ok(())) }
+                });
+
+                (Some(WebSocketEvents { router, ws }), Some(driver))
+            }
+            None => (None, None),
+        };
+
+        let shared = Shared { rest, driver };
+        let exclusive = Exclusive { events };
+        let inner = InnerHybridInterface {
+            shared,
+            exclusive: Mutex::new(exclusive),
+        };
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn create(&self, timeout: Duration) -> Result<u64, Error> {
+        if self.inner.shared.driver.is_none() {
+            return self.inner.shared.rest.create(timeout).await;
+        }
+        let session_id = self.inner.shared.rest.create_session(timeout).await?;
+        self.claim(session_id, timeout).await?;
+        Ok(session_id)
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn server_info(&self, timeout: Duration) -> Result<ServerInfoRsp, Error> {
+        self.inner.shared.rest.server_info(timeout).await
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn attach(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        self.attach_plugin(session_id, plugin_id, None, timeout)
+            .await
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn attach_with_opaque_id(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: String,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        self.attach_plugin(session_id, plugin_id, Some(opaque_id), timeout)
+            .await
+    }
+
+    fn has_keep_alive(&self) -> bool {
+        // Long-polling keeps the session alive on its own
+        self.inner.shared.driver.is_some()
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn keep_alive(&self, session_id: u64, timeout: Duration) -> Result<(), Error> {
+        let request = json!({
+            "janus": "keepalive"
+        });
+        let response = self
+            .inner
+            .shared
+            .rest
+            .send_session_request(session_id, request, timeout)
+            .await?;
+        match response.janus {
+            ResponseType::Error { error } => Err(Error::JanusError {
+                code: error.code,
+                reason: error.reason,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn destroy(&self, session_id: u64, timeout: Duration) -> Result<(), Error> {
+        self.inner.shared.rest.destroy(session_id, timeout).await
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn fire_and_forget_msg(&self, message: HandleMessage) -> Result<String, Error> {
+        self.inner.shared.rest.fire_and_forget_msg(message).await
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn send_msg_waiton_ack(
+        &self,
+        message: HandleMessage,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        self.inner
+            .shared
+            .rest
+            .send_msg_waiton_ack(message, timeout)
+            .await
+    }
+
+    async fn internal_send_msg_waiton_rsp(
+        &self,
+        message: HandleMessage,
+        timeout: Duration,
+    ) -> Result<JaResponse, Error> {
+        self.inner
+            .shared
+            .rest
+            .internal_send_msg_waiton_rsp(message, timeout)
+            .await
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn fire_and_forget_msg_with_jsep(
+        &self,
+        message: HandleMessageWithJsep,
+    ) -> Result<String, Error> {
+        self.inner
+            .shared
+            .rest
+            .fire_and_forget_msg_with_jsep(message)
+            .await
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn send_msg_waiton_ack_with_jsep(
+        &self,
+        message: HandleMessageWithJsep,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        self.inner
+            .shared
+            .rest
+            .send_msg_waiton_ack_with_jsep(message, timeout)
+            .await
+    }
+
+    async fn send_handle_request(&self, request: HandleMessage) -> Result<(), Error> {
+        self.inner.shared.rest.send_handle_request(request).await
+    }
+
+    async fn send_handle_request_waiton_ack(
+        &self,
+        request: HandleMessage,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        self.inner
+            .shared
+            .rest
+            .send_handle_request_waiton_ack(request, timeout)
+            .await
+    }
+
+    fn name(&self) -> Box<str> {
+        "Hybrid Interface".to_string().into_boxed_str()
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn close(&self) -> Result<(), Error> {
+        match self.inner.exclusive.lock().await.events.as_mut() {
+            Some(events) => events.ws.close().await,
+            None => Ok(()),
+        }
+    }
+
+    fn task_group(&self) -> Option<JaTaskGroup> {
+        self.inner.shared.rest.task_group()
+    }
+
+    fn parsing(&self) -> Parsing {
+        self.inner.shared.rest.parsing()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HybridInterface;
+    use crate::janus_interface::ConnectionParams;
+    use crate::janus_interface::JanusInterface;
+    use crate::japrotocol::JaHandleEvent;
+    use crate::japrotocol::ResponseType;
+    use futures_util::SinkExt;
+    use futures_util::StreamExt;
+    use serde_json::json;
+    use serde_json::Value;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+    use tokio::net::TcpStream;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::tungstenite::handshake::server::ErrorResponse;
+    use tokio_tungstenite::tungstenite::handshake::server::Request;
+    use tokio_tungstenite::tungstenite::handshake::server::Response;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// Reads an HTTP request and returns its JSON body.
+    async fn read_request(socket: &mut TcpStream) -> Value {
+        let mut request = vec![];
+        let mut chunk = [0; 1024];
+        loop {
+            let read = socket.read(&mut chunk).await.unwrap();
+            request.extend_from_slice(&chunk[..read]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or_default();
+                if body.len() >= length {
+                    return serde_json::from_str(body).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Spawns an HTTP server creating session 1 and attaching handles 2, 3, …, and returns its
+    /// url.
+    async fn http_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handles = AtomicU64::new(2);
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let request = read_request(&mut socket).await;
+                let id = match request["janus"].as_str() {
+                    Some("create") => 1,
+                    Some("attach") => handles.fetch_add(1, Ordering::Relaxed),
+                    janus => panic!("Unexpected request {janus:?}"),
+                };
+                let body = json!({
+                    "janus": "success",
+                    "transaction": request["transaction"],
+                    "data": { "id": id }
+                })
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    /// Echoes the subprotocol requested by the client.
+    #[allow(clippy::result_large_err)]
+    fn accept_janus_protocol(
+        _: &Request,
+        mut response: Response,
+    ) -> Result<Response, ErrorResponse> {
+        response
+            .headers_mut()
+            .insert("Sec-Websocket-Protocol", "janus-protocol".parse().unwrap());
+        Ok(response)
+    }
+
+    /// Spawns a WebSocket server answering the claims, reported on `claims`, and pushing the
+    /// events received on `events`. Returns its url.
+    async fn ws_server(
+        claims: mpsc::UnboundedSender<u64>,
+        mut events: mpsc::UnboundedReceiver<Value>,
+    ) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_hdr_async(socket, accept_janus_protocol)
+                .await
+                .unwrap();
+            loop {
+                tokio::select! {
+                    Some(Ok(message)) = ws.next() => {
+                        let request: Value = match message {
+                            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+                            Message::Binary(data) => serde_json::from_slice(&data).unwrap(),
+                            _ => continue,
+                        };
+                        assert_eq!(request["janus"], "claim");
+                        let session_id = request["session_id"].as_u64().unwrap();
+                        claims.send(session_id).unwrap();
+                        let reply = json!({
+                            "janus": "success",
+                            "session_id": session_id,
+                            "transaction": request["transaction"]
+                        });
+                        ws.send(Message::text(reply.to_string())).await.unwrap();
+                    }
+                    Some(event) = events.recv() => {
+                        ws.send(Message::text(event.to_string())).await.unwrap();
+                    }
+                    else => break,
+                }
+            }
+        });
+        url
+    }
+
+    fn event(sender: u64) -> Value {
+        json!({
+            "janus": "event",
+            "session_id": 1,
+            "sender": sender,
+            "plugindata": {
+                "plugin": "janus.plugin.echotest",
+                "data": { "echotest": "event", "result": "ok" }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn it_should_claim_sessions_and_route_their_events_to_the_handles() {
+        let (claims_tx, mut claims) = mpsc::unbounded_channel();
+        let (events, events_rx) = mpsc::unbounded_channel();
+        let conn_params = ConnectionParams {
+            url: http_server().await,
+            capacity: 10,
+            apisecret: None,
+            server_root: "janus".to_string(),
+            events_url: Some(ws_server(claims_tx, events_rx).await),
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let next = Arc::new(AtomicU64::new(0));
+        let interface = HybridInterface::make_interface(conn_params, move || {
+            format!("tx{}", next.fetch_add(1, Ordering::Relaxed))
+        })
+        .await
+        .unwrap();
+        let timeout = Duration::from_secs(5);
+
+        let session_id = interface.create(timeout).await.unwrap();
+        assert_eq!(session_id, 1);
+        assert_eq!(claims.recv().await, Some(1));
+
+        let plugin = "janus.plugin.echotest".to_string();
+        let (first, mut first_events) = interface
+            .attach(session_id, plugin.clone(), timeout)
+            .await
+            .unwrap();
+        let (second, mut second_events) =
+            interface.attach(session_id, plugin, timeout).await.unwrap();
+        events.send(event(second)).unwrap();
+        events.send(event(first)).unwrap();
+
+        let received = tokio::time::timeout(timeout, first_events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.sender, Some(first));
+        assert!(matches!(
+            received.janus,
+            ResponseType::Event(JaHandleEvent::PluginEvent { .. })
+        ));
+        let received = tokio::time::timeout(timeout, second_events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.sender, Some(second));
+        assert!(first_events.try_recv().is_err());
+    }
+}
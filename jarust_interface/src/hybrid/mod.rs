@@ -0,0 +1,3 @@
+pub mod hybrid_interface;
+
+pub use hybrid_interface::HybridInterface;
@@ -5,14 +5,17 @@ use crate::japrotocol::JaSuccessProtocol;
 use crate::japrotocol::PluginInnerData;
 use crate::japrotocol::ResponseType;
 use crate::japrotocol::ServerInfoRsp;
+use crate::parsing::ParseMode;
+use crate::parsing::Parsing;
 use crate::tgenerator::GenerateTransaction;
 use crate::Error;
+use jarust_rt::sync::mpsc;
+use jarust_rt::JaTaskGroup;
 use serde::de::DeserializeOwned;
 use std::fmt::Debug;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
 
 #[cfg(not(target_family = "wasm"))]
 pub trait MaybeSend: Send {}
@@ -34,6 +37,98 @@ pub trait MaybeSync {}
 #[cfg(target_family = "wasm")]
 impl<T> MaybeSync for T {}
 
+/// Long-polling parameters (for the restful and hybrid interfaces).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct LongPollParams {
+    /// The maximum number of events janus returns per poll (`maxev`).
+    pub maxev: u32,
+    /// Whether to send a random request id (`rid`) with every poll, so caching proxies
+    /// never serve a stale poll.
+    pub rid: bool,
+}
+
+impl Default for LongPollParams {
+    fn default() -> Self {
+        Self {
+            maxev: 5,
+            rid: false,
+        }
+    }
+}
+
+/// HTTP client parameters (for the restful and hybrid interfaces).
+///
+/// Only the default headers are applied on WASM, where the browser owns the connections.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct HttpClientParams {
+    /// The proxy every request is sent through (e.g. `http://proxy.local:3128`).
+    pub proxy: Option<String>,
+    /// Headers added to every request (e.g. an auth header required by an API gateway).
+    pub default_headers: Vec<(String, String)>,
+    /// Additional PEM encoded root certificates to trust (e.g. a private CA).
+    pub root_certificates: Vec<Vec<u8>>,
+    /// PEM encoded client certificate chain and private key, for mutual TLS.
+    pub identity: Option<Vec<u8>>,
+    /// The maximum number of idle connections kept per host.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// How long idle connections are kept alive in the pool.
+    pub pool_idle_timeout: Option<Duration>,
+    /// Whether to use HTTP/2 right away instead of negotiating it.
+    pub http2_prior_knowledge: bool,
+}
+
+/// WebSocket heartbeat parameters, used to detect dead peers (e.g. half-open connections
+/// behind a load balancer).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct WebSocketHeartbeat {
+    /// How long the connection may stay silent before a ping is sent.
+    pub interval: Duration,
+    /// How long to wait for any frame after a ping before closing the connection.
+    pub timeout: Duration,
+}
+
+impl Default for WebSocketHeartbeat {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// WebSocket connection parameters (for the websocket and hybrid interfaces).
+///
+/// Only the subprotocol is applied on WASM, where the browser owns the connections.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct WebSocketParams {
+    /// The subprotocol requested during the handshake, `janus-admin-protocol` targets the admin API.
+    pub protocol: String,
+    /// Headers added to the handshake request (e.g. an auth cookie required by a reverse proxy).
+    pub headers: Vec<(String, String)>,
+    /// Additional PEM encoded root certificates to trust (e.g. a private CA).
+    pub root_certificates: Vec<Vec<u8>>,
+    /// PEM encoded client certificate chain and private key, for mutual TLS. With native-tls
+    /// the key must be PKCS#8 (`BEGIN PRIVATE KEY`), rustls also accepts PKCS#1 and SEC1 keys.
+    pub identity: Option<Vec<u8>>,
+    /// The HTTP proxy the connection is tunneled through with `CONNECT` (e.g. `http://proxy.local:3128`).
+    pub proxy: Option<String>,
+    /// The heartbeat detecting dead peers, disabled when `None`.
+    pub heartbeat: Option<WebSocketHeartbeat>,
+}
+
+impl Default for WebSocketParams {
+    fn default() -> Self {
+        Self {
+            protocol: "janus-protocol".to_string(),
+            headers: Vec::new(),
+            root_certificates: Vec::new(),
+            identity: None,
+            proxy: None,
+            heartbeat: None,
+        }
+    }
+}
+
 pub struct ConnectionParams {
     /// The url of the janus server.
     pub url: String,
@@ -43,6 +138,19 @@ pub struct ConnectionParams {
     pub apisecret: Option<String>,
     /// The server root, it should match the server root of the janus server when choosing the restful interface.
     pub server_root: String,
+    /// The url of the janus WebSocket transport (for the hybrid interface).
+    ///
+    /// When set, the hybrid interface receives the events of every session over this single shared
+    /// WebSocket connection, otherwise it long-polls each session over HTTP.
+    pub events_url: Option<String>,
+    /// The long-polling parameters (for the restful and hybrid interfaces).
+    pub long_poll: LongPollParams,
+    /// The HTTP client parameters (for the restful and hybrid interfaces).
+    pub http_client: HttpClientParams,
+    /// The WebSocket connection parameters (for the websocket and hybrid interfaces).
+    pub websocket: WebSocketParams,
+    /// What happens to the messages that fail to parse.
+    pub parse_mode: ParseMode,
 }
 
 /// [`JanusInterface`] is the main trait that defines the interface for the janus server.
@@ -74,6 +182,21 @@ pub trait JanusInterface: Debug + MaybeSend + MaybeSync + 'static {
         timeout: Duration,
     ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error>;
 
+    /// Attaches a plugin to the session, tagging the handle with an opaque id (reported by
+    /// janus to event handlers and the admin API).
+    ///
+    /// Interfaces that don't support it attach the plugin without the opaque id.
+    async fn attach_with_opaque_id(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: String,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        tracing::warn!(opaque_id, "Opaque ids aren't supported by {}", self.name());
+        self.attach(session_id, plugin_id, timeout).await
+    }
+
     /// Indicates if the interface has keep alive messages.
     fn has_keep_alive(&self) -> bool;
 
@@ -139,6 +262,23 @@ pub trait JanusInterface: Debug + MaybeSend + MaybeSync + 'static {
     fn name(&self) -> Box<str> {
         "Janus Interface".to_string().into_boxed_str()
     }
+
+    /// Closes the connection to the server, e.g. with a WebSocket Close frame. The interface
+    /// can't be used afterwards.
+    async fn close(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Returns the group supervising the background tasks of the interface (demultiplexing,
+    /// long polling, ...), if it has any.
+    fn task_group(&self) -> Option<JaTaskGroup> {
+        None
+    }
+
+    /// Returns the parse mode of the interface and its count of parse failures.
+    fn parsing(&self) -> Parsing {
+        Parsing::default()
+    }
 }
 
 impl dyn JanusInterface {
@@ -1,7 +1,13 @@
+use crate::sdp::Sdp;
+use crate::Error;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::hash::Hash;
+use std::hash::Hasher;
 
 /// The top-level response
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -92,10 +98,24 @@ pub enum GenericEvent {
     Detached,
     /// The PeerConnection was closed, either by Janus or by the user/application, and as such cannot be used anymore.
     #[serde(rename = "hangup")]
-    Hangup,
+    Hangup {
+        /// Why the PeerConnection was closed, e.g. `DTLS alert` or `Close PC`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
     /// Whether Janus is receiving (receiving: true/false) audio/video (type: "audio/video") on this PeerConnection.
     #[serde(rename = "media")]
-    Media,
+    Media {
+        /// The stream the event is about, missing before Janus 1.0.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mid: Option<String>,
+        #[serde(rename = "type")]
+        media: String,
+        receiving: bool,
+        /// For how long no media was received, when `receiving` is false.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seconds: Option<u32>,
+    },
     #[serde(rename = "timeout")]
     Timeout,
     /// ICE and DTLS succeeded, and so Janus correctly established a PeerConnection with the user/application.
@@ -104,12 +124,33 @@ pub enum GenericEvent {
     /// Whether Janus is reporting trouble sending/receiving (uplink: true/false) media on this PeerConnection.
     #[serde(rename = "slowlink")]
     Slowlink {
+        /// The stream the event is about, missing before Janus 1.0.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mid: Option<String>,
         media: String,
         uplink: bool,
         lost: u32,
+        /// The NACKs received in the last second, reported by newer Janus versions.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        nacks: Option<u32>,
     },
+    /// A server-side ICE candidate, when Janus trickles its own candidates.
     #[serde(rename = "trickle")]
-    Trickle,
+    Trickle { candidate: TrickleCandidate },
+    /// A message that failed to parse, delivered as-is in [lenient](crate::parsing::ParseMode::Lenient) mode.
+    #[serde(rename = "unparsed", skip_deserializing)]
+    Unparsed { raw: JsonValue, error: String },
+}
+
+/// The candidate of a trickle event.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TrickleCandidate {
+    /// All the candidates were gathered.
+    Completed {
+        completed: bool,
+    },
+    Candidate(Candidate),
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
@@ -118,24 +159,98 @@ pub enum JsepType {
     Offer,
     #[serde(rename = "answer")]
     Answer,
+    #[serde(rename = "pranswer")]
+    Pranswer,
+    /// Rolls a pending renegotiation back, it carries no SDP.
+    #[serde(rename = "rollback")]
+    Rollback,
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub struct Jsep {
     #[serde(rename = "type")]
     pub jsep_type: JsepType,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub sdp: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub trickle: Option<bool>,
+    /// Whether the media is end-to-end encrypted (insertable streams).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e2ee: Option<bool>,
+    /// Simulcast hints for the plugins, e.g. the ssrcs or rids of the layers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulcast: Option<JsonValue>,
+    /// SVC hints for the plugins.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub svc: Option<JsonValue>,
+    /// Whether the JSEP is a renegotiation of an established PeerConnection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update: Option<bool>,
+    /// The fields jarust doesn't know about, kept so they round-trip.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, JsonValue>,
+}
+
+/// A free-form JSON value, hashed and ordered through its serialization so it can be embedded
+/// in the protocol types.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JsonValue(pub Value);
+
+impl Hash for JsonValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_string().hash(state);
+    }
+}
+
+impl PartialOrd for JsonValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for JsonValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.to_string().cmp(&other.0.to_string())
+    }
+}
+
+impl From<Value> for JsonValue {
+    fn from(value: Value) -> Self {
+        Self(value)
+    }
+}
+
+impl Jsep {
+    /// Parses the SDP.
+    #[allow(clippy::result_large_err)]
+    pub fn parse_sdp(&self) -> Result<Sdp, Error> {
+        Sdp::parse(&self.sdp)
+    }
+
+    /// Rewrites the SDP, e.g. `jsep.munge_sdp(|sdp| { sdp.force_codec("video", "VP8"); })`.
+    #[allow(clippy::result_large_err)]
+    pub fn munge_sdp(&mut self, munge: impl FnOnce(&mut Sdp)) -> Result<(), Error> {
+        let mut sdp = self.parse_sdp()?;
+        munge(&mut sdp);
+        self.sdp = sdp.to_string();
+        Ok(())
+    }
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub struct Candidate {
     #[serde(rename = "sdpMid")]
     pub sdp_mid: String,
     #[serde(rename = "sdpMLineIndex")]
     pub sdp_mline_index: u32,
     pub candidate: String,
+    #[serde(
+        rename = "usernameFragment",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub username_fragment: Option<String>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -193,6 +308,7 @@ pub struct MetaData {
 
 #[cfg(test)]
 mod tests {
+    use super::Candidate;
     use super::GenericEvent;
     use super::JaData;
     use super::JaHandleEvent;
@@ -200,8 +316,10 @@ mod tests {
     use super::JaSuccessProtocol;
     use super::Jsep;
     use super::JsepType;
+    use super::JsonValue;
     use super::PluginData;
     use super::ResponseType;
+    use super::TrickleCandidate;
     use crate::japrotocol::PluginInnerData;
     use serde_json::json;
 
@@ -291,6 +409,11 @@ mod tests {
                 sdp: "random_sdp".to_string(),
                 trickle: None,
                 jsep_type: JsepType::Answer,
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             }),
         };
         assert_eq!(actual_event, expected);
@@ -380,9 +503,11 @@ mod tests {
         let actual_event = serde_json::from_value::<JaResponse>(event).unwrap();
         let expected = JaResponse {
             janus: ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Slowlink {
+                mid: None,
                 media: "audio".to_string(),
                 uplink: true,
                 lost: 10,
+                nacks: None,
             })),
             transaction: None,
             sender: Some(2676358135723942u64),
@@ -391,4 +516,141 @@ mod tests {
         };
         assert_eq!(actual_event, expected);
     }
+
+    #[test]
+    fn it_round_trips_jsep_hints_and_unknown_fields() {
+        let jsep = json!({
+            "type": "offer",
+            "sdp": "random_sdp",
+            "e2ee": true,
+            "update": true,
+            "simulcast": { "rids": ["h", "l"] },
+            "custom": { "foo": "bar" }
+        });
+        let parsed = serde_json::from_value::<Jsep>(jsep.clone()).unwrap();
+        assert_eq!(parsed.e2ee, Some(true));
+        assert_eq!(parsed.update, Some(true));
+        assert_eq!(
+            parsed.simulcast,
+            Some(JsonValue(json!({ "rids": ["h", "l"] })))
+        );
+        assert_eq!(
+            parsed.extra.get("custom"),
+            Some(&JsonValue(json!({ "foo": "bar" })))
+        );
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), jsep);
+    }
+
+    #[test]
+    fn it_parse_rollback_jsep_without_sdp() {
+        let jsep = serde_json::from_value::<Jsep>(json!({ "type": "rollback" })).unwrap();
+        assert_eq!(jsep.jsep_type, JsepType::Rollback);
+        assert_eq!(
+            serde_json::to_value(&jsep).unwrap(),
+            json!({ "type": "rollback" })
+        );
+    }
+
+    #[test]
+    fn it_parse_slow_link_event_with_mid_and_nacks() {
+        let event = json!({
+            "janus": "slowlink",
+            "sender": 2676358135723942u64,
+            "mid": "1",
+            "media": "video",
+            "uplink": false,
+            "lost": 3,
+            "nacks": 12
+        });
+        let actual_event = serde_json::from_value::<JaResponse>(event).unwrap();
+        assert_eq!(
+            actual_event.janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Slowlink {
+                mid: Some("1".to_string()),
+                media: "video".to_string(),
+                uplink: false,
+                lost: 3,
+                nacks: Some(12),
+            }))
+        );
+    }
+
+    #[test]
+    fn it_parse_media_event() {
+        let event = json!({
+            "janus": "media",
+            "session_id": 1942958911060866u64,
+            "sender": 2676358135723942u64,
+            "mid": "0",
+            "type": "audio",
+            "receiving": false,
+            "seconds": 5
+        });
+        let actual_event = serde_json::from_value::<JaResponse>(event).unwrap();
+        assert_eq!(
+            actual_event.janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Media {
+                mid: Some("0".to_string()),
+                media: "audio".to_string(),
+                receiving: false,
+                seconds: Some(5),
+            }))
+        );
+    }
+
+    #[test]
+    fn it_parse_hangup_event() {
+        let event = json!({
+            "janus": "hangup",
+            "session_id": 1942958911060866u64,
+            "sender": 2676358135723942u64,
+            "reason": "DTLS alert"
+        });
+        let actual_event = serde_json::from_value::<JaResponse>(event).unwrap();
+        assert_eq!(
+            actual_event.janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Hangup {
+                reason: Some("DTLS alert".to_string()),
+            }))
+        );
+    }
+
+    #[test]
+    fn it_parse_trickle_events() {
+        let event = json!({
+            "janus": "trickle",
+            "session_id": 1942958911060866u64,
+            "sender": 2676358135723942u64,
+            "candidate": {
+                "sdpMid": "0",
+                "sdpMLineIndex": 0,
+                "candidate": "candidate:1 1 udp 2015363327 10.0.0.1 45124 typ host"
+            }
+        });
+        let actual_event = serde_json::from_value::<JaResponse>(event).unwrap();
+        assert_eq!(
+            actual_event.janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Trickle {
+                candidate: TrickleCandidate::Candidate(Candidate {
+                    sdp_mid: "0".to_string(),
+                    sdp_mline_index: 0,
+                    candidate: "candidate:1 1 udp 2015363327 10.0.0.1 45124 typ host".to_string(),
+                    username_fragment: None,
+                }),
+            }))
+        );
+
+        let event = json!({
+            "janus": "trickle",
+            "sender": 2676358135723942u64,
+            "candidate": { "completed": true }
+        });
+        let actual_event = serde_json::from_value::<JaResponse>(event).unwrap();
+        assert_eq!(
+            actual_event.janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Trickle {
+                candidate: TrickleCandidate::Completed { completed: true },
+            }))
+        );
+    }
 }
@@ -0,0 +1,410 @@
+//! Middleware stacked around a [`JanusInterface`], to hook cross-cutting behaviour (logging,
+//! secret injection, metrics, retries, rate limiting, fault injection, ...) without
+//! reimplementing the whole interface.
+//!
+//! Every request of the interface is reified as a [`Call`], which a [`Middleware`] may inspect,
+//! rewrite, retry or answer itself before dispatching it to the interface it wraps.
+//!
+//! ```rust,ignore
+//! #[derive(Debug)]
+//! struct Logging;
+//!
+//! #[async_trait::async_trait]
+//! impl Middleware for Logging {
+//!     async fn call(&self, call: Call, inner: &dyn JanusInterface) -> Result<Reply, Error> {
+//!         let name = call.name();
+//!         let result = call.dispatch(inner).await;
+//!         tracing::info!(name, ok = result.is_ok(), "Janus request");
+//!         result
+//!     }
+//! }
+//!
+//! let interface = WebSocketInterface::make_interface(conn_params, generator)
+//!     .await?
+//!     .layer(Logging)
+//!     .layer(Metrics::default());
+//! let connection = jarust_core::custom_connect(interface).await?;
+//! ```
+
+use crate::handle_msg::HandleMessage;
+use crate::handle_msg::HandleMessageWithJsep;
+use crate::janus_interface::ConnectionParams;
+use crate::janus_interface::JanusInterface;
+use crate::janus_interface::MaybeSend;
+use crate::janus_interface::MaybeSync;
+use crate::japrotocol::JaResponse;
+use crate::japrotocol::ServerInfoRsp;
+use crate::parsing::Parsing;
+use crate::tgenerator::GenerateTransaction;
+use crate::Error;
+use jarust_rt::sync::mpsc;
+use jarust_rt::JaTaskGroup;
+use std::fmt::Debug;
+use std::time::Duration;
+
+pub mod retry;
+
+pub use retry::Retry;
+pub use retry::RetryPolicy;
+
+/// A request to a [`JanusInterface`], one variant per method sending a request.
+#[derive(Debug, Clone)]
+pub enum Call {
+    Create {
+        timeout: Duration,
+    },
+    ServerInfo {
+        timeout: Duration,
+    },
+    Attach {
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: Option<String>,
+        timeout: Duration,
+    },
+    KeepAlive {
+        session_id: u64,
+        timeout: Duration,
+    },
+    Destroy {
+        session_id: u64,
+        timeout: Duration,
+    },
+    FireAndForget {
+        message: HandleMessage,
+    },
+    SendWaitonAck {
+        message: HandleMessage,
+        timeout: Duration,
+    },
+    SendWaitonRsp {
+        message: HandleMessage,
+        timeout: Duration,
+    },
+    FireAndForgetWithJsep {
+        message: HandleMessageWithJsep,
+    },
+    SendWaitonAckWithJsep {
+        message: HandleMessageWithJsep,
+        timeout: Duration,
+    },
+    HandleRequest {
+        request: HandleMessage,
+    },
+    HandleRequestWaitonAck {
+        request: HandleMessage,
+        timeout: Duration,
+    },
+}
+
+impl Call {
+    /// The name of the call, e.g. for logs and metrics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Call::Create { .. } => "create",
+            Call::ServerInfo { .. } => "server_info",
+            Call::Attach { .. } => "attach",
+            Call::KeepAlive { .. } => "keep_alive",
+            Call::Destroy { .. } => "destroy",
+            Call::FireAndForget { .. } => "fire_and_forget_msg",
+            Call::SendWaitonAck { .. } => "send_msg_waiton_ack",
+            Call::SendWaitonRsp { .. } => "send_msg_waiton_rsp",
+            Call::FireAndForgetWithJsep { .. } => "fire_and_forget_msg_with_jsep",
+            Call::SendWaitonAckWithJsep { .. } => "send_msg_waiton_ack_with_jsep",
+            Call::HandleRequest { .. } => "send_handle_request",
+            Call::HandleRequestWaitonAck { .. } => "send_handle_request_waiton_ack",
+        }
+    }
+
+    /// Sends the call over `interface`.
+    pub async fn dispatch(self, interface: &dyn JanusInterface) -> Result<Reply, Error> {
+        let reply = match self {
+            Call::Create { timeout } => Reply::Session(interface.create(timeout).await?),
+            Call::ServerInfo { timeout } => {
+                Reply::ServerInfo(Box::new(interface.server_info(timeout).await?))
+            }
+            Call::Attach {
+                session_id,
+                plugin_id,
+                opaque_id,
+                timeout,
+            } => {
+                let (handle_id, receiver) = match opaque_id {
+                    Some(opaque_id) => {
+                        interface
+                            .attach_with_opaque_id(session_id, plugin_id, opaque_id, timeout)
+                            .await?
+                    }
+                    None => interface.attach(session_id, plugin_id, timeout).await?,
+                };
+                Reply::Attached {
+                    handle_id,
+                    receiver,
+                }
+            }
+            Call::KeepAlive {
+                session_id,
+                timeout,
+            } => {
+                interface.keep_alive(session_id, timeout).await?;
+                Reply::Done
+            }
+            Call::Destroy {
+                session_id,
+                timeout,
+            } => {
+                interface.destroy(session_id, timeout).await?;
+                Reply::Done
+            }
+            Call::FireAndForget { message } => {
+                Reply::Transaction(interface.fire_and_forget_msg(message).await?)
+            }
+            Call::SendWaitonAck { message, timeout } => {
+                Reply::Transaction(interface.send_msg_waiton_ack(message, timeout).await?)
+            }
+            Call::SendWaitonRsp { message, timeout } => Reply::Response(Box::new(
+                interface
+                    .internal_send_msg_waiton_rsp(message, timeout)
+                    .await?,
+            )),
+            Call::FireAndForgetWithJsep { message } => {
+                Reply::Transaction(interface.fire_and_forget_msg_with_jsep(message).await?)
+            }
+            Call::SendWaitonAckWithJsep { message, timeout } => Reply::Transaction(
+                interface
+                    .send_msg_waiton_ack_with_jsep(message, timeout)
+                    .await?,
+            ),
+            Call::HandleRequest { request } => {
+                interface.send_handle_request(request).await?;
+                Reply::Done
+            }
+            Call::HandleRequestWaitonAck { request, timeout } => Reply::Transaction(
+                interface
+                    .send_handle_request_waiton_ack(request, timeout)
+                    .await?,
+            ),
+        };
+        Ok(reply)
+    }
+}
+
+/// The reply to a [`Call`].
+#[derive(Debug)]
+pub enum Reply {
+    /// The id of the created session.
+    Session(u64),
+    /// The server info.
+    // Boxed so the entire enum isn't as large as this variant
+    ServerInfo(Box<ServerInfoRsp>),
+    /// The id of the attached handle and its events.
+    Attached {
+        handle_id: u64,
+        receiver: mpsc::UnboundedReceiver<JaResponse>,
+    },
+    /// The transaction of the sent message.
+    Transaction(String),
+    /// The response to the sent message.
+    // Boxed so the entire enum isn't as large as this variant
+    Response(Box<JaResponse>),
+    /// The call doesn't return anything.
+    Done,
+}
+
+/// A wrapper around the calls of an interface.
+///
+/// Replying with a [`Reply`] variant that doesn't match the [`Call`] fails the call with
+/// [`Error::UnexpectedResponse`].
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+pub trait Middleware: Debug + MaybeSend + MaybeSync + 'static {
+    /// Handles a call, usually by dispatching it to `inner` with [`Call::dispatch`].
+    async fn call(&self, call: Call, inner: &dyn JanusInterface) -> Result<Reply, Error>;
+}
+
+/// An interface wrapped by a [`Middleware`], itself an interface to stack other layers on
+/// or hand to `jarust_core::custom_connect`.
+#[derive(Debug)]
+pub struct Layered<I, M> {
+    inner: I,
+    middleware: M,
+}
+
+impl<I, M> Layered<I, M> {
+    pub fn new(inner: I, middleware: M) -> Self {
+        Self { inner, middleware }
+    }
+
+    /// The wrapped interface.
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    pub fn middleware(&self) -> &M {
+        &self.middleware
+    }
+}
+
+/// Stacks middleware around an interface, the last layer added is the outermost.
+pub trait LayerExt: JanusInterface + Sized {
+    fn layer<M: Middleware>(self, middleware: M) -> Layered<Self, M> {
+        Layered::new(self, middleware)
+    }
+}
+
+impl<I: JanusInterface> LayerExt for I {}
+
+macro_rules! forward {
+    ($self:ident, $call:expr, $reply:pat => $value:expr) => {{
+        let call = $call;
+        let name = call.name();
+        match $self.middleware.call(call, &$self.inner).await? {
+            $reply => Ok($value),
+            reply => {
+                tracing::error!("Unexpected reply to {name}: {reply:?}");
+                Err(Error::UnexpectedResponse)
+            }
+        }
+    }};
+}
+
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+impl<I: JanusInterface, M: Middleware> JanusInterface for Layered<I, M> {
+    /// Layers wrap an existing interface, see [`LayerExt::layer`].
+    async fn make_interface(
+        _: ConnectionParams,
+        _: impl GenerateTransaction,
+    ) -> Result<Self, Error> {
+        Err(Error::Unsupported {
+            reason: "a layered interface wraps an existing interface".to_string(),
+        })
+    }
+
+    async fn create(&self, timeout: Duration) -> Result<u64, Error> {
+        forward!(self, Call::Create { timeout }, Reply::Session(id) => id)
+    }
+
+    async fn server_info(&self, timeout: Duration) -> Result<ServerInfoRsp, Error> {
+        forward!(self, Call::ServerInfo { timeout }, Reply::ServerInfo(info) => *info)
+    }
+
+    async fn attach(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        let call = Call::Attach {
+            session_id,
+            plugin_id,
+            opaque_id: None,
+            timeout,
+        };
+        forward!(self, call, Reply::Attached { handle_id, receiver } => (handle_id, receiver))
+    }
+
+    async fn attach_with_opaque_id(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: String,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        let call = Call::Attach {
+            session_id,
+            plugin_id,
+            opaque_id: Some(opaque_id),
+            timeout,
+        };
+        forward!(self, call, Reply::Attached { handle_id, receiver } => (handle_id, receiver))
+    }
+
+    fn has_keep_alive(&self) -> bool {
+        self.inner.has_keep_alive()
+    }
+
+    async fn keep_alive(&self, session_id: u64, timeout: Duration) -> Result<(), Error> {
+        let call = Call::KeepAlive {
+            session_id,
+            timeout,
+        };
+        forward!(self, call, Reply::Done => ())
+    }
+
+    async fn destroy(&self, session_id: u64, timeout: Duration) -> Result<(), Error> {
+        let call = Call::Destroy {
+            session_id,
+            timeout,
+        };
+        forward!(self, call, Reply::Done => ())
+    }
+
+    async fn fire_and_forget_msg(&self, message: HandleMessage) -> Result<String, Error> {
+        forward!(self, Call::FireAndForget { message }, Reply::Transaction(transaction) => transaction)
+    }
+
+    async fn send_msg_waiton_ack(
+        &self,
+        message: HandleMessage,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        let call = Call::SendWaitonAck { message, timeout };
+        forward!(self, call, Reply::Transaction(transaction) => transaction)
+    }
+
+    async fn internal_send_msg_waiton_rsp(
+        &self,
+        message: HandleMessage,
+        timeout: Duration,
+    ) -> Result<JaResponse, Error> {
+        let call = Call::SendWaitonRsp { message, timeout };
+        forward!(self, call, Reply::Response(response) => *response)
+    }
+
+    async fn fire_and_forget_msg_with_jsep(
+        &self,
+        message: HandleMessageWithJsep,
+    ) -> Result<String, Error> {
+        let call = Call::FireAndForgetWithJsep { message };
+        forward!(self, call, Reply::Transaction(transaction) => transaction)
+    }
+
+    async fn send_msg_waiton_ack_with_jsep(
+        &self,
+        message: HandleMessageWithJsep,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        let call = Call::SendWaitonAckWithJsep { message, timeout };
+        forward!(self, call, Reply::Transaction(transaction) => transaction)
+    }
+
+    async fn send_handle_request(&self, request: HandleMessage) -> Result<(), Error> {
+        forward!(self, Call::HandleRequest { request }, Reply::Done => ())
+    }
+
+    async fn send_handle_request_waiton_ack(
+        &self,
+        request: HandleMessage,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        let call = Call::HandleRequestWaitonAck { request, timeout };
+        forward!(self, call, Reply::Transaction(transaction) => transaction)
+    }
+
+    fn name(&self) -> Box<str> {
+        self.inner.name()
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        self.inner.close().await
+    }
+
+    fn task_group(&self) -> Option<JaTaskGroup> {
+        self.inner.task_group()
+    }
+
+    fn parsing(&self) -> Parsing {
+        self.inner.parsing()
+    }
+}
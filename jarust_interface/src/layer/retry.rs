@@ -0,0 +1,226 @@
+//! Retries the idempotent calls failing with a transient error.
+//!
+//! Every attempt is dispatched to the wrapped interface as a new request, with a fresh
+//! transaction and the full timeout of the call.
+
+use super::Call;
+use super::Middleware;
+use super::Reply;
+use crate::janus_interface::JanusInterface;
+use crate::Error;
+use rand::Rng;
+use std::time::Duration;
+
+/// Which calls are retried, on which errors and how often.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts of a call, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry, doubled on every retry.
+    pub initial_backoff: Duration,
+    /// The maximum delay between two attempts.
+    pub max_backoff: Duration,
+    /// Whether to retry the calls that timed out.
+    pub retry_timeouts: bool,
+    /// Whether to retry the calls that failed to reach janus (e.g. a dropped socket).
+    pub retry_transport_errors: bool,
+    /// The janus error codes to retry, e.g. 490 for transport specific errors.
+    pub retryable_codes: Vec<u16>,
+    /// The plugin requests (the `request` of the message body) that are safe to send twice,
+    /// e.g. `list` or `exists`.
+    ///
+    /// `server_info` is always idempotent, sessions and handles are never created, destroyed
+    /// or hung up twice. Keep-alives aren't retried either, the keep-alive scheduler has its own
+    /// failure policy.
+    pub idempotent_requests: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            retry_timeouts: true,
+            retry_transport_errors: true,
+            retryable_codes: vec![490],
+            idempotent_requests: [
+                "list",
+                "exists",
+                "info",
+                "listparticipants",
+                "listforwarders",
+                "listannouncements",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether the call is safe to send more than once.
+    pub fn is_idempotent(&self, call: &Call) -> bool {
+        let message = match call {
+            Call::ServerInfo { .. } => return true,
+            Call::FireAndForget { message }
+            | Call::SendWaitonAck { message, .. }
+            | Call::SendWaitonRsp { message, .. } => message,
+            _ => return false,
+        };
+        message.body["request"]
+            .as_str()
+            .is_some_and(|request| self.idempotent_requests.iter().any(|r| r == request))
+    }
+
+    /// Whether the error is transient, so the call may succeed on a retry.
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        match error {
+            Error::RequestTimeout => self.retry_timeouts,
+            Error::JanusError { code, .. } => self.retryable_codes.contains(code),
+            Error::TransportNotOpened
+            | Error::SendError
+            | Error::IncompletePacket
+            | Error::WebSocket(_)
+            | Error::IO(_)
+            | Error::Reqwest(_) => self.retry_transport_errors,
+            #[cfg(all(not(target_family = "wasm"), feature = "socketio"))]
+            Error::SocketIo(_) => self.retry_transport_errors,
+            _ => false,
+        }
+    }
+
+    /// The delay before the given retry (starting at 1), give or take 10% so the retries of
+    /// calls failing together are spread.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.9..1.1))
+    }
+}
+
+/// A [`Middleware`] retrying the calls according to a [`RetryPolicy`].
+#[derive(Clone, Debug, Default)]
+pub struct Retry {
+    policy: RetryPolicy,
+}
+
+impl Retry {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self { policy }
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+}
+
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+impl Middleware for Retry {
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all, fields(call = call.name()))]
+    async fn call(&self, call: Call, inner: &dyn JanusInterface) -> Result<Reply, Error> {
+        if !self.policy.is_idempotent(&call) {
+            return call.dispatch(inner).await;
+        }
+        let mut attempt = 1;
+        loop {
+            match call.clone().dispatch(inner).await {
+                Err(error)
+                    if attempt < self.policy.max_attempts && self.policy.is_retryable(&error) =>
+                {
+                    let backoff = self.policy.backoff(attempt);
+                    tracing::warn!(
+                        "Attempt {attempt} failed with {error}, retrying in {backoff:?}"
+                    );
+                    jarust_rt::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use crate::handle_msg::HandleMessage;
+    use crate::layer::Call;
+    use crate::Error;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn message(body: serde_json::Value) -> HandleMessage {
+        HandleMessage {
+            session_id: 1,
+            handle_id: 2,
+            body,
+        }
+    }
+
+    #[test]
+    fn it_should_only_retry_idempotent_calls() {
+        let policy = RetryPolicy::default();
+        let timeout = Duration::from_secs(1);
+
+        assert!(policy.is_idempotent(&Call::ServerInfo { timeout }));
+        assert!(policy.is_idempotent(&Call::SendWaitonRsp {
+            message: message(json!({ "request": "list" })),
+            timeout,
+        }));
+        assert!(!policy.is_idempotent(&Call::SendWaitonRsp {
+            message: message(json!({ "request": "create" })),
+            timeout,
+        }));
+        assert!(!policy.is_idempotent(&Call::Create { timeout }));
+        assert!(!policy.is_idempotent(&Call::KeepAlive {
+            session_id: 1,
+            timeout,
+        }));
+        assert!(!policy.is_idempotent(&Call::HandleRequest {
+            request: message(json!({ "janus": "hangup" })),
+        }));
+    }
+
+    #[test]
+    fn it_should_only_retry_transient_errors() {
+        let policy = RetryPolicy {
+            retry_timeouts: false,
+            ..Default::default()
+        };
+
+        assert!(policy.is_retryable(&Error::TransportNotOpened));
+        assert!(policy.is_retryable(&Error::JanusError {
+            code: 490,
+            reason: "Transport error".to_string(),
+        }));
+        assert!(!policy.is_retryable(&Error::JanusError {
+            code: 458,
+            reason: "No such session".to_string(),
+        }));
+        assert!(!policy.is_retryable(&Error::RequestTimeout));
+        assert!(!policy.is_retryable(&Error::UnexpectedResponse));
+    }
+
+    #[test]
+    fn it_should_back_off_exponentially_up_to_the_max() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            ..Default::default()
+        };
+
+        let within = |backoff: Duration, expected: u64| {
+            let expected = Duration::from_millis(expected);
+            backoff >= expected.mul_f64(0.9) && backoff <= expected.mul_f64(1.1)
+        };
+        assert!(within(policy.backoff(1), 100));
+        assert!(within(policy.backoff(2), 200));
+        assert!(within(policy.backoff(3), 400));
+        assert!(within(policy.backoff(4), 500));
+        assert!(within(policy.backoff(40), 500));
+    }
+}
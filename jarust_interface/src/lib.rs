@@ -2,19 +2,31 @@
 //!
 //! Jarust interface contains:
 //!
-//! - Transport abstraction, you can use the built-in WebSocket interface, restful interface, or bring your own.
+//! - Transport abstraction, you can use the built-in WebSocket interface, restful interface, hybrid interface, or bring your own.
+//! - Message transport abstraction, plug your own framing into the streaming interface without reimplementing the janus api.
 //! - Transaction generation abstraction, you can use the built-in transaction generator or bring your own.
 //! - DTOs for the Janus API.
+//! - A lossless SDP model with munging helpers.
+//! - Strict or lenient handling of the messages that fail to parse.
+//! - Middleware layers stacked around any interface, to hook logging, metrics, retries, ...
+//! - A sans-IO core of the janus protocol, to drive it from any runtime or a synchronous program.
 //! - Errors
 //!
 
 pub mod error;
 pub mod handle_msg;
+#[cfg(not(target_family = "wasm"))]
+pub mod hybrid;
 pub mod janus_interface;
 pub mod japrotocol;
+pub mod layer;
+pub mod parsing;
+pub mod protocol;
 pub mod restful;
+pub mod sdp;
 #[cfg(all(not(target_family = "wasm"), feature = "socketio"))]
 pub mod socketio;
+pub mod streaming;
 pub mod tgenerator;
 pub mod websocket;
 
@@ -0,0 +1,206 @@
+//! Handling of the messages that fail to parse.
+//!
+//! Janus releases regularly add fields and events, in [`ParseMode::Strict`] mode such messages
+//! are logged and dropped, in [`ParseMode::Lenient`] mode they're delivered as
+//! [`GenericEvent::Unparsed`] events. Either way they're counted.
+
+use crate::japrotocol::GenericEvent;
+use crate::japrotocol::JaHandleEvent;
+use crate::japrotocol::JaResponse;
+use crate::japrotocol::ResponseType;
+use serde_json::Value;
+use std::fmt::Display;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// What happens to the messages that fail to parse.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum ParseMode {
+    /// Log and drop them.
+    #[default]
+    Strict,
+    /// Deliver them as [`GenericEvent::Unparsed`] events.
+    Lenient,
+}
+
+/// The parse mode of a connection and its count of parse failures, shared by the interface,
+/// the handles and the plugins.
+#[derive(Clone, Debug, Default)]
+pub struct Parsing {
+    mode: ParseMode,
+    failures: Arc<AtomicU64>,
+}
+
+impl Parsing {
+    pub fn new(mode: ParseMode) -> Self {
+        Self {
+            mode,
+            failures: Arc::default(),
+        }
+    }
+
+    pub fn mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    /// Returns the number of messages that failed to parse so far.
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Counts a message that failed to parse, returning it as an event in lenient mode.
+    pub fn unparsed(&self, raw: Value, error: impl Display) -> Option<GenericEvent> {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        match self.mode {
+            ParseMode::Strict => {
+                tracing::error!("Dropping unparsed message: {error}");
+                None
+            }
+            ParseMode::Lenient => {
+                tracing::warn!("Delivering unparsed message: {error}");
+                Some(GenericEvent::Unparsed {
+                    raw: raw.into(),
+                    error: error.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Same as [`unparsed`](Self::unparsed), wrapped in a response carrying the routing fields
+    /// (transaction, session and sender) of the raw message.
+    pub fn unparsed_response(&self, raw: Value, error: impl Display) -> Option<JaResponse> {
+        let transaction = raw["transaction"].as_str().map(ToString::to_string);
+        let session_id = raw["session_id"].as_u64();
+        let sender = raw["sender"].as_u64();
+        let event = self.unparsed(raw, error)?;
+        Some(JaResponse {
+            janus: ResponseType::Event(JaHandleEvent::GenericEvent(event)),
+            transaction,
+            session_id,
+            sender,
+            jsep: None,
+        })
+    }
+
+    /// Converts a response into a plugin event, counting the failures and delivering them as
+    /// [`GenericEvent::Unparsed`] events in lenient mode.
+    ///
+    /// The response is borrowed, so it's only serialized back for the events failing to convert.
+    pub fn convert<E>(&self, response: JaResponse) -> Option<E>
+    where
+        E: for<'a> TryFrom<&'a JaResponse> + From<GenericEvent>,
+        for<'a> <E as TryFrom<&'a JaResponse>>::Error: Display,
+    {
+        match E::try_from(&response) {
+            Ok(event) => Some(event),
+            Err(err) => {
+                let raw = match self.mode {
+                    ParseMode::Strict => Value::Null,
+                    ParseMode::Lenient => serde_json::to_value(&response).unwrap_or_default(),
+                };
+                self.unparsed(raw, err).map(E::from)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParseMode;
+    use super::Parsing;
+    use crate::japrotocol::GenericEvent;
+    use crate::japrotocol::JaHandleEvent;
+    use crate::japrotocol::JaResponse;
+    use crate::japrotocol::ResponseType;
+    use serde_json::json;
+
+    /// A plugin event only converting from generic events.
+    #[derive(Debug, PartialEq)]
+    struct Generic(GenericEvent);
+
+    impl TryFrom<&JaResponse> for Generic {
+        type Error = &'static str;
+
+        fn try_from(value: &JaResponse) -> Result<Self, Self::Error> {
+            match &value.janus {
+                ResponseType::Event(JaHandleEvent::GenericEvent(event)) => Ok(Self(event.clone())),
+                _ => Err("not an event"),
+            }
+        }
+    }
+
+    impl From<GenericEvent> for Generic {
+        fn from(value: GenericEvent) -> Self {
+            Self(value)
+        }
+    }
+
+    #[test]
+    fn it_should_count_and_drop_in_strict_mode() {
+        let parsing = Parsing::default();
+        let shared = parsing.clone();
+        assert_eq!(parsing.unparsed_response(json!({}), "unknown event"), None);
+        assert_eq!(shared.failures(), 1);
+    }
+
+    #[test]
+    fn it_should_deliver_routable_events_in_lenient_mode() {
+        let parsing = Parsing::new(ParseMode::Lenient);
+        let raw = json!({
+            "janus": "future_event",
+            "session_id": 1,
+            "sender": 2,
+            "transaction": "abc"
+        });
+        let response = parsing
+            .unparsed_response(raw.clone(), "unknown variant `future_event`")
+            .unwrap();
+        assert_eq!(response.session_id, Some(1));
+        assert_eq!(response.sender, Some(2));
+        assert_eq!(response.transaction, Some("abc".to_string()));
+        assert_eq!(
+            response.janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Unparsed {
+                raw: raw.into(),
+                error: "unknown variant `future_event`".to_string()
+            }))
+        );
+        assert_eq!(parsing.failures(), 1);
+    }
+
+    #[test]
+    fn it_should_only_serialize_the_responses_failing_to_convert() {
+        let parsing = Parsing::new(ParseMode::Lenient);
+        let event = JaResponse {
+            janus: ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Detached)),
+            transaction: None,
+            session_id: Some(1),
+            sender: Some(2),
+            jsep: None,
+        };
+        let ack = JaResponse {
+            janus: ResponseType::Ack,
+            transaction: Some("abc".to_string()),
+            session_id: Some(1),
+            sender: None,
+            jsep: None,
+        };
+
+        assert_eq!(
+            parsing.convert::<Generic>(event),
+            Some(Generic(GenericEvent::Detached))
+        );
+        assert_eq!(parsing.failures(), 0);
+
+        let raw = serde_json::to_value(&ack).unwrap();
+        assert_eq!(
+            parsing.convert::<Generic>(ack),
+            Some(Generic(GenericEvent::Unparsed {
+                raw: raw.into(),
+                error: "not an event".to_string()
+            }))
+        );
+        assert_eq!(parsing.failures(), 1);
+    }
+}
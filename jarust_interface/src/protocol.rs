@@ -0,0 +1,419 @@
+//! Sans-IO core of the janus protocol.
+//!
+//! [`JanusProtocol`] decorates the requests, tracks their transactions and classifies the
+//! inbound messages into replies and routed events, without doing any IO itself: the caller
+//! writes the bytes [`send`](JanusProtocol::send) returns and [`feed`](JanusProtocol::feed)s
+//! the bytes it reads. The streaming and hybrid interfaces drive it from async tasks, it can be
+//! driven the same way from any runtime, a synchronous program, an FFI layer or a
+//! deterministic test.
+//!
+//! [`feed`](JanusProtocol::feed) is [`decode`](JanusProtocol::decode), which doesn't need the
+//! protocol state, followed by [`classify`](JanusProtocol::classify). Drivers sharing the
+//! protocol between tasks decode outside of their lock, so large events don't hold back the
+//! requests.
+//!
+//! ```rust,ignore
+//! let mut protocol = JanusProtocol::new(ProtocolParams {
+//!     server_root: "janus".to_string(),
+//!     apisecret: None,
+//!     capacity: 32,
+//!     parsing: Parsing::default(),
+//! }, transaction_generator);
+//! let request = protocol.send(json!({ "janus": "create" }), Some(Expect::Response));
+//! socket.write(&request.data)?;
+//! for output in protocol.feed(&socket.read()?) {
+//!     match output {
+//!         Output::Reply { transaction, response } => { /* complete the request */ }
+//!         Output::Event { path, event } => { /* deliver to the handle at `path` */ }
+//!         Output::Unhandled(_) => {}
+//!     }
+//! }
+//! ```
+
+use crate::japrotocol::JaResponse;
+use crate::japrotocol::ResponseType;
+use crate::parsing::Parsing;
+use crate::tgenerator::TransactionGenerator;
+use crate::transport::ringbuf_map::RingBufMap;
+use bytes::Bytes;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The reply a request waits on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Expect {
+    /// A `success` or `server_info` response.
+    Response,
+    /// An `ack`.
+    Ack,
+}
+
+/// A request ready to be written.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Request {
+    pub transaction: String,
+    /// The route of the request (`{session_id}/{handle_id}`, `{session_id}` or the server root),
+    /// for transports that multiplex on it.
+    pub path: String,
+    /// The JSON encoded request.
+    pub data: Bytes,
+}
+
+/// What an inbound message carries.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Output {
+    /// The reply to a request waiting on it: its response, ack or error. The request isn't
+    /// pending anymore.
+    Reply {
+        transaction: String,
+        response: JaResponse,
+    },
+    /// An event for the handle (`{session_id}/{handle_id}`) or session (`{session_id}`) at
+    /// `path`.
+    Event { path: String, event: JaResponse },
+    /// A reply no request waits on (e.g. it timed out), or an event without a route.
+    Unhandled(JaResponse),
+}
+
+#[derive(Debug)]
+pub struct ProtocolParams {
+    /// The root of the routes, the path of the requests outside of a session.
+    pub server_root: String,
+    pub apisecret: Option<String>,
+    /// The number of transactions whose route is remembered, for the events that only carry
+    /// the transaction of the request they answer.
+    pub capacity: usize,
+    pub parsing: Parsing,
+}
+
+/// The state machine of the janus protocol for a single connection.
+#[derive(Debug)]
+pub struct JanusProtocol {
+    server_root: String,
+    apisecret: Option<String>,
+    transaction_generator: TransactionGenerator,
+    parsing: Parsing,
+    /// The routes of the requests sent, by transaction.
+    routes: RingBufMap<String, String>,
+    /// The requests waiting on a reply, by transaction.
+    pending: HashMap<String, Expect>,
+}
+
+impl JanusProtocol {
+    pub fn new(params: ProtocolParams, transaction_generator: TransactionGenerator) -> Self {
+        Self {
+            server_root: params.server_root,
+            apisecret: params.apisecret,
+            transaction_generator,
+            parsing: params.parsing,
+            routes: RingBufMap::new(params.capacity),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Decorates the request with the `apisecret` and a new transaction, returning it encoded.
+    ///
+    /// With `expect`, the request is pending until its reply is fed or it's
+    /// [`cancel`](Self::cancel)led.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    pub fn send(&mut self, mut request: Value, expect: Option<Expect>) -> Request {
+        let transaction = self.transaction_generator.generate_transaction();
+        if let Some(apisecret) = &self.apisecret {
+            request["apisecret"] = apisecret.as_str().into();
+        }
+        request["transaction"] = transaction.as_str().into();
+
+        let path = path_from_request(&request).unwrap_or_else(|| self.server_root.clone());
+        self.routes.put(transaction.clone(), path.clone());
+        if let Some(expect) = expect {
+            self.pending.insert(transaction.clone(), expect);
+        }
+        tracing::trace!("Sending {request:#?}");
+        Request {
+            transaction,
+            path,
+            data: request.to_string().into(),
+        }
+    }
+
+    /// Forgets the pending request, e.g. once it timed out. Its reply is then unhandled.
+    pub fn cancel(&mut self, transaction: &str) {
+        self.pending.remove(transaction);
+    }
+
+    /// Forgets every pending request, e.g. once the connection is lost.
+    pub fn cancel_all(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Returns the number of requests waiting on a reply.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Feeds an inbound message (a JSON encoded janus message or an array of them).
+    ///
+    /// Messages failing to parse are handled according to the parse mode, `keepalive`s are
+    /// skipped.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Output> {
+        Self::decode(&self.parsing, data)
+            .into_iter()
+            .map(|response| self.classify(response))
+            .collect()
+    }
+
+    /// Returns the parse mode of the connection, which [`decode`](Self::decode) takes.
+    pub fn parsing(&self) -> &Parsing {
+        &self.parsing
+    }
+
+    /// Decodes an inbound message (a JSON encoded janus message or an array of them) into its
+    /// responses, to [`classify`](Self::classify) next.
+    ///
+    /// Messages failing to parse are handled according to the parse mode, `keepalive`s are
+    /// skipped.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    pub fn decode(parsing: &Parsing, data: &[u8]) -> Vec<JaResponse> {
+        let Ok(text) = std::str::from_utf8(data) else {
+            tracing::error!("Incomplete packet received");
+            return vec![];
+        };
+        tracing::trace!("Received {text}");
+
+        let error = match serde_json::from_str::<JaResponse>(text) {
+            Ok(response) => return vec![response],
+            Err(error) => error,
+        };
+        let raw = serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()));
+        let messages = match raw {
+            Value::Array(messages) => messages,
+            raw => return unparsed(parsing, raw, error).into_iter().collect(),
+        };
+        let mut responses = Vec::with_capacity(messages.len());
+        for message in messages {
+            if message["janus"] == "keepalive" {
+                continue;
+            }
+            match JaResponse::deserialize(&message) {
+                Ok(response) => responses.push(response),
+                Err(error) => responses.extend(unparsed(parsing, message, error)),
+            }
+        }
+        responses
+    }
+
+    /// Classifies a decoded response into the reply of a pending request or a routed event.
+    pub fn classify(&mut self, response: JaResponse) -> Output {
+        let expect = match &response.janus {
+            ResponseType::Event(_) => return self.route(response),
+            ResponseType::Error { error } => {
+                tracing::error!("{error:#?}");
+                // An error answers any request
+                None
+            }
+            ResponseType::Ack => Some(Expect::Ack),
+            ResponseType::Success(_) | ResponseType::ServerInfo(_) => Some(Expect::Response),
+        };
+        let Some(transaction) = response.transaction.clone() else {
+            return Output::Unhandled(response);
+        };
+        match self.pending.get(&transaction) {
+            Some(pending) if expect.is_none_or(|expect| expect == *pending) => {
+                self.pending.remove(&transaction);
+                Output::Reply {
+                    transaction,
+                    response,
+                }
+            }
+            _ => {
+                tracing::debug!(transaction, "No request waits on the reply");
+                Output::Unhandled(response)
+            }
+        }
+    }
+
+    /// Routes the event by the request it answers, or else by its session and sender.
+    fn route(&self, event: JaResponse) -> Output {
+        let path = event
+            .transaction
+            .as_ref()
+            .and_then(|transaction| self.routes.get(transaction).cloned())
+            .or_else(|| path_from_response(&event));
+        match path {
+            Some(path) => Output::Event { path, event },
+            None => Output::Unhandled(event),
+        }
+    }
+}
+
+fn unparsed(parsing: &Parsing, raw: Value, error: serde_json::Error) -> Option<JaResponse> {
+    if raw["janus"] == "keepalive" {
+        return None;
+    }
+    parsing.unparsed_response(raw, error)
+}
+
+/// Returns the route of a request: `{session_id}/{handle_id}` or `{session_id}`.
+pub fn path_from_request(request: &Value) -> Option<String> {
+    let session_id = request["session_id"].as_u64()?;
+    match request["handle_id"].as_u64() {
+        Some(handle_id) => Some(format!("{session_id}/{handle_id}")),
+        None => Some(format!("{session_id}")),
+    }
+}
+
+/// Returns the route of an event: `{session_id}/{sender}` or `{session_id}`.
+pub fn path_from_response(response: &JaResponse) -> Option<String> {
+    let session_id = response.session_id?;
+    match response.sender {
+        Some(sender) => Some(format!("{session_id}/{sender}")),
+        None => Some(format!("{session_id}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Expect;
+    use super::JanusProtocol;
+    use super::Output;
+    use super::ProtocolParams;
+    use crate::japrotocol::GenericEvent;
+    use crate::japrotocol::JaHandleEvent;
+    use crate::japrotocol::ResponseType;
+    use crate::parsing::ParseMode;
+    use crate::parsing::Parsing;
+    use crate::tgenerator::TransactionGenerator;
+    use serde_json::json;
+    use serde_json::Value;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+
+    fn protocol(mode: ParseMode) -> JanusProtocol {
+        let next = AtomicU64::new(0);
+        JanusProtocol::new(
+            ProtocolParams {
+                server_root: "janus".to_string(),
+                apisecret: Some("secret".to_string()),
+                capacity: 8,
+                parsing: Parsing::new(mode),
+            },
+            TransactionGenerator::new(move || {
+                format!("tx{}", next.fetch_add(1, Ordering::Relaxed))
+            }),
+        )
+    }
+
+    #[test]
+    fn it_should_decorate_and_route_requests() {
+        let mut protocol = protocol(ParseMode::Strict);
+
+        let create = protocol.send(json!({ "janus": "create" }), Some(Expect::Response));
+        let message = json!({ "janus": "message", "session_id": 1, "handle_id": 2 });
+        let message = protocol.send(message, None);
+
+        assert_eq!(create.transaction, "tx0");
+        assert_eq!(create.path, "janus");
+        assert_eq!(
+            serde_json::from_slice::<Value>(&create.data).unwrap(),
+            json!({ "janus": "create", "apisecret": "secret", "transaction": "tx0" })
+        );
+        assert_eq!(message.path, "1/2");
+        assert_eq!(protocol.pending(), 1);
+    }
+
+    #[test]
+    fn it_should_only_reply_to_the_expected_reply_or_an_error() {
+        let mut protocol = protocol(ParseMode::Strict);
+        let keepalive = json!({ "janus": "keepalive", "session_id": 1 });
+        protocol.send(keepalive, Some(Expect::Ack));
+
+        let success = br#"{ "janus": "success", "transaction": "tx0", "data": { "id": 3 } }"#;
+        let error = br#"{
+            "janus": "error",
+            "transaction": "tx0",
+            "error": { "code": 458, "reason": "No such session" }
+        }"#;
+        assert!(matches!(protocol.feed(success)[..], [Output::Unhandled(_)]));
+        assert!(matches!(
+            &protocol.feed(error)[..],
+            [Output::Reply { transaction, .. }] if transaction == "tx0"
+        ));
+        assert_eq!(protocol.pending(), 0);
+    }
+
+    #[test]
+    fn it_should_not_reply_to_cancelled_requests() {
+        let mut protocol = protocol(ParseMode::Strict);
+        let request = protocol.send(json!({ "janus": "info" }), Some(Expect::Response));
+        protocol.cancel(&request.transaction);
+
+        let ack = br#"{ "janus": "ack", "transaction": "tx0" }"#;
+        assert!(matches!(protocol.feed(ack)[..], [Output::Unhandled(_)]));
+    }
+
+    #[test]
+    fn it_should_route_events_by_request_or_sender() {
+        let mut protocol = protocol(ParseMode::Strict);
+        let message = json!({ "janus": "message", "session_id": 1, "handle_id": 2 });
+        protocol.send(message, Some(Expect::Ack));
+
+        let events = br#"[
+            { "janus": "keepalive" },
+            { "janus": "webrtcup", "session_id": 1, "sender": 3 },
+            { "janus": "hangup", "transaction": "tx0", "reason": "Close PC" }
+        ]"#;
+        let paths = protocol
+            .feed(events)
+            .into_iter()
+            .map(|output| match output {
+                Output::Event { path, .. } => path,
+                output => panic!("Unexpected output {output:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, ["1/3", "1/2"]);
+        assert_eq!(protocol.pending(), 1);
+    }
+
+    #[test]
+    fn it_should_decode_without_the_protocol_state() {
+        let mut protocol = protocol(ParseMode::Strict);
+        let request = protocol.send(json!({ "janus": "create" }), Some(Expect::Response));
+
+        let replies = br#"[
+            { "janus": "keepalive" },
+            { "janus": "success", "transaction": "tx0", "data": { "id": 7 } }
+        ]"#;
+        let responses = JanusProtocol::decode(&Parsing::default(), replies);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(protocol.pending(), 1);
+
+        let outputs = responses
+            .into_iter()
+            .map(|response| protocol.classify(response))
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            &outputs[..],
+            [Output::Reply { transaction, .. }] if *transaction == request.transaction
+        ));
+        assert_eq!(protocol.pending(), 0);
+    }
+
+    #[test]
+    fn it_should_route_unparsed_events_in_lenient_mode() {
+        let mut protocol = protocol(ParseMode::Lenient);
+        let unknown = br#"{ "janus": "future_event", "session_id": 1, "sender": 2 }"#;
+
+        let outputs = protocol.feed(unknown);
+
+        assert!(matches!(
+            &outputs[..],
+            [Output::Event { path, event }] if path == "1/2" && matches!(
+                event.janus,
+                ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Unparsed { .. }))
+            )
+        ));
+        assert!(protocol.feed(b"\xff").is_empty());
+    }
+}
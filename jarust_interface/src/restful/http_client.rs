@@ -0,0 +1,79 @@
+use crate::janus_interface::HttpClientParams;
+use crate::Error;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+
+/// Builds the HTTP client shared by every request of an interface from the given parameters.
+// Same as `interface_support::extract_id`, the crate-wide `Error` trips `result_large_err`
+#[allow(clippy::result_large_err)]
+#[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+pub(crate) fn make_client(params: &HttpClientParams) -> Result<reqwest::Client, Error> {
+    let builder = reqwest::Client::builder().default_headers(make_headers(params)?);
+
+    #[cfg(not(target_family = "wasm"))]
+    let builder = {
+        let mut builder = builder;
+        if let Some(proxy) = &params.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        for pem in &params.root_certificates {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(pem)?);
+        }
+        if let Some(pem) = &params.identity {
+            builder = builder.identity(reqwest::Identity::from_pem(pem)?);
+        }
+        if let Some(max) = params.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = params.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if params.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        builder
+    };
+
+    Ok(builder.build()?)
+}
+
+#[allow(clippy::result_large_err)]
+fn make_headers(params: &HttpClientParams) -> Result<HeaderMap, Error> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &params.default_headers {
+        let invalid = || Error::InvalidHeader { name: name.clone() };
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?;
+        let header_value = HeaderValue::from_str(value).map_err(|_| invalid())?;
+        headers.append(header_name, header_value);
+    }
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::make_client;
+    use crate::janus_interface::HttpClientParams;
+    use crate::Error;
+
+    #[test]
+    fn it_should_build_with_default_headers() {
+        let params = HttpClientParams {
+            default_headers: vec![("Authorization".to_string(), "Bearer token".to_string())],
+            ..Default::default()
+        };
+        assert!(make_client(&params).is_ok());
+    }
+
+    #[test]
+    fn it_should_reject_invalid_header_names() {
+        let params = HttpClientParams {
+            default_headers: vec![("Invalid Header".to_string(), "value".to_string())],
+            ..Default::default()
+        };
+        assert!(matches!(
+            make_client(&params),
+            Err(Error::InvalidHeader { name }) if name == "Invalid Header"
+        ));
+    }
+}
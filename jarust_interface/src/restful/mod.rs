@@ -1,3 +1,4 @@
+mod http_client;
 pub mod restful_interface;
 
 pub use restful_interface::RestfulInterface;
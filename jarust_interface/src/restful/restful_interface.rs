@@ -1,21 +1,28 @@
+use super::http_client;
 use crate::handle_msg::HandleMessage;
 use crate::handle_msg::HandleMessageWithJsep;
 use crate::janus_interface::ConnectionParams;
 use crate::janus_interface::JanusInterface;
+use crate::janus_interface::LongPollParams;
 use crate::japrotocol::JaResponse;
 use crate::japrotocol::ResponseType;
 use crate::japrotocol::ServerInfoRsp;
+use crate::parsing::Parsing;
 use crate::tgenerator::GenerateTransaction;
 use crate::tgenerator::TransactionGenerator;
 use crate::transport::interface_support;
+use crate::transport::long_poll::LongPoll;
+use crate::transport::router::Router;
 use crate::Error;
-use jarust_rt::JaTask;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::Mutex;
+use jarust_rt::task_group::TaskId;
+use jarust_rt::JaTaskGroup;
 use serde_json::json;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::sync::Mutex;
 
 #[derive(Debug)]
 struct Shared {
@@ -23,11 +30,16 @@ struct Shared {
     transaction_generator: TransactionGenerator,
     client: reqwest::Client,
     url: String,
+    long_poll: LongPollParams,
+    tasks: JaTaskGroup,
+    parsing: Parsing,
 }
 
 #[derive(Debug)]
 struct Exclusive {
-    tasks: Vec<JaTask>,
+    router: Router,
+    /// A single long-poll task per session, demultiplexing events to its handles.
+    long_polls: HashMap<u64, TaskId>,
 }
 
 #[derive(Debug)]
@@ -49,6 +61,134 @@ impl RestfulInterface {
             request,
         )
     }
+
+    /// Starts long-polling the session, unless it's already being polled.
+    async fn ensure_long_poll(&self, session_id: u64) {
+        let mut guard = self.inner.exclusive.lock().await;
+        let router = guard.router.clone();
+        guard.long_polls.entry(session_id).or_insert_with(|| {
+            let long_poll = LongPoll {
+                client: self.inner.shared.client.clone(),
+                url: format!("{}/{session_id}", self.inner.shared.url),
+                session_id,
+                params: self.inner.shared.long_poll,
+                router,
+                parsing: self.inner.shared.parsing.clone(),
+            };
+            let signal = self.inner.shared.tasks.shutdown_signal();
+            // Weak, the tasks are owned by the interface
+            let inner = Arc::downgrade(&self.inner);
+            self.inner
+                .shared
+                .tasks
+                .spawn_fallible("Long polling", async move {
+                    let result = signal.until(long_poll.start()).await.unwrap_or(Ok(()));
+                    // Polled again by the next request on the session, if it's still alive
+                    if let Some(inner) = inner.upgrade() {
+                        inner.exclusive.lock().await.long_polls.remove(&session_id);
+                    }
+                    result
+                })
+        });
+    }
+
+    /// The group of the long-poll tasks, shared with the interfaces built on top of this one.
+    #[cfg(not(target_family = "wasm"))]
+    pub(crate) fn tasks(&self) -> &JaTaskGroup {
+        &self.inner.shared.tasks
+    }
+
+    /// Creates a new session without polling its events.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    pub(crate) async fn create_session(&self, timeout: Duration) -> Result<u64, Error> {
+        let url = &self.inner.shared.url;
+        let request = json!({"janus": "create"});
+        let (request, _) = self.decorate_request(request);
+
+        let response = self
+            .inner
+            .shared
+            .client
+            .post(url.to_string())
+            .json(&request)
+            .timeout(timeout)
+            .send()
+            .await?
+            .json::<JaResponse>()
+            .await?;
+
+        interface_support::extract_id(response)
+    }
+
+    /// Attaches a plugin to the session without spawning any event polling.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    pub(crate) async fn attach_handle(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: Option<String>,
+        timeout: Duration,
+    ) -> Result<u64, Error> {
+        let mut request = json!({
+            "janus": "attach",
+            "plugin": plugin_id
+        });
+        if let Some(opaque_id) = opaque_id {
+            request["opaque_id"] = opaque_id.into();
+        }
+        let response = self
+            .send_session_request(session_id, request, timeout)
+            .await?;
+        interface_support::extract_id(response)
+    }
+
+    /// Attaches a plugin to the session and starts polling its events.
+    async fn attach_plugin(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: Option<String>,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        let handle_id = self
+            .attach_handle(session_id, plugin_id, opaque_id, timeout)
+            .await?;
+        let receiver = self
+            .inner
+            .exclusive
+            .lock()
+            .await
+            .router
+            .add_subroute(&format!("{session_id}/{handle_id}"))
+            .await;
+        self.ensure_long_poll(session_id).await;
+        Ok((handle_id, receiver))
+    }
+
+    /// Sends a session-level request (e.g. `attach`, `keepalive`, `claim`) and returns
+    /// the synchronous response.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    pub(crate) async fn send_session_request(
+        &self,
+        session_id: u64,
+        request: Value,
+        timeout: Duration,
+    ) -> Result<JaResponse, Error> {
+        let url = &self.inner.shared.url;
+        let (request, _) = self.decorate_request(request);
+        let response = self
+            .inner
+            .shared
+            .client
+            .post(format!("{url}/{session_id}"))
+            .json(&request)
+            .timeout(timeout)
+            .send()
+            .await?
+            .json::<JaResponse>()
+            .await?;
+        Ok(response)
+    }
 }
 
 #[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
@@ -60,15 +200,22 @@ impl JanusInterface for RestfulInterface {
         transaction_generator: impl GenerateTransaction,
     ) -> Result<Self, Error> {
         tracing::debug!("Creating new Restful Interface");
-        let client = reqwest::Client::new();
+        let client = http_client::make_client(&conn_params.http_client)?;
         let transaction_generator = TransactionGenerator::new(transaction_generator);
+        let router = Router::new(&conn_params.server_root);
         let shared = Shared {
             apisecret: conn_params.apisecret,
             transaction_generator,
             client,
             url: format!("{}/{}", conn_params.url, conn_params.server_root),
+            long_poll: conn_params.long_poll,
+            tasks: JaTaskGroup::new("Restful Interface"),
+            parsing: Parsing::new(conn_params.parse_mode),
+        };
+        let exclusive = Exclusive {
+            router,
+            long_polls: HashMap::new(),
         };
-        let exclusive = Exclusive { tasks: Vec::new() };
         let inner = InnerRestfulInterface {
             shared,
             exclusive: Mutex::new(exclusive),
@@ -80,23 +227,9 @@ impl JanusInterface for RestfulInterface {
 
     #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
     async fn create(&self, timeout: Duration) -> Result<u64, Error> {
-        let url = &self.inner.shared.url;
-        let request = json!({"janus": "create"});
-        let (request, _) = self.decorate_request(request);
-
-        let response = self
-            .inner
-            .shared
-            .client
-            .post(url.to_string())
-            .json(&request)
-            .timeout(timeout)
-            .send()
-            .await?
-            .json::<JaResponse>()
-            .await?;
-
-        let session_id = interface_support::extract_id(response)?;
+        let session_id = self.create_session(timeout).await?;
+        // Polling right away also keeps the session alive before any handle is attached.
+        self.ensure_long_poll(session_id).await;
         Ok(session_id)
     }
 
@@ -130,73 +263,20 @@ impl JanusInterface for RestfulInterface {
         plugin_id: String,
         timeout: Duration,
     ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
-        let url = &self.inner.shared.url;
-        let request = json!({
-            "janus": "attach",
-            "plugin": plugin_id
-        });
-        let (request, _) = self.decorate_request(request);
-
-        let response = self
-            .inner
-            .shared
-            .client
-            .post(format!("{url}/{session_id}"))
-            .json(&request)
-            .timeout(timeout)
-            .send()
-            .await?
-            .json::<JaResponse>()
-            .await?;
-        let handle_id = interface_support::extract_id(response)?;
-        let (tx, rx) = mpsc::unbounded_channel();
-
-        let handle = jarust_rt::spawn("Long polling", {
-            let client = self.inner.shared.client.clone();
-            let url = url.clone();
-
-            async move {
-                // Delay applied before retrying after a failed request, so a downed or
-                // slow server doesn't turn this loop into a busy spin.
-                const ERROR_BACKOFF: Duration = Duration::from_millis(500);
-
-                loop {
-                    let response = match client
-                        .get(format!("{url}/{session_id}?maxev=5"))
-                        .send()
-                        .await
-                    {
-                        Ok(response) => response,
-                        Err(err) => {
-                            tracing::warn!("Long-poll request failed: {err}");
-                            jarust_rt::sleep(ERROR_BACKOFF).await;
-                            continue;
-                        }
-                    };
-
-                    let events = match response.json::<Vec<JaResponse>>().await {
-                        Ok(events) => events,
-                        Err(err) => {
-                            tracing::warn!("Failed to decode long-poll response: {err}");
-                            jarust_rt::sleep(ERROR_BACKOFF).await;
-                            continue;
-                        }
-                    };
-
-                    for event in events {
-                        if tx.send(event).is_err() {
-                            // Receiver dropped (handle gone); stop polling.
-                            tracing::debug!("Long-poll receiver dropped, stopping task");
-                            return;
-                        }
-                    }
-                }
-            }
-        });
-
-        self.inner.exclusive.lock().await.tasks.push(handle);
+        self.attach_plugin(session_id, plugin_id, None, timeout)
+            .await
+    }
 
-        Ok((handle_id, rx))
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn attach_with_opaque_id(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: String,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        self.attach_plugin(session_id, plugin_id, Some(opaque_id), timeout)
+            .await
     }
 
     fn has_keep_alive(&self) -> bool {
@@ -223,6 +303,17 @@ impl JanusInterface for RestfulInterface {
             .timeout(timeout)
             .send()
             .await?;
+
+        let long_poll = self
+            .inner
+            .exclusive
+            .lock()
+            .await
+            .long_polls
+            .remove(&session_id);
+        if let Some(task) = long_poll {
+            self.inner.shared.tasks.cancel(task);
+        }
         Ok(())
     }
 
@@ -381,13 +472,12 @@ impl JanusInterface for RestfulInterface {
     fn name(&self) -> Box<str> {
         "Restful Interface".to_string().into_boxed_str()
     }
-}
 
-impl Drop for InnerRestfulInterface {
-    fn drop(&mut self) {
-        // `get_mut` avoids locking: we have exclusive access while dropping.
-        for task in self.exclusive.get_mut().tasks.iter() {
-            task.cancel();
-        }
+    fn task_group(&self) -> Option<JaTaskGroup> {
+        Some(self.inner.shared.tasks.clone())
+    }
+
+    fn parsing(&self) -> Parsing {
+        self.inner.shared.parsing.clone()
     }
 }
@@ -0,0 +1,722 @@
+//! # SDP
+//!
+//! A lossless SDP model: lines are kept as they are, so serializing a parsed SDP gives back
+//! the original text, and typed accessors parse the attributes on demand.
+//!
+//! It also provides the munging helpers commonly applied to offers and answers before
+//! handing them over to janus (forcing codecs, stripping candidates, capping bitrates, ...).
+
+use crate::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A single `<type>=<value>` SDP line.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SdpLine {
+    pub kind: char,
+    pub value: String,
+}
+
+impl SdpLine {
+    pub fn new(kind: char, value: impl Into<String>) -> Self {
+        Self {
+            kind,
+            value: value.into(),
+        }
+    }
+
+    /// Creates an `a=` line.
+    pub fn attribute(name: &str, value: Option<&str>) -> Self {
+        match value {
+            Some(value) => Self::new('a', format!("{name}:{value}")),
+            None => Self::new('a', name),
+        }
+    }
+
+    /// For `a=` lines, returns the attribute name and its value, e.g. `("mid", Some("0"))`.
+    pub fn as_attribute(&self) -> Option<(&str, Option<&str>)> {
+        if self.kind != 'a' {
+            return None;
+        }
+        match self.value.split_once(':') {
+            Some((name, value)) => Some((name, Some(value))),
+            None => Some((&self.value, None)),
+        }
+    }
+
+    fn is_attribute(&self, name: &str) -> bool {
+        self.as_attribute()
+            .is_some_and(|(attribute, _)| attribute == name)
+    }
+}
+
+impl fmt::Display for SdpLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.kind, self.value)
+    }
+}
+
+/// The `m=` line of a media section.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MediaLine {
+    /// `audio`, `video`, `application`, ...
+    pub kind: String,
+    pub port: u16,
+    pub port_count: Option<u16>,
+    pub protocol: String,
+    /// The payload types for RTP media, `webrtc-datachannel` for data channels.
+    pub formats: Vec<String>,
+}
+
+impl FromStr for MediaLine {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut tokens = value.split(' ');
+        let (Some(kind), Some(port), Some(protocol)) =
+            (tokens.next(), tokens.next(), tokens.next())
+        else {
+            return Err(format!("incomplete media line: {value}"));
+        };
+        let (port, port_count) = match port.split_once('/') {
+            Some((port, count)) => (port, Some(count)),
+            None => (port, None),
+        };
+        let port = port
+            .parse()
+            .map_err(|_| format!("invalid media port: {port}"))?;
+        let port_count = port_count
+            .map(|count| count.parse())
+            .transpose()
+            .map_err(|_| format!("invalid media port count: {value}"))?;
+        Ok(Self {
+            kind: kind.to_string(),
+            port,
+            port_count,
+            protocol: protocol.to_string(),
+            formats: tokens.map(str::to_string).collect(),
+        })
+    }
+}
+
+impl fmt::Display for MediaLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.port)?;
+        if let Some(count) = self.port_count {
+            write!(f, "/{count}")?;
+        }
+        write!(f, " {}", self.protocol)?;
+        for format in &self.formats {
+            write!(f, " {format}")?;
+        }
+        Ok(())
+    }
+}
+
+/// `a=sendrecv`, `a=sendonly`, `a=recvonly` or `a=inactive`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    SendRecv,
+    SendOnly,
+    RecvOnly,
+    Inactive,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::SendRecv,
+        Direction::SendOnly,
+        Direction::RecvOnly,
+        Direction::Inactive,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::SendRecv => "sendrecv",
+            Direction::SendOnly => "sendonly",
+            Direction::RecvOnly => "recvonly",
+            Direction::Inactive => "inactive",
+        }
+    }
+
+    fn from_attribute(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|direction| direction.as_str() == name)
+    }
+}
+
+/// `a=rtpmap:<payload type> <encoding>/<clock rate>[/<channels>]`
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RtpMap {
+    pub payload_type: u8,
+    pub encoding: String,
+    pub clock_rate: u32,
+    pub channels: Option<u16>,
+}
+
+impl FromStr for RtpMap {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (payload_type, codec) = value.split_once(' ').ok_or(())?;
+        let mut codec = codec.split('/');
+        let encoding = codec.next().ok_or(())?;
+        let clock_rate = codec.next().ok_or(())?.parse().map_err(|_| ())?;
+        let channels = codec.next().map(str::parse).transpose().map_err(|_| ())?;
+        Ok(Self {
+            payload_type: payload_type.parse().map_err(|_| ())?,
+            encoding: encoding.to_string(),
+            clock_rate,
+            channels,
+        })
+    }
+}
+
+/// `a=extmap:<id>[/<direction>] <uri> [<attributes>]`
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ExtMap {
+    pub id: u16,
+    pub direction: Option<String>,
+    pub uri: String,
+    pub attributes: Option<String>,
+}
+
+impl FromStr for ExtMap {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut tokens = value.splitn(3, ' ');
+        let id = tokens.next().ok_or(())?;
+        let uri = tokens.next().ok_or(())?;
+        let (id, direction) = match id.split_once('/') {
+            Some((id, direction)) => (id, Some(direction.to_string())),
+            None => (id, None),
+        };
+        Ok(Self {
+            id: id.parse().map_err(|_| ())?,
+            direction,
+            uri: uri.to_string(),
+            attributes: tokens.next().map(str::to_string),
+        })
+    }
+}
+
+/// `a=rid:<id> <send|recv> [<restrictions>]`, used for simulcast.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Rid {
+    pub id: String,
+    pub direction: String,
+    pub restrictions: Option<String>,
+}
+
+impl FromStr for Rid {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut tokens = value.splitn(3, ' ');
+        let id = tokens.next().ok_or(())?;
+        let direction = tokens.next().ok_or(())?;
+        Ok(Self {
+            id: id.to_string(),
+            direction: direction.to_string(),
+            restrictions: tokens.next().map(str::to_string),
+        })
+    }
+}
+
+/// `a=fingerprint:<hash function> <fingerprint>`
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Fingerprint {
+    pub hash_function: String,
+    pub value: String,
+}
+
+/// A media section, from its `m=` line up to the next one.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MediaSection {
+    pub media: MediaLine,
+    /// The lines following the `m=` line.
+    pub lines: Vec<SdpLine>,
+}
+
+impl MediaSection {
+    pub fn kind(&self) -> &str {
+        &self.media.kind
+    }
+
+    /// Returns the value of the first `a=<name>` attribute. Flag attributes have an empty value.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        attribute(&self.lines, name)
+    }
+
+    /// Returns the values of every `a=<name>` attribute.
+    pub fn attributes<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        attributes(&self.lines, name)
+    }
+
+    pub fn mid(&self) -> Option<&str> {
+        self.attribute("mid")
+    }
+
+    pub fn direction(&self) -> Option<Direction> {
+        self.lines
+            .iter()
+            .filter_map(SdpLine::as_attribute)
+            .find_map(|(name, _)| Direction::from_attribute(name))
+    }
+
+    /// Replaces the direction attribute, or adds one.
+    pub fn set_direction(&mut self, direction: Direction) {
+        let line = SdpLine::attribute(direction.as_str(), None);
+        let position = self.lines.iter().position(|line| {
+            line.as_attribute()
+                .is_some_and(|(name, _)| Direction::from_attribute(name).is_some())
+        });
+        match position {
+            Some(position) => self.lines[position] = line,
+            None => self.lines.push(line),
+        }
+    }
+
+    pub fn rtpmaps(&self) -> Vec<RtpMap> {
+        self.attributes("rtpmap")
+            .filter_map(|value| value.parse().ok())
+            .collect()
+    }
+
+    /// Returns the format parameters of a payload type.
+    pub fn fmtp(&self, payload_type: u8) -> Option<&str> {
+        self.attributes("fmtp").find_map(|value| {
+            let (pt, params) = value.split_once(' ')?;
+            (pt.parse() == Ok(payload_type)).then_some(params)
+        })
+    }
+
+    pub fn extmaps(&self) -> Vec<ExtMap> {
+        self.attributes("extmap")
+            .filter_map(|value| value.parse().ok())
+            .collect()
+    }
+
+    pub fn rids(&self) -> Vec<Rid> {
+        self.attributes("rid")
+            .filter_map(|value| value.parse().ok())
+            .collect()
+    }
+
+    /// Returns the `a=simulcast` attribute, e.g. `send h;m;l`.
+    pub fn simulcast(&self) -> Option<&str> {
+        self.attribute("simulcast")
+    }
+
+    pub fn ice_ufrag(&self) -> Option<&str> {
+        self.attribute("ice-ufrag")
+    }
+
+    pub fn ice_pwd(&self) -> Option<&str> {
+        self.attribute("ice-pwd")
+    }
+
+    pub fn fingerprint(&self) -> Option<Fingerprint> {
+        let (hash_function, value) = self.attribute("fingerprint")?.split_once(' ')?;
+        Some(Fingerprint {
+            hash_function: hash_function.to_string(),
+            value: value.to_string(),
+        })
+    }
+
+    /// Returns the DTLS role: `actpass`, `active` or `passive`.
+    pub fn setup(&self) -> Option<&str> {
+        self.attribute("setup")
+    }
+
+    pub fn candidates(&self) -> impl Iterator<Item = &str> + '_ {
+        self.attributes("candidate")
+    }
+
+    /// Keeps only the payload types of the codec (and their retransmission payload types).
+    ///
+    /// Returns `false`, leaving the section untouched, when the codec isn't offered.
+    pub fn force_codec(&mut self, encoding: &str) -> bool {
+        let rtpmaps = self.rtpmaps();
+        let mut kept = rtpmaps
+            .iter()
+            .filter(|rtpmap| rtpmap.encoding.eq_ignore_ascii_case(encoding))
+            .map(|rtpmap| rtpmap.payload_type)
+            .collect::<Vec<_>>();
+        if kept.is_empty() {
+            return false;
+        }
+        let rtx = rtpmaps
+            .iter()
+            .filter(|rtpmap| rtpmap.encoding.eq_ignore_ascii_case("rtx"))
+            .filter(|rtpmap| {
+                self.fmtp(rtpmap.payload_type)
+                    .and_then(|params| params.strip_prefix("apt="))
+                    .and_then(|apt| apt.parse().ok())
+                    .is_some_and(|apt| kept.contains(&apt))
+            })
+            .map(|rtpmap| rtpmap.payload_type)
+            .collect::<Vec<_>>();
+        kept.extend(rtx);
+
+        self.media
+            .formats
+            .retain(|format| format.parse().is_ok_and(|pt| kept.contains(&pt)));
+        self.lines.retain(|line| {
+            let Some((name, Some(value))) = line.as_attribute() else {
+                return true;
+            };
+            if !matches!(name, "rtpmap" | "fmtp" | "rtcp-fb") {
+                return true;
+            }
+            // `a=rtcp-fb:* ...` applies to every payload type
+            let pt = value.split(' ').next().unwrap_or_default();
+            pt == "*" || pt.parse().is_ok_and(|pt| kept.contains(&pt))
+        });
+        true
+    }
+
+    /// Caps the bandwidth of the section with a `b=AS` line (and updates `b=TIAS` if present).
+    pub fn set_bitrate(&mut self, kbps: u32) {
+        let mut has_as = false;
+        for line in self.lines.iter_mut().filter(|line| line.kind == 'b') {
+            if line.value.starts_with("AS:") {
+                line.value = format!("AS:{kbps}");
+                has_as = true;
+            } else if line.value.starts_with("TIAS:") {
+                line.value = format!("TIAS:{}", u64::from(kbps) * 1000);
+            }
+        }
+        if !has_as {
+            // `b=` lines follow the `i=` and `c=` lines of the section
+            let position = self
+                .lines
+                .iter()
+                .take_while(|line| matches!(line.kind, 'i' | 'c' | 'b'))
+                .count();
+            self.lines
+                .insert(position, SdpLine::new('b', format!("AS:{kbps}")));
+        }
+    }
+}
+
+/// A parsed session description.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Sdp {
+    /// The session-level lines, before the first `m=` line.
+    pub session: Vec<SdpLine>,
+    pub media: Vec<MediaSection>,
+    line_ending: LineEnding,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+struct LineEnding {
+    crlf: bool,
+    terminated: bool,
+}
+
+impl Sdp {
+    /// Parses an SDP, failing on lines that aren't `<type>=<value>` or on malformed `m=` lines.
+    #[allow(clippy::result_large_err)]
+    pub fn parse(sdp: &str) -> Result<Self, Error> {
+        let line_ending = LineEnding {
+            crlf: sdp.contains("\r\n"),
+            terminated: sdp.ends_with('\n'),
+        };
+        let mut session = Vec::new();
+        let mut media: Vec<MediaSection> = Vec::new();
+        for (index, line) in sdp.lines().enumerate() {
+            let invalid = |reason: String| Error::InvalidSdp {
+                line: index + 1,
+                reason,
+            };
+            let mut chars = line.chars();
+            let (Some(kind), Some('=')) = (chars.next(), chars.next()) else {
+                return Err(invalid(format!("expected <type>=<value>, got {line:?}")));
+            };
+            let value = chars.as_str();
+            if kind == 'm' {
+                media.push(MediaSection {
+                    media: value.parse().map_err(invalid)?,
+                    lines: Vec::new(),
+                });
+                continue;
+            }
+            let line = SdpLine::new(kind, value);
+            match media.last_mut() {
+                Some(section) => section.lines.push(line),
+                None => session.push(line),
+            }
+        }
+        Ok(Self {
+            session,
+            media,
+            line_ending,
+        })
+    }
+
+    /// Returns the value of the first session-level `a=<name>` attribute.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        attribute(&self.session, name)
+    }
+
+    /// Returns the mids of the media sections, in order.
+    pub fn mids(&self) -> Vec<&str> {
+        self.media.iter().filter_map(MediaSection::mid).collect()
+    }
+
+    /// Returns the media section with the given mid.
+    pub fn media_by_mid(&self, mid: &str) -> Option<&MediaSection> {
+        self.media.iter().find(|section| section.mid() == Some(mid))
+    }
+
+    pub fn media_by_mid_mut(&mut self, mid: &str) -> Option<&mut MediaSection> {
+        self.media
+            .iter_mut()
+            .find(|section| section.mid() == Some(mid))
+    }
+
+    /// Forces the codec on every media section of the given kind (`audio` or `video`) offering it.
+    pub fn force_codec(&mut self, kind: &str, encoding: &str) -> &mut Self {
+        for section in self
+            .media
+            .iter_mut()
+            .filter(|section| section.kind() == kind)
+        {
+            section.force_codec(encoding);
+        }
+        self
+    }
+
+    /// Removes the ICE candidates, e.g. to trickle them separately.
+    pub fn strip_candidates(&mut self) -> &mut Self {
+        let is_candidate = |line: &SdpLine| {
+            line.is_attribute("candidate") || line.is_attribute("end-of-candidates")
+        };
+        self.session.retain(|line| !is_candidate(line));
+        for section in &mut self.media {
+            section.lines.retain(|line| !is_candidate(line));
+        }
+        self
+    }
+
+    /// Caps the bitrate of every media section of the given kind.
+    pub fn set_bitrate(&mut self, kind: &str, kbps: u32) -> &mut Self {
+        for section in self
+            .media
+            .iter_mut()
+            .filter(|section| section.kind() == kind)
+        {
+            section.set_bitrate(kbps);
+        }
+        self
+    }
+}
+
+impl FromStr for Sdp {
+    type Err = Error;
+
+    fn from_str(sdp: &str) -> Result<Self, Self::Err> {
+        Self::parse(sdp)
+    }
+}
+
+impl fmt::Display for Sdp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ending = if self.line_ending.crlf { "\r\n" } else { "\n" };
+        let mut lines =
+            self.session
+                .iter()
+                .map(ToString::to_string)
+                .chain(self.media.iter().flat_map(|section| {
+                    std::iter::once(format!("m={}", section.media))
+                        .chain(section.lines.iter().map(ToString::to_string))
+                }));
+        if let Some(first) = lines.next() {
+            f.write_str(&first)?;
+        }
+        for line in lines {
+            write!(f, "{ending}{line}")?;
+        }
+        if self.line_ending.terminated {
+            f.write_str(ending)?;
+        }
+        Ok(())
+    }
+}
+
+fn attribute<'a>(lines: &'a [SdpLine], name: &str) -> Option<&'a str> {
+    lines
+        .iter()
+        .filter_map(SdpLine::as_attribute)
+        .find(|(attribute, _)| *attribute == name)
+        .map(|(_, value)| value.unwrap_or_default())
+}
+
+fn attributes<'a>(lines: &'a [SdpLine], name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    lines
+        .iter()
+        .filter_map(SdpLine::as_attribute)
+        .filter(move |(attribute, _)| *attribute == name)
+        .map(|(_, value)| value.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Direction;
+    use super::Sdp;
+    use crate::japrotocol::Jsep;
+    use crate::japrotocol::JsepType;
+
+    const OFFER: &str = "v=0\r\n\
+o=- 4611731400430051336 2 IN IP4 127.0.0.1\r\n\
+s=-\r\n\
+t=0 0\r\n\
+a=group:BUNDLE 0 1 2\r\n\
+a=msid-semantic: WMS\r\n\
+m=audio 9 UDP/TLS/RTP/SAVPF 111 0\r\n\
+c=IN IP4 0.0.0.0\r\n\
+a=rtcp:9 IN IP4 0.0.0.0\r\n\
+a=candidate:1 1 udp 2122260223 192.168.1.2 54321 typ host\r\n\
+a=end-of-candidates\r\n\
+a=ice-ufrag:abcd\r\n\
+a=ice-pwd:0123456789abcdef0123456789\r\n\
+a=fingerprint:sha-256 AA:BB:CC\r\n\
+a=setup:actpass\r\n\
+a=mid:0\r\n\
+a=extmap:1 urn:ietf:params:rtp-hdrext:ssrc-audio-level\r\n\
+a=sendrecv\r\n\
+a=rtpmap:111 opus/48000/2\r\n\
+a=rtcp-fb:111 transport-cc\r\n\
+a=fmtp:111 minptime=10;useinbandfec=1\r\n\
+a=rtpmap:0 PCMU/8000\r\n\
+m=video 9 UDP/TLS/RTP/SAVPF 96 97 98 99\r\n\
+c=IN IP4 0.0.0.0\r\n\
+a=mid:1\r\n\
+a=extmap:4/sendonly urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id\r\n\
+a=sendonly\r\n\
+a=rtpmap:96 VP8/90000\r\n\
+a=rtcp-fb:96 nack\r\n\
+a=rtpmap:97 rtx/90000\r\n\
+a=fmtp:97 apt=96\r\n\
+a=rtpmap:98 H264/90000\r\n\
+a=fmtp:98 profile-level-id=42e01f\r\n\
+a=rtpmap:99 rtx/90000\r\n\
+a=fmtp:99 apt=98\r\n\
+a=rtcp-fb:* ccm fir\r\n\
+a=rid:h send\r\n\
+a=rid:l send max-width=320\r\n\
+a=simulcast:send h;l\r\n\
+m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+c=IN IP4 0.0.0.0\r\n\
+a=mid:2\r\n\
+a=sctp-port:5000\r\n";
+
+    #[test]
+    fn it_should_round_trip() {
+        let sdp = Sdp::parse(OFFER).unwrap();
+        assert_eq!(sdp.to_string(), OFFER);
+
+        let unterminated = "v=0\no=- 1 2 IN IP4 127.0.0.1\nm=audio 9/2 RTP/AVP 0";
+        assert_eq!(Sdp::parse(unterminated).unwrap().to_string(), unterminated);
+    }
+
+    #[test]
+    fn it_should_parse_media_sections() {
+        let sdp = Sdp::parse(OFFER).unwrap();
+        assert_eq!(sdp.mids(), vec!["0", "1", "2"]);
+        assert_eq!(sdp.attribute("group"), Some("BUNDLE 0 1 2"));
+
+        let audio = &sdp.media[0];
+        assert_eq!(audio.kind(), "audio");
+        assert_eq!(audio.media.formats, vec!["111", "0"]);
+        assert_eq!(audio.direction(), Some(Direction::SendRecv));
+        assert_eq!(audio.rtpmaps()[0].encoding, "opus");
+        assert_eq!(audio.rtpmaps()[0].channels, Some(2));
+        assert_eq!(audio.fmtp(111), Some("minptime=10;useinbandfec=1"));
+        assert_eq!(audio.ice_ufrag(), Some("abcd"));
+        assert_eq!(audio.fingerprint().unwrap().hash_function, "sha-256");
+        assert_eq!(audio.setup(), Some("actpass"));
+        assert_eq!(audio.candidates().count(), 1);
+
+        let video = sdp.media_by_mid("1").unwrap();
+        assert_eq!(video.direction(), Some(Direction::SendOnly));
+        assert_eq!(video.extmaps()[0].direction.as_deref(), Some("sendonly"));
+        assert_eq!(
+            video.rids()[1].restrictions.as_deref(),
+            Some("max-width=320")
+        );
+        assert_eq!(video.simulcast(), Some("send h;l"));
+    }
+
+    #[test]
+    fn it_should_reject_invalid_lines() {
+        let result = Sdp::parse("v=0\r\nnot a line\r\n");
+        assert!(matches!(
+            result,
+            Err(crate::Error::InvalidSdp { line: 2, .. })
+        ));
+        assert!(Sdp::parse("v=0\r\nm=audio\r\n").is_err());
+    }
+
+    #[test]
+    fn it_should_force_codecs() {
+        let mut sdp = Sdp::parse(OFFER).unwrap();
+        sdp.force_codec("video", "h264").force_codec("audio", "AV1");
+
+        let video = &sdp.media[1];
+        assert_eq!(video.media.formats, vec!["98", "99"]);
+        let payload_types = video
+            .rtpmaps()
+            .iter()
+            .map(|rtpmap| rtpmap.payload_type)
+            .collect::<Vec<_>>();
+        assert_eq!(payload_types, vec![98, 99]);
+        assert!(video.fmtp(97).is_none());
+        assert_eq!(
+            video.attributes("rtcp-fb").collect::<Vec<_>>(),
+            vec!["* ccm fir"]
+        );
+        // Audio doesn't offer AV1, it's left untouched
+        assert_eq!(sdp.media[0].media.formats, vec!["111", "0"]);
+    }
+
+    #[test]
+    fn it_should_strip_candidates_and_cap_bitrates() {
+        let mut sdp = Sdp::parse(OFFER).unwrap();
+        sdp.strip_candidates().set_bitrate("video", 512);
+        assert_eq!(sdp.media[0].candidates().count(), 0);
+        assert!(sdp.media[0].attribute("end-of-candidates").is_none());
+
+        let serialized = sdp.to_string();
+        assert!(serialized.contains(
+            "m=video 9 UDP/TLS/RTP/SAVPF 96 97 98 99\r\nc=IN IP4 0.0.0.0\r\nb=AS:512\r\n"
+        ));
+
+        sdp.set_bitrate("video", 256);
+        assert_eq!(sdp.to_string().matches("b=AS:").count(), 1);
+    }
+
+    #[test]
+    fn it_should_munge_jsep() {
+        let mut jsep = Jsep {
+            jsep_type: JsepType::Offer,
+            sdp: OFFER.to_string(),
+            trickle: None,
+            e2ee: None,
+            simulcast: None,
+            svc: None,
+            update: None,
+            extra: Default::default(),
+        };
+        jsep.munge_sdp(|sdp| {
+            sdp.media[2].set_direction(Direction::Inactive);
+        })
+        .unwrap();
+        let sdp = jsep.parse_sdp().unwrap();
+        assert_eq!(sdp.media[2].direction(), Some(Direction::Inactive));
+        assert!(jsep.sdp.ends_with("a=sctp-port:5000\r\na=inactive\r\n"));
+    }
+}
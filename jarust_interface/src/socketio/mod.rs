@@ -1,4 +1,5 @@
 mod socketio_client;
 mod socketio_interface;
 
+pub use socketio_client::SocketIoClient;
 pub use socketio_interface::SocketIoInterface;
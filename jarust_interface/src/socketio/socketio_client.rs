@@ -1,16 +1,19 @@
+use crate::janus_interface::ConnectionParams;
+use crate::streaming::MessageTransport;
 use crate::Error;
 use bytes::Bytes;
 use futures_util::FutureExt;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::oneshot;
 use rust_socketio::asynchronous::Client;
 use rust_socketio::asynchronous::ClientBuilder;
 use rust_socketio::Payload;
 use std::sync::Arc;
 use std::sync::Mutex;
-use tokio::sync::mpsc;
-use tokio::sync::oneshot;
 
 const JANUS_EVENT: &str = "janus";
 
+/// [`MessageTransport`] over a Socket.IO connection, janus messages are emitted on the `janus` event.
 pub struct SocketIoClient {
     socket: Option<Client>,
 }
@@ -33,10 +36,16 @@ impl SocketIoClient {
     pub fn new() -> Self {
         Self { socket: None }
     }
+}
 
+#[async_trait::async_trait]
+impl MessageTransport for SocketIoClient {
     #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
-    pub async fn connect(&mut self, url: &str) -> Result<mpsc::UnboundedReceiver<Bytes>, Error> {
-        let url = normalize_scheme(url);
+    async fn connect(
+        &mut self,
+        conn_params: &ConnectionParams,
+    ) -> Result<mpsc::UnboundedReceiver<Bytes>, Error> {
+        let url = normalize_scheme(&conn_params.url);
         tracing::debug!("Connecting to {url}");
         let (tx, rx) = mpsc::unbounded_channel::<Bytes>();
 
@@ -114,7 +123,7 @@ impl SocketIoClient {
     }
 
     #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
-    pub async fn send(&self, data: &[u8], _: &str) -> Result<(), Error> {
+    async fn send(&mut self, data: &[u8], _: &str) -> Result<(), Error> {
         let Some(socket) = &self.socket else {
             tracing::error!("Transport not opened!");
             return Err(Error::TransportNotOpened);
@@ -123,6 +132,18 @@ impl SocketIoClient {
         socket.emit(JANUS_EVENT, value).await?;
         Ok(())
     }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn close(&mut self) -> Result<(), Error> {
+        if let Some(socket) = self.socket.take() {
+            socket.disconnect().await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> Box<str> {
+        "Socket.IO".to_string().into_boxed_str()
+    }
 }
 
 fn normalize_scheme(url: &str) -> String {
@@ -0,0 +1,55 @@
+use crate::janus_interface::ConnectionParams;
+use crate::janus_interface::MaybeSend;
+use crate::janus_interface::MaybeSync;
+use crate::Error;
+use bytes::Bytes;
+use jarust_rt::sync::mpsc;
+
+/// [`MessageTransport`] moves raw janus messages over a persistent connection.
+///
+/// It's the extension point of [`StreamingJanusInterface`], which builds the janus api
+/// (transactions, acks, responses and event routing) on top of it. The built-in WebSocket
+/// and Socket.IO clients implement it, a custom framing (e.g. a QUIC tunnel) only has to
+/// implement this trait to get a full [`JanusInterface`].
+///
+/// Transports are constructed with [`Default`] and configured from the [`ConnectionParams`]
+/// when connecting.
+///
+/// [`StreamingJanusInterface`]: super::StreamingJanusInterface
+/// [`JanusInterface`]: crate::janus_interface::JanusInterface
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+pub trait MessageTransport: Default + MaybeSend + MaybeSync + 'static {
+    /// Connects to the janus server, returning the stream of inbound messages.
+    ///
+    /// Each item is a single JSON encoded janus message, the stream ends when the connection is lost.
+    async fn connect(
+        &mut self,
+        conn_params: &ConnectionParams,
+    ) -> Result<mpsc::UnboundedReceiver<Bytes>, Error>;
+
+    /// Sends a single JSON encoded janus message.
+    ///
+    /// `path` is the route of the message (`{session_id}/{handle_id}`, `{session_id}` or the server root),
+    /// for transports that multiplex on it.
+    async fn send(&mut self, data: &[u8], path: &str) -> Result<(), Error>;
+
+    /// Sends the `(data, path)` messages queued together, in order.
+    ///
+    /// Sends them one by one by default, transports that can flush several messages at once
+    /// (e.g. a WebSocket sink) should override it.
+    async fn send_batch(&mut self, batch: &[(Bytes, String)]) -> Result<(), Error> {
+        for (data, path) in batch {
+            self.send(data, path).await?;
+        }
+        Ok(())
+    }
+
+    /// Closes the connection, ending the inbound stream.
+    async fn close(&mut self) -> Result<(), Error>;
+
+    /// Returns the name of the transport (for the debug trait)
+    fn name(&self) -> Box<str> {
+        "Message Transport".to_string().into_boxed_str()
+    }
+}
@@ -0,0 +1,6 @@
+pub mod message_transport;
+pub mod streaming_interface;
+mod writer;
+
+pub use message_transport::MessageTransport;
+pub use streaming_interface::StreamingJanusInterface;
@@ -0,0 +1,439 @@
+use super::writer::Writer;
+use super::MessageTransport;
+use crate::handle_msg::HandleMessage;
+use crate::handle_msg::HandleMessageWithJsep;
+use crate::janus_interface::ConnectionParams;
+use crate::janus_interface::JanusInterface;
+use crate::japrotocol::JaResponse;
+use crate::japrotocol::ResponseType;
+use crate::japrotocol::ServerInfoRsp;
+use crate::parsing::Parsing;
+use crate::protocol::Expect;
+use crate::protocol::JanusProtocol;
+use crate::protocol::ProtocolParams;
+use crate::protocol::Request;
+use crate::tgenerator::GenerateTransaction;
+use crate::tgenerator::TransactionGenerator;
+use crate::transport::demuxer::Demuxer;
+use crate::transport::driver::ProtocolDriver;
+use crate::transport::interface_support;
+use crate::transport::router::Router;
+use crate::Error;
+use jarust_rt::sync::mpsc;
+use jarust_rt::JaTaskGroup;
+use serde_json::json;
+use serde_json::Value;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug)]
+struct Shared {
+    name: Box<str>,
+    tasks: JaTaskGroup,
+    parsing: Parsing,
+    driver: ProtocolDriver,
+    router: Router,
+    writer: Writer,
+}
+
+#[derive(Debug)]
+struct InnerStreamingJanusInterface {
+    shared: Shared,
+}
+
+/// [`StreamingJanusInterface`] implements the janus api over any [`MessageTransport`].
+///
+/// It drives the sans-IO [`JanusProtocol`]: requests are decorated with a transaction and
+/// queued to a writer task owning the transport, which writes the messages queued together in a
+/// single batch. The inbound messages are demultiplexed into responses, acks and handle events.
+pub struct StreamingJanusInterface<T> {
+    inner: Arc<InnerStreamingJanusInterface>,
+    transport: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for StreamingJanusInterface<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            transport: PhantomData,
+        }
+    }
+}
+
+impl<T: MessageTransport> StreamingJanusInterface<T> {
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    pub async fn send(&self, message: Value) -> Result<String, Error> {
+        let request = self.inner.shared.driver.send(message);
+        let transaction = request.transaction.clone();
+        self.transmit(request).await?;
+        Ok(transaction)
+    }
+
+    /// Sends the request and waits for its response.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn send_waiton_rsp(
+        &self,
+        message: Value,
+        timeout: Duration,
+    ) -> Result<JaResponse, Error> {
+        let (request, reply) = self
+            .inner
+            .shared
+            .driver
+            .send_waiton(message, Expect::Response);
+        self.transmit(request).await?;
+        reply.wait(timeout).await
+    }
+
+    /// Sends the request and waits for its ack, returns the transaction of the request.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn send_waiton_ack(&self, message: Value, timeout: Duration) -> Result<String, Error> {
+        let (request, reply) = self.inner.shared.driver.send_waiton(message, Expect::Ack);
+        let transaction = request.transaction.clone();
+        self.transmit(request).await?;
+        reply.wait(timeout).await?;
+        Ok(transaction)
+    }
+
+    async fn transmit(&self, request: Request) -> Result<(), Error> {
+        self.inner
+            .shared
+            .writer
+            .send(request.data, request.path)
+            .await
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn attach_plugin(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: Option<String>,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        let mut request = json!({
+            "janus": "attach",
+            "session_id": session_id,
+            "plugin": plugin_id
+        });
+        if let Some(opaque_id) = opaque_id {
+            request["opaque_id"] = opaque_id.into();
+        }
+        let response = self.send_waiton_rsp(request, timeout).await?;
+        let handle_id = interface_support::extract_id(response)?;
+        let receiver = self
+            .inner
+            .shared
+            .router
+            .add_subroute(&format!("{session_id}/{handle_id}"))
+            .await;
+        Ok((handle_id, receiver))
+    }
+}
+
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+impl<T: MessageTransport> JanusInterface for StreamingJanusInterface<T> {
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn make_interface(
+        conn_params: ConnectionParams,
+        transaction_generator: impl GenerateTransaction,
+    ) -> Result<Self, Error> {
+        let mut transport = T::default();
+        let name = format!("{} Interface", transport.name()).into_boxed_str();
+        tracing::debug!("Creating {name}");
+        let router = Router::new(&conn_params.server_root);
+        let receiver = transport.connect(&conn_params).await?;
+        let parsing = Parsing::new(conn_params.parse_mode);
+        let protocol = JanusProtocol::new(
+            ProtocolParams {
+                server_root: conn_params.server_root,
+                apisecret: conn_params.apisecret,
+                capacity: conn_params.capacity,
+                parsing: parsing.clone(),
+            },
+            TransactionGenerator::new(transaction_generator),
+        );
+        let driver = ProtocolDriver::new(protocol);
+        let tasks = JaTaskGroup::new(&name);
+        // Once the demuxer stops the pending requests are cancelled
+        tasks.spawn_fallible("Demultiplexing task", {
+            let signal = tasks.shutdown_signal();
+            let demuxer = Demuxer {
+                inbound_stream: receiver,
+                router: router.clone(),
+                driver: driver.clone(),
+            };
+            async move { signal.until(demuxer.start()).await.unwrap_or(Ok(())) }
+        });
+        let writer = Writer::spawn(transport, &tasks);
+
+        let shared = Shared {
+            name,
+            tasks,
+            parsing,
+            driver,
+            router,
+            writer,
+        };
+        let inner = InnerStreamingJanusInterface { shared };
+        let this = Self {
+            inner: Arc::new(inner),
+            transport: PhantomData,
+        };
+        Ok(this)
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn create(&self, timeout: Duration) -> Result<u64, Error> {
+        let request = json!({
+            "janus": "create"
+        });
+
+        let response = self.send_waiton_rsp(request, timeout).await?;
+        let session_id = interface_support::extract_id(response)?;
+        Ok(session_id)
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn server_info(&self, timeout: Duration) -> Result<ServerInfoRsp, Error> {
+        let request = json!({
+            "janus": "info"
+        });
+        let response = self.send_waiton_rsp(request, timeout).await?;
+        match response.janus {
+            ResponseType::ServerInfo(info) => Ok(*info),
+            ResponseType::Error { error } => Err(Error::JanusError {
+                code: error.code,
+                reason: error.reason,
+            }),
+            _ => Err(Error::IncompletePacket),
+        }
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn attach(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        self.attach_plugin(session_id, plugin_id, None, timeout)
+            .await
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn attach_with_opaque_id(
+        &self,
+        session_id: u64,
+        plugin_id: String,
+        opaque_id: String,
+        timeout: Duration,
+    ) -> Result<(u64, mpsc::UnboundedReceiver<JaResponse>), Error> {
+        self.attach_plugin(session_id, plugin_id, Some(opaque_id), timeout)
+            .await
+    }
+
+    fn has_keep_alive(&self) -> bool {
+        true
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn keep_alive(&self, session_id: u64, timeout: Duration) -> Result<(), Error> {
+        let request = json!({
+            "janus": "keepalive",
+            "session_id": session_id
+        });
+        self.send_waiton_ack(request, timeout).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn destroy(&self, session_id: u64, timeout: Duration) -> Result<(), Error> {
+        let request = json!({
+            "janus": "destroy",
+            "session_id": session_id
+        });
+        self.send_waiton_rsp(request, timeout).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn fire_and_forget_msg(&self, message: HandleMessage) -> Result<String, Error> {
+        let transaction = self.send(message.to_message_envelope()).await?;
+        Ok(transaction)
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn send_msg_waiton_ack(
+        &self,
+        message: HandleMessage,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        self.send_waiton_ack(message.to_message_envelope(), timeout)
+            .await
+    }
+
+    async fn internal_send_msg_waiton_rsp(
+        &self,
+        message: HandleMessage,
+        timeout: Duration,
+    ) -> Result<JaResponse, Error> {
+        self.send_waiton_rsp(message.to_message_envelope(), timeout)
+            .await
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn fire_and_forget_msg_with_jsep(
+        &self,
+        message: HandleMessageWithJsep,
+    ) -> Result<String, Error> {
+        let transaction = self.send(message.to_message_envelope()).await?;
+        Ok(transaction)
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn send_msg_waiton_ack_with_jsep(
+        &self,
+        message: HandleMessageWithJsep,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        self.send_waiton_ack(message.to_message_envelope(), timeout)
+            .await
+    }
+
+    async fn send_handle_request(&self, request: HandleMessage) -> Result<(), Error> {
+        let mut req = request.body;
+        interface_support::merge_json(
+            &mut req,
+            &json!({
+                "session_id": request.session_id,
+                "handle_id": request.handle_id,
+            }),
+        );
+        _ = self.send(req).await?;
+        Ok(())
+    }
+
+    async fn send_handle_request_waiton_ack(
+        &self,
+        request: HandleMessage,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        let mut req = request.body;
+        interface_support::merge_json(
+            &mut req,
+            &json!({
+                "session_id": request.session_id,
+                "handle_id": request.handle_id,
+            }),
+        );
+        self.send_waiton_ack(req, timeout).await
+    }
+
+    fn name(&self) -> Box<str> {
+        self.inner.shared.name.clone()
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn close(&self) -> Result<(), Error> {
+        self.inner.shared.writer.close().await
+    }
+
+    fn task_group(&self) -> Option<JaTaskGroup> {
+        Some(self.inner.shared.tasks.clone())
+    }
+
+    fn parsing(&self) -> Parsing {
+        self.inner.shared.parsing.clone()
+    }
+}
+
+impl<T> std::fmt::Debug for StreamingJanusInterface<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("StreamingJanusInterface")
+            .field(&self.inner.shared.name)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamingJanusInterface;
+    use crate::janus_interface::ConnectionParams;
+    use crate::janus_interface::JanusInterface;
+    use crate::streaming::MessageTransport;
+    use crate::tgenerator::RandomTransactionGenerator;
+    use crate::Error;
+    use bytes::Bytes;
+    use jarust_rt::sync::mpsc;
+    use serde_json::json;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    /// Answers every request with a success carrying the id `4242`.
+    #[derive(Default)]
+    struct LoopbackTransport {
+        inbound: Option<mpsc::UnboundedSender<Bytes>>,
+    }
+
+    #[async_trait::async_trait]
+    impl MessageTransport for LoopbackTransport {
+        async fn connect(
+            &mut self,
+            _: &ConnectionParams,
+        ) -> Result<mpsc::UnboundedReceiver<Bytes>, Error> {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.inbound = Some(tx);
+            Ok(rx)
+        }
+
+        async fn send(&mut self, data: &[u8], _: &str) -> Result<(), Error> {
+            let request: Value = serde_json::from_slice(data)?;
+            let response = json!({
+                "janus": "success",
+                "transaction": request["transaction"],
+                "data": { "id": 4242 }
+            });
+            let inbound = self.inbound.as_ref().ok_or(Error::TransportNotOpened)?;
+            inbound
+                .send(response.to_string().into())
+                .map_err(|_| Error::SendError)
+        }
+
+        async fn close(&mut self) -> Result<(), Error> {
+            self.inbound = None;
+            Ok(())
+        }
+
+        fn name(&self) -> Box<str> {
+            "Loopback".to_string().into_boxed_str()
+        }
+    }
+
+    #[tokio::test]
+    async fn it_should_run_the_janus_api_over_a_custom_transport() {
+        let conn_params = ConnectionParams {
+            url: "loopback".to_string(),
+            capacity: 10,
+            apisecret: None,
+            server_root: "janus".to_string(),
+            events_url: None,
+            long_poll: Default::default(),
+            http_client: Default::default(),
+            websocket: Default::default(),
+            parse_mode: Default::default(),
+        };
+        let interface = StreamingJanusInterface::<LoopbackTransport>::make_interface(
+            conn_params,
+            RandomTransactionGenerator,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(&*interface.name(), "Loopback Interface");
+        let session_id = interface.create(Duration::from_secs(1)).await.unwrap();
+        assert_eq!(session_id, 4242);
+    }
+}
@@ -0,0 +1,126 @@
+//! The writer task of [`StreamingJanusInterface`], it owns the transport so concurrent requests
+//! queue on a channel instead of serializing behind a lock held for the whole write.
+//!
+//! [`StreamingJanusInterface`]: super::StreamingJanusInterface
+
+use super::MessageTransport;
+use crate::Error;
+use bytes::Bytes;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::oneshot;
+use jarust_rt::JaTaskGroup;
+
+/// Messages already queued are written together, up to this many.
+const MAX_BATCH: usize = 64;
+
+enum Command {
+    Send {
+        data: Bytes,
+        path: String,
+        done: oneshot::Sender<Result<(), Error>>,
+    },
+    Close {
+        done: oneshot::Sender<Result<(), Error>>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Writer {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Writer {
+    /// Spawns the writer task in `tasks`, moving `transport` into it.
+    pub(crate) fn spawn<T: MessageTransport>(transport: T, tasks: &JaTaskGroup) -> Self {
+        let (commands, receiver) = mpsc::unbounded_channel();
+        let signal = tasks.shutdown_signal();
+        tasks.spawn("Writer task", async move {
+            signal.until(run(transport, receiver)).await;
+        });
+        Self { commands }
+    }
+
+    /// Sends the message, resolving once it's written.
+    pub(crate) async fn send(&self, data: Bytes, path: String) -> Result<(), Error> {
+        let (done, result) = oneshot::channel();
+        self.commands
+            .send(Command::Send { data, path, done })
+            .map_err(|_| Error::TransportNotOpened)?;
+        result.await.map_err(|_| Error::TransportNotOpened)?
+    }
+
+    /// Closes the transport once the messages queued before are written.
+    pub(crate) async fn close(&self) -> Result<(), Error> {
+        let (done, result) = oneshot::channel();
+        if self.commands.send(Command::Close { done }).is_err() {
+            // The writer is gone with the transport
+            return Ok(());
+        }
+        result.await.unwrap_or(Ok(()))
+    }
+}
+
+async fn run<T: MessageTransport>(
+    mut transport: T,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+) {
+    let mut batch = Vec::with_capacity(MAX_BATCH);
+    let mut waiters = Vec::with_capacity(MAX_BATCH);
+    while let Some(command) = commands.recv().await {
+        // The requests are often sent by tasks woken together (e.g. by the same batch of
+        // replies), yielding once lets them queue behind the first one
+        yield_now().await;
+        let mut close = None;
+        let mut next = Some(command);
+        while let Some(command) = next.take() {
+            match command {
+                Command::Send { data, path, done } => {
+                    batch.push((data, path));
+                    waiters.push(done);
+                }
+                Command::Close { done } => {
+                    close = Some(done);
+                    break;
+                }
+            }
+            if batch.len() < MAX_BATCH {
+                next = commands.try_recv().ok();
+            }
+        }
+
+        if !batch.is_empty() {
+            tracing::trace!(messages = batch.len(), "Writing batch");
+            match transport.send_batch(&batch).await {
+                Ok(()) => waiters.drain(..).for_each(|done| _ = done.send(Ok(()))),
+                Err(err) => {
+                    tracing::error!(messages = batch.len(), "Failed to write batch: {err}");
+                    // The error isn't cloneable, the first message gets it and the rest a
+                    // generic send error
+                    let mut waiters = waiters.drain(..);
+                    if let Some(done) = waiters.next() {
+                        _ = done.send(Err(err));
+                    }
+                    waiters.for_each(|done| _ = done.send(Err(Error::SendError)));
+                }
+            }
+            batch.clear();
+        }
+
+        if let Some(done) = close {
+            _ = done.send(transport.close().await);
+        }
+    }
+}
+
+/// Yields to the other tasks once.
+async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if std::mem::replace(&mut yielded, true) {
+            return std::task::Poll::Ready(());
+        }
+        cx.waker().wake_by_ref();
+        std::task::Poll::Pending
+    })
+    .await
+}
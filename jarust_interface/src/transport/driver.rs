@@ -0,0 +1,228 @@
+//! Drives the sans-IO [`JanusProtocol`] for the async interfaces.
+//!
+//! The requests waiting on a reply get a oneshot receiver, completed as soon as the reply is
+//! fed. A waiter giving up (e.g. on timeout) cancels its request. The inbound messages are
+//! decoded outside of the lock, which is only held to match them with their request.
+
+use crate::japrotocol::JaResponse;
+use crate::japrotocol::ResponseType;
+use crate::parsing::Parsing;
+use crate::protocol::Expect;
+use crate::protocol::JanusProtocol;
+use crate::protocol::Output;
+use crate::protocol::Request;
+use crate::Error;
+use jarust_rt::sync::oneshot;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::Duration;
+
+#[derive(Debug)]
+struct State {
+    protocol: JanusProtocol,
+    waiters: HashMap<String, oneshot::Sender<JaResponse>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ProtocolDriver {
+    inner: Arc<Mutex<State>>,
+    parsing: Parsing,
+}
+
+impl ProtocolDriver {
+    pub(crate) fn new(protocol: JanusProtocol) -> Self {
+        Self {
+            parsing: protocol.parsing().clone(),
+            inner: Arc::new(Mutex::new(State {
+                protocol,
+                waiters: HashMap::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The protocol doesn't call user code, a panic can't leave it halfway
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Prepares a request no one waits on.
+    pub(crate) fn send(&self, request: Value) -> Request {
+        self.lock().protocol.send(request, None)
+    }
+
+    /// Prepares a request waiting on `expect`, registered before it's written so the reply
+    /// can't be missed.
+    pub(crate) fn send_waiton(&self, request: Value, expect: Expect) -> (Request, PendingReply) {
+        let (sender, receiver) = oneshot::channel();
+        let mut state = self.lock();
+        let request = state.protocol.send(request, Some(expect));
+        state.waiters.insert(request.transaction.clone(), sender);
+        drop(state);
+        let reply = PendingReply {
+            transaction: request.transaction.clone(),
+            receiver,
+            driver: self.clone(),
+        };
+        (request, reply)
+    }
+
+    /// Feeds an inbound message, completing the requests it replies to and returning the
+    /// events to route.
+    pub(crate) fn feed(&self, data: &[u8]) -> Vec<(String, JaResponse)> {
+        let responses = JanusProtocol::decode(&self.parsing, data);
+        let mut replies = vec![];
+        let mut events = vec![];
+        let mut state = self.lock();
+        for response in responses {
+            match state.protocol.classify(response) {
+                Output::Reply {
+                    transaction,
+                    response,
+                } => {
+                    if let Some(waiter) = state.waiters.remove(&transaction) {
+                        replies.push((waiter, response));
+                    }
+                }
+                Output::Event { path, event } => events.push((path, event)),
+                Output::Unhandled(_) => {}
+            }
+        }
+        drop(state);
+        for (waiter, response) in replies {
+            _ = waiter.send(response);
+        }
+        events
+    }
+
+    /// Cancels every pending request, e.g. once the connection is gone.
+    pub(crate) fn cancel_all(&self) {
+        let mut state = self.lock();
+        state.protocol.cancel_all();
+        state.waiters.clear();
+    }
+
+    #[allow(unused)]
+    pub(crate) fn pending(&self) -> usize {
+        self.lock().protocol.pending()
+    }
+}
+
+/// The reply of a pending request, dropping it cancels the request.
+#[derive(Debug)]
+pub(crate) struct PendingReply {
+    transaction: String,
+    receiver: oneshot::Receiver<JaResponse>,
+    driver: ProtocolDriver,
+}
+
+impl PendingReply {
+    /// Waits for the reply, bounded by `timeout`. A janus error reply is returned as
+    /// [`Error::JanusError`].
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all, fields(transaction = self.transaction))]
+    pub(crate) async fn wait(mut self, timeout: Duration) -> Result<JaResponse, Error> {
+        match jarust_rt::timeout(timeout, &mut self.receiver).await {
+            Ok(Ok(response)) => match response.janus {
+                ResponseType::Error { error } => Err(Error::JanusError {
+                    code: error.code,
+                    reason: error.reason,
+                }),
+                _ => Ok(response),
+            },
+            Ok(Err(_)) => {
+                tracing::error!("Request cancelled, the transport is gone");
+                Err(Error::TransportNotOpened)
+            }
+            Err(_) => {
+                tracing::error!("Request timeout");
+                Err(Error::RequestTimeout)
+            }
+        }
+    }
+}
+
+impl Drop for PendingReply {
+    fn drop(&mut self) {
+        let mut state = self.driver.lock();
+        state.protocol.cancel(&self.transaction);
+        state.waiters.remove(&self.transaction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProtocolDriver;
+    use crate::parsing::Parsing;
+    use crate::protocol::Expect;
+    use crate::protocol::JanusProtocol;
+    use crate::protocol::ProtocolParams;
+    use crate::tgenerator::TransactionGenerator;
+    use crate::Error;
+    use serde_json::json;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    fn driver() -> ProtocolDriver {
+        let next = AtomicU64::new(0);
+        ProtocolDriver::new(JanusProtocol::new(
+            ProtocolParams {
+                server_root: "janus".to_string(),
+                apisecret: None,
+                capacity: 8,
+                parsing: Parsing::default(),
+            },
+            TransactionGenerator::new(move || {
+                format!("tx{}", next.fetch_add(1, Ordering::Relaxed))
+            }),
+        ))
+    }
+
+    #[tokio::test]
+    async fn it_should_complete_every_pending_request_of_a_burst() {
+        let driver = driver();
+        let replies = (0..100)
+            .map(|_| {
+                driver
+                    .send_waiton(json!({ "janus": "keepalive" }), Expect::Ack)
+                    .1
+            })
+            .collect::<Vec<_>>();
+
+        for i in 0..100 {
+            let ack = json!({ "janus": "ack", "transaction": format!("tx{i}") });
+            assert!(driver.feed(ack.to_string().as_bytes()).is_empty());
+        }
+
+        for (i, reply) in replies.into_iter().enumerate() {
+            let rsp = reply.wait(Duration::from_secs(1)).await.unwrap();
+            assert_eq!(rsp.transaction, Some(format!("tx{i}")));
+        }
+        assert_eq!(driver.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn it_should_cancel_requests_on_timeout() {
+        let driver = driver();
+        let (_, reply) = driver.send_waiton(json!({ "janus": "info" }), Expect::Response);
+        assert_eq!(driver.pending(), 1);
+
+        let result = reply.wait(Duration::from_millis(10)).await;
+
+        assert!(matches!(result, Err(Error::RequestTimeout)));
+        assert_eq!(driver.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn it_should_fail_pending_requests_once_cancelled() {
+        let driver = driver();
+        let (_, reply) = driver.send_waiton(json!({ "janus": "info" }), Expect::Response);
+
+        driver.cancel_all();
+
+        let result = reply.wait(Duration::from_secs(1)).await;
+        assert!(matches!(result, Err(Error::TransportNotOpened)));
+    }
+}
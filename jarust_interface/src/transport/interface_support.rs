@@ -6,10 +6,8 @@ use crate::japrotocol::JaResponse;
 use crate::japrotocol::JaSuccessProtocol;
 use crate::japrotocol::ResponseType;
 use crate::tgenerator::TransactionGenerator;
-use crate::transport::napmap::NapMap;
 use crate::Error;
 use serde_json::Value;
-use std::time::Duration;
 
 /// Injects the optional `apisecret` and a freshly generated `transaction` into a
 /// request, returning the decorated request and the transaction id.
@@ -26,33 +24,6 @@ pub(crate) fn decorate_request(
     (request, transaction)
 }
 
-/// Waits for the response/ack carrying `transaction` to land in `map`, bounded by
-/// `timeout`. Used by streaming transports (WebSocket, Socket.IO) that demultiplex
-/// inbound frames into response and ack maps keyed by transaction.
-pub(crate) async fn poll_transaction(
-    map: &NapMap<String, JaResponse>,
-    transaction: &str,
-    timeout: Duration,
-) -> Result<JaResponse, Error> {
-    match tokio::time::timeout(timeout, map.get(transaction.to_string())).await {
-        Ok(Some(response)) => match response.janus {
-            ResponseType::Error { error } => Err(Error::JanusError {
-                code: error.code,
-                reason: error.reason,
-            }),
-            _ => Ok(response),
-        },
-        Ok(None) => {
-            tracing::error!("Incomplete packet");
-            Err(Error::IncompletePacket)
-        }
-        Err(_) => {
-            tracing::error!("Request timeout");
-            Err(Error::RequestTimeout)
-        }
-    }
-}
-
 /// Extracts the `id` from a `create`/`attach` success response, mapping Janus errors
 /// and unexpected shapes to the appropriate [`Error`].
 // The small `Ok(u64)` next to the (crate-wide) large `Error` enum trips
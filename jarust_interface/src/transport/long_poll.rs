@@ -0,0 +1,215 @@
+//! Per-session long-polling over the janus HTTP transport.
+//!
+//! A single loop polls `GET /{session_id}` and demultiplexes the received events to
+//! the handles of that session through the [`Router`], so handles sharing a session
+//! don't compete for the same events.
+
+use super::router::Router;
+use crate::janus_interface::LongPollParams;
+use crate::japrotocol::JaResponse;
+use crate::japrotocol::ResponseType;
+use crate::parsing::Parsing;
+use crate::protocol;
+use crate::Error;
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Delay applied before retrying after a failed request, so a downed or slow server
+/// doesn't turn the polling loop into a busy spin.
+const ERROR_BACKOFF: Duration = Duration::from_millis(500);
+
+pub(crate) struct LongPoll {
+    pub(crate) client: reqwest::Client,
+    /// The session url, i.e. `{url}/{server_root}/{session_id}`.
+    pub(crate) url: String,
+    pub(crate) session_id: u64,
+    pub(crate) params: LongPollParams,
+    pub(crate) router: Router,
+    pub(crate) parsing: Parsing,
+}
+
+impl LongPoll {
+    /// Async task polling the session events until the session is gone, which is returned as
+    /// an error. The routes of the session are closed once it stops.
+    #[tracing::instrument(name = "long_poll", level = tracing::Level::TRACE, skip_all, fields(session_id = self.session_id))]
+    pub(crate) async fn start(self) -> Result<(), Error> {
+        let result = self.poll().await;
+        self.router
+            .close_subroutes(&self.session_id.to_string())
+            .await;
+        result
+    }
+
+    async fn poll(&self) -> Result<(), Error> {
+        loop {
+            let mut url = format!("{}?maxev={}", self.url, self.params.maxev);
+            if self.params.rid {
+                url.push_str(&format!("&rid={}", rand::random::<u64>()));
+            }
+
+            let response = match self.client.get(&url).send().await {
+                Ok(response) => response,
+                Err(err) => {
+                    tracing::warn!("Long-poll request failed: {err}");
+                    jarust_rt::sleep(ERROR_BACKOFF).await;
+                    continue;
+                }
+            };
+
+            let body = match response.json::<Value>().await {
+                Ok(body) => body,
+                Err(err) => {
+                    tracing::warn!("Failed to decode long-poll response: {err}");
+                    jarust_rt::sleep(ERROR_BACKOFF).await;
+                    continue;
+                }
+            };
+
+            for mut event in parse_events(body, &self.parsing) {
+                if let ResponseType::Error { error } = &event.janus {
+                    // Errors on the long-poll endpoint are session level (e.g. 458 no such
+                    // session), there is nothing left to poll.
+                    tracing::error!("Long-poll failed, stopping task: {error:#?}");
+                    return Err(Error::JanusError {
+                        code: error.code,
+                        reason: error.reason.clone(),
+                    });
+                }
+
+                let session_id = *event.session_id.get_or_insert(self.session_id);
+                let Some(path) = protocol::path_from_response(&event) else {
+                    continue;
+                };
+                if self.router.pub_subroute(&path, event).await.is_err() {
+                    tracing::debug!(session_id, "Route receiver dropped");
+                }
+            }
+        }
+    }
+}
+
+/// Parses a long-poll body into responses.
+///
+/// With `maxev` janus replies with an array of events, otherwise with a single event.
+/// `keepalive` events (sent when nothing happened during the poll window) are skipped,
+/// unparsable events are handled according to the parse mode.
+fn parse_events(body: Value, parsing: &Parsing) -> Vec<JaResponse> {
+    let events = match body {
+        Value::Array(events) => events,
+        event => vec![event],
+    };
+    events
+        .into_iter()
+        .filter(|event| event["janus"] != "keepalive")
+        // Deserialized by reference, the raw event is only kept for the unparsed ones
+        .filter_map(|event| match JaResponse::deserialize(&event) {
+            Ok(response) => Some(response),
+            Err(err) => parsing.unparsed_response(event, err),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_events;
+    use super::LongPoll;
+    use crate::japrotocol::GenericEvent;
+    use crate::japrotocol::JaHandleEvent;
+    use crate::japrotocol::ResponseType;
+    use crate::parsing::ParseMode;
+    use crate::parsing::Parsing;
+    use crate::transport::router::Router;
+    use crate::Error;
+    use serde_json::json;
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// Spawns a single-shot HTTP server answering with `body`, and returns its url.
+    async fn server(body: serde_json::Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            _ = socket.read(&mut request).await.unwrap();
+            let body = body.to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn it_should_fail_and_close_the_session_routes_once_the_session_is_gone() {
+        let url = server(json!({
+            "janus": "error",
+            "session_id": 1,
+            "error": { "code": 458, "reason": "No such session 1" }
+        }))
+        .await;
+        let router = Router::new("janus");
+        let mut handle = router.add_subroute("1/2").await;
+        let long_poll = LongPoll {
+            client: reqwest::Client::new(),
+            url: format!("{url}/janus/1"),
+            session_id: 1,
+            params: Default::default(),
+            router,
+            parsing: Parsing::default(),
+        };
+
+        let result = long_poll.start().await;
+
+        assert!(matches!(result, Err(Error::JanusError { code: 458, .. })));
+        assert!(handle.recv().await.is_none());
+    }
+
+    #[test]
+    fn it_should_skip_keepalives() {
+        let events = parse_events(
+            json!([
+                { "janus": "keepalive" },
+                { "janus": "webrtcup", "session_id": 1, "sender": 2 }
+            ]),
+            &Parsing::default(),
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::WebrtcUp))
+        );
+    }
+
+    #[test]
+    fn it_should_accept_a_single_event() {
+        let events = parse_events(
+            json!({ "janus": "hangup", "session_id": 1, "sender": 2 }),
+            &Parsing::default(),
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sender, Some(2));
+    }
+
+    #[test]
+    fn it_should_keep_unparsable_events_in_lenient_mode() {
+        let event = json!({ "janus": "future_event", "session_id": 1, "sender": 2 });
+        let strict = Parsing::default();
+        assert!(parse_events(event.clone(), &strict).is_empty());
+        assert_eq!(strict.failures(), 1);
+
+        let lenient = Parsing::new(ParseMode::Lenient);
+        let events = parse_events(event, &lenient);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sender, Some(2));
+        assert!(matches!(
+            events[0].janus,
+            ResponseType::Event(JaHandleEvent::GenericEvent(GenericEvent::Unparsed { .. }))
+        ));
+        assert_eq!(lenient.failures(), 1);
+    }
+}
@@ -1,6 +1,6 @@
 pub(crate) mod demuxer;
+pub(crate) mod driver;
 pub(crate) mod interface_support;
-pub(crate) mod napmap;
+pub(crate) mod long_poll;
 pub(crate) mod ringbuf_map;
 pub(crate) mod router;
-pub(crate) mod tmanager;
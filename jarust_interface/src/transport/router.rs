@@ -1,10 +1,9 @@
 use crate::japrotocol::JaResponse;
 use crate::Error;
-use serde_json::Value;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::RwLock;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tokio::sync::RwLock;
 
 #[derive(Debug)]
 struct Shared {
@@ -46,7 +45,7 @@ impl Router {
     }
 
     #[tracing::instrument(level = tracing::Level::TRACE, skip(self))]
-    async fn make_route(&mut self, path: &str) -> mpsc::UnboundedReceiver<JaResponse> {
+    async fn make_route(&self, path: &str) -> mpsc::UnboundedReceiver<JaResponse> {
         let (tx, rx) = mpsc::unbounded_channel();
         {
             self.inner
@@ -60,7 +59,7 @@ impl Router {
         rx
     }
 
-    pub(crate) async fn add_subroute(&mut self, end: &str) -> mpsc::UnboundedReceiver<JaResponse> {
+    pub(crate) async fn add_subroute(&self, end: &str) -> mpsc::UnboundedReceiver<JaResponse> {
         let path = &format!("{}/{}", self.inner.shared.root_path, end);
         self.make_route(path).await
     }
@@ -72,7 +71,7 @@ impl Router {
             guard.routes.get(path).cloned()
         };
         if let Some(channel) = channel {
-            if channel.send(message.clone()).is_err() {
+            if channel.send(message).is_err() {
                 return Err(Error::SendError);
             }
         }
@@ -88,28 +87,26 @@ impl Router {
         let path = &format!("{}/{}", self.inner.shared.root_path, subroute);
         self.publish(path, message).await
     }
-}
 
-impl Router {
-    pub fn path_from_request(request: &Value) -> Option<String> {
-        if let (Some(session_id), Some(handle_id)) = (
-            request["session_id"].as_u64(),
-            request["handle_id"].as_u64(),
-        ) {
-            Some(format!("{session_id}/{handle_id}"))
-        } else {
-            request["session_id"]
-                .as_u64()
-                .map(|session_id| format!("{session_id}"))
-        }
+    /// Drops every route, ending the receivers handed out by [`Router::add_subroute`].
+    #[tracing::instrument(level = tracing::Level::TRACE, skip(self))]
+    pub(crate) async fn close_routes(&self) {
+        self.inner.exclusive.write().await.routes.clear();
+        tracing::trace!("Routes closed");
     }
 
-    pub fn path_from_response(response: &JaResponse) -> Option<String> {
-        let session_id = response.session_id?;
-        let Some(sender) = response.sender else {
-            return Some(format!("{session_id}"));
-        };
-        Some(format!("{session_id}/{sender}"))
+    /// Drops the subroute and the routes under it, e.g. the handles of a session.
+    #[tracing::instrument(level = tracing::Level::TRACE, skip(self))]
+    pub(crate) async fn close_subroutes(&self, subroute: &str) {
+        let path = format!("{}/{}", self.inner.shared.root_path, subroute);
+        let nested = format!("{path}/");
+        self.inner
+            .exclusive
+            .write()
+            .await
+            .routes
+            .retain(|route, _| *route != path && !route.starts_with(&nested));
+        tracing::trace!("Subroutes closed");
     }
 }
 
@@ -121,7 +118,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_basic_usage() {
-        let mut router = Router::new("janus");
+        let router = Router::new("janus");
         let mut channel_one = router.add_subroute("one").await;
         let mut channel_two = router.add_subroute("two").await;
 
@@ -175,4 +172,31 @@ mod tests {
         assert_eq!(size_one, 1);
         assert_eq!(size_two, 2);
     }
+
+    #[tokio::test]
+    async fn it_should_end_receivers_when_routes_are_closed() {
+        let router = Router::new("janus");
+        let mut channel = router.add_subroute("one").await;
+        router.close_routes().await;
+        assert!(channel.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn it_should_only_end_the_closed_subroutes() {
+        let router = Router::new("janus");
+        let mut handle = router.add_subroute("1/2").await;
+        let mut other_session = router.add_subroute("12/3").await;
+        router.close_subroutes("1").await;
+        assert!(handle.recv().await.is_none());
+
+        let ack = JaResponse {
+            janus: ResponseType::Ack,
+            transaction: None,
+            session_id: None,
+            sender: None,
+            jsep: None,
+        };
+        router.pub_subroute("12/3", ack).await.unwrap();
+        assert!(other_session.recv().await.is_some());
+    }
 }
@@ -12,4 +12,15 @@ mod adapter;
 #[path = "rustls_adapter.rs"]
 mod adapter;
 
+mod proxy;
+
+use crate::Error;
+use std::fmt::Display;
+
 pub use adapter::connect_async;
+
+fn invalid_tls_config(err: impl Display) -> Error {
+    Error::InvalidTlsConfig {
+        reason: err.to_string(),
+    }
+}
@@ -0,0 +1,100 @@
+//! HTTP `CONNECT` tunneling, the WebSocket (and its TLS session) then runs on top of the tunnel.
+
+use crate::Error;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::http::Uri;
+
+/// Upper bound of the proxy response head, so a misbehaving proxy can't grow it forever.
+const MAX_RESPONSE_HEAD: usize = 8 * 1024;
+
+fn proxy_error(reason: impl Into<String>) -> Error {
+    Error::Proxy {
+        reason: reason.into(),
+    }
+}
+
+/// Opens a tunnel to the host of `uri` through the HTTP proxy at `proxy`
+/// (`http://host:port` or `host:port`).
+#[tracing::instrument(level = tracing::Level::TRACE, skip(uri))]
+pub(super) async fn connect(proxy: &str, uri: &Uri) -> Result<TcpStream, Error> {
+    let proxy = proxy.strip_prefix("http://").unwrap_or(proxy);
+    let proxy = proxy.trim_end_matches('/');
+    let host = uri
+        .host()
+        .ok_or_else(|| proxy_error("missing host in the WebSocket url"))?;
+    let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+        Some("wss") => 443,
+        _ => 80,
+    });
+    let target = format!("{host}:{port}");
+
+    tracing::debug!("Tunneling to {target}");
+    let mut stream = TcpStream::connect(proxy).await?;
+    stream
+        .write_all(format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n\r\n").as_bytes())
+        .await?;
+
+    // Read byte by byte, anything past the head belongs to the tunneled connection
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_RESPONSE_HEAD {
+            return Err(proxy_error("response head too large"));
+        }
+        head.push(stream.read_u8().await?);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(stream),
+        _ => Err(proxy_error(format!("tunnel refused: {status_line}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::connect;
+    use crate::Error;
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// Spawns a single-shot proxy answering with `response`, returns its address and the received request.
+    async fn fake_proxy(response: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(socket.read_u8().await.unwrap());
+            }
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (addr, handle)
+    }
+
+    #[tokio::test]
+    async fn it_should_tunnel_to_the_default_port() {
+        let (addr, proxy) = fake_proxy("HTTP/1.1 200 Connection established\r\n\r\n").await;
+        let uri = "wss://janus.example.com/ws".parse().unwrap();
+        assert!(connect(&format!("http://{addr}"), &uri).await.is_ok());
+        assert_eq!(
+            proxy.await.unwrap(),
+            "CONNECT janus.example.com:443 HTTP/1.1\r\nHost: janus.example.com:443\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_should_fail_when_the_proxy_refuses() {
+        let (addr, _proxy) = fake_proxy("HTTP/1.1 407 Proxy Authentication Required\r\n\r\n").await;
+        let uri = "ws://janus.example.com:8188".parse().unwrap();
+        assert!(matches!(
+            connect(&addr, &uri).await,
+            Err(Error::Proxy { .. })
+        ));
+    }
+}
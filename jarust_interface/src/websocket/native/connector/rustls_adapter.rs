@@ -1,28 +1,62 @@
+use super::invalid_tls_config;
+use super::proxy;
+use crate::janus_interface::WebSocketParams;
 use crate::Error;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use rustls::pki_types::PrivateKeyDer;
 use rustls::RootCertStore;
 use std::sync::Arc;
 use tokio::net::TcpStream;
+use tokio_tungstenite::client_async_tls_with_config;
 use tokio_tungstenite::connect_async_tls_with_config;
 use tokio_tungstenite::tungstenite::handshake::client::Request;
 use tokio_tungstenite::Connector;
 use tokio_tungstenite::MaybeTlsStream;
 use tokio_tungstenite::WebSocketStream;
 
-fn make_tls_client_config() -> Result<Arc<rustls::ClientConfig>, Error> {
+#[allow(clippy::result_large_err)]
+fn make_tls_client_config(params: &WebSocketParams) -> Result<Arc<rustls::ClientConfig>, Error> {
     let mut root_store = RootCertStore::empty();
     let platform_certs = rustls_native_certs::load_native_certs().certs;
     root_store.add_parsable_certificates(platform_certs);
-    let client_config = rustls::ClientConfig::builder()
-        .with_root_certificates(root_store)
-        .with_no_client_auth();
+    for pem in &params.root_certificates {
+        for cert in CertificateDer::pem_slice_iter(pem) {
+            root_store
+                .add(cert.map_err(invalid_tls_config)?)
+                .map_err(invalid_tls_config)?;
+        }
+    }
+
+    let builder = rustls::ClientConfig::builder().with_root_certificates(root_store);
+    let client_config = match &params.identity {
+        Some(pem) => {
+            let cert_chain = CertificateDer::pem_slice_iter(pem)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(invalid_tls_config)?;
+            let key = PrivateKeyDer::from_pem_slice(pem).map_err(invalid_tls_config)?;
+            builder
+                .with_client_auth_cert(cert_chain, key)
+                .map_err(invalid_tls_config)?
+        }
+        None => builder.with_no_client_auth(),
+    };
     Ok(Arc::new(client_config))
 }
 
 pub async fn connect_async(
     request: Request,
+    params: &WebSocketParams,
 ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, Error> {
     tracing::trace!("Using rustls");
-    let connector = Connector::Rustls(make_tls_client_config()?);
-    let (stream, ..) = connect_async_tls_with_config(request, None, true, Some(connector)).await?;
+    let connector = Some(Connector::Rustls(make_tls_client_config(params)?));
+    let (stream, ..) = match &params.proxy {
+        Some(proxy) => {
+            let tunnel = proxy::connect(proxy, request.uri()).await?;
+            tunnel.set_nodelay(true)?;
+            client_async_tls_with_config(request, tunnel, None, connector).await?
+        }
+        None => connect_async_tls_with_config(request, None, true, connector).await?,
+    };
     Ok(stream)
 }
@@ -1,6 +1,7 @@
 mod connector;
-mod websocket_client;
+pub(crate) mod websocket_client;
 
 pub mod websocket_interface;
 
+pub use websocket_client::WebSocketClient;
 pub use websocket_interface::WebSocketInterface;
@@ -1,20 +1,39 @@
 use super::connector;
+use crate::janus_interface::ConnectionParams;
+use crate::janus_interface::WebSocketHeartbeat;
+use crate::janus_interface::WebSocketParams;
+use crate::streaming::MessageTransport;
 use crate::Error;
 use bytes::Bytes;
 use futures_util::stream::SplitSink;
+use futures_util::stream::SplitStream;
 use futures_util::stream::StreamExt;
 use futures_util::SinkExt;
+use jarust_rt::sync::mpsc;
+use jarust_rt::sync::Mutex;
 use jarust_rt::JaTask;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpStream;
-use tokio::sync::mpsc;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderName;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::MaybeTlsStream;
 use tokio_tungstenite::WebSocketStream;
 
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type WsSink = Arc<Mutex<SplitSink<WsStream, Message>>>;
+
+/// Bound of the reply to a server close without a heartbeat, a writer may hold the sink on a
+/// full socket.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// [`MessageTransport`] over a native WebSocket connection, configured by [`WebSocketParams`].
 #[derive(Debug)]
 pub struct WebSocketClient {
-    sender: Option<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>,
+    /// Shared with the incoming messages task, which sends the heartbeat pings.
+    sender: Option<WsSink>,
     task: Option<JaTask>,
 }
 
@@ -32,40 +51,184 @@ impl WebSocketClient {
         }
     }
 
+    /// Connects to `url`, which may differ from the janus url (e.g. the events url of the hybrid interface).
     #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
-    pub async fn connect(&mut self, url: &str) -> Result<mpsc::UnboundedReceiver<Bytes>, Error> {
+    pub(crate) async fn open(
+        &mut self,
+        url: &str,
+        params: &WebSocketParams,
+    ) -> Result<mpsc::UnboundedReceiver<Bytes>, Error> {
         tracing::debug!("Connecting to {url}");
         let mut request = url.into_client_request()?;
         let headers = request.headers_mut();
-        headers.insert("Sec-Websocket-Protocol", "janus-protocol".parse()?);
-        let stream = connector::connect_async(request).await?;
+        for (name, value) in &params.headers {
+            let invalid = || Error::InvalidHeader { name: name.clone() };
+            let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?;
+            let header_value = HeaderValue::from_str(value).map_err(|_| invalid())?;
+            headers.append(header_name, header_value);
+        }
+        headers.insert("Sec-Websocket-Protocol", params.protocol.parse()?);
+        let stream = connector::connect_async(request, params).await?;
 
-        let (sender, mut receiver) = stream.split();
+        let (sender, receiver) = stream.split();
+        let sender = Arc::new(Mutex::new(sender));
         let (tx, rx) = mpsc::unbounded_channel();
 
-        let task = jarust_rt::spawn("WebSocket incoming messages", async move {
-            while let Some(Ok(message)) = receiver.next().await {
-                if let Message::Text(text) = message {
-                    let _ = tx.send(text.into());
-                }
-            }
-        });
+        let task = jarust_rt::spawn(
+            "WebSocket incoming messages",
+            Self::receive(receiver, sender.clone(), tx, params.heartbeat),
+        );
 
         self.sender = Some(sender);
         self.task = Some(task);
         Ok(rx)
     }
 
-    pub async fn send(&mut self, data: &[u8], _: &str) -> Result<(), Error> {
+    /// Forwards the incoming text messages to `tx` until the connection is closed by the
+    /// server, fails, or (with a heartbeat) goes silent.
+    ///
+    /// Pings and closes are answered by tungstenite while reading, the replies are flushed
+    /// on the next read or write.
+    async fn receive(
+        mut receiver: SplitStream<WsStream>,
+        sender: WsSink,
+        tx: mpsc::UnboundedSender<Bytes>,
+        heartbeat: Option<WebSocketHeartbeat>,
+    ) {
+        let mut awaiting_pong = false;
+        loop {
+            let next = match heartbeat {
+                Some(heartbeat) => {
+                    let silence = if awaiting_pong {
+                        heartbeat.timeout
+                    } else {
+                        heartbeat.interval
+                    };
+                    match jarust_rt::timeout(silence, receiver.next()).await {
+                        Ok(next) => next,
+                        Err(_) if awaiting_pong => {
+                            tracing::error!(
+                                "No pong received in {silence:?}, closing the connection"
+                            );
+                            // Bounded with the lock, a writer may hold it on a full socket
+                            let close = async { sender.lock().await.close().await };
+                            let _ = jarust_rt::timeout(heartbeat.timeout, close).await;
+                            break;
+                        }
+                        Err(_) => {
+                            tracing::trace!("Sending ping");
+                            let ping = async {
+                                sender.lock().await.send(Message::Ping(Bytes::new())).await
+                            };
+                            match jarust_rt::timeout(heartbeat.timeout, ping).await {
+                                Ok(Ok(())) => {}
+                                Ok(Err(err)) => {
+                                    tracing::error!("Failed to send ping: {err}");
+                                    break;
+                                }
+                                Err(_) => {
+                                    tracing::error!(
+                                        "Ping not sent in {:?}, the peer isn't reading",
+                                        heartbeat.timeout
+                                    );
+                                    break;
+                                }
+                            }
+                            awaiting_pong = true;
+                            continue;
+                        }
+                    }
+                }
+                None => receiver.next().await,
+            };
+            // Any frame proves the peer is alive
+            awaiting_pong = false;
+
+            match next {
+                Some(Ok(Message::Text(text))) => {
+                    let _ = tx.send(text.into());
+                }
+                Some(Ok(Message::Close(frame))) => {
+                    match frame {
+                        Some(frame) => tracing::info!(
+                            "Connection closed by the server {{ code: {}, reason: {} }}",
+                            frame.code,
+                            frame.reason
+                        ),
+                        None => tracing::info!("Connection closed by the server"),
+                    }
+                    // Flushes the queued close reply, bounded with the lock as for the pings
+                    let timeout = heartbeat.map_or(CLOSE_TIMEOUT, |heartbeat| heartbeat.timeout);
+                    let close = async { sender.lock().await.close().await };
+                    if jarust_rt::timeout(timeout, close).await.is_err() {
+                        tracing::warn!(
+                            "Close reply not sent in {timeout:?}, the peer isn't reading"
+                        );
+                    }
+                    break;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => {
+                    tracing::error!("WebSocket connection failed: {err}");
+                    break;
+                }
+                None => {
+                    tracing::warn!("WebSocket connection ended");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageTransport for WebSocketClient {
+    async fn connect(
+        &mut self,
+        conn_params: &ConnectionParams,
+    ) -> Result<mpsc::UnboundedReceiver<Bytes>, Error> {
+        self.open(&conn_params.url, &conn_params.websocket).await
+    }
+
+    async fn send(&mut self, data: &[u8], _: &str) -> Result<(), Error> {
         let item = Message::Binary(data.to_vec().into());
-        if let Some(sender) = &mut self.sender {
-            sender.send(item).await?;
+        if let Some(sender) = &self.sender {
+            sender.lock().await.send(item).await?;
         } else {
             tracing::error!("Transport not opened!");
             return Err(Error::TransportNotOpened);
         }
         Ok(())
     }
+
+    async fn send_batch(&mut self, batch: &[(Bytes, String)]) -> Result<(), Error> {
+        let Some(sender) = &self.sender else {
+            tracing::error!("Transport not opened!");
+            return Err(Error::TransportNotOpened);
+        };
+        // Fed without flushing, so the whole batch goes out in a single flush
+        let mut sender = sender.lock().await;
+        for (data, _) in batch {
+            sender.feed(Message::Binary(data.clone())).await?;
+        }
+        sender.flush().await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn close(&mut self) -> Result<(), Error> {
+        if let Some(task) = self.task.take() {
+            task.cancel();
+        }
+        if let Some(sender) = self.sender.take() {
+            sender.lock().await.close().await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> Box<str> {
+        "WebSocket".to_string().into_boxed_str()
+    }
 }
 
 impl Drop for WebSocketClient {
@@ -77,3 +240,156 @@ impl Drop for WebSocketClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::WebSocketClient;
+    use crate::janus_interface::WebSocketHeartbeat;
+    use crate::janus_interface::WebSocketParams;
+    use crate::streaming::MessageTransport;
+    use futures_util::SinkExt;
+    use std::future::Future;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::tungstenite::handshake::server::ErrorResponse;
+    use tokio_tungstenite::tungstenite::handshake::server::Request;
+    use tokio_tungstenite::tungstenite::handshake::server::Response;
+    use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+    use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::WebSocketStream;
+
+    /// Echoes the subprotocol requested by the client.
+    #[allow(clippy::result_large_err)]
+    fn accept_janus_protocol(
+        _: &Request,
+        mut response: Response,
+    ) -> Result<Response, ErrorResponse> {
+        response
+            .headers_mut()
+            .insert("Sec-Websocket-Protocol", "janus-protocol".parse().unwrap());
+        Ok(response)
+    }
+
+    /// Spawns a single-shot server and returns its url, the server runs `on_open` on the accepted socket.
+    async fn server<F, Fut>(on_open: F) -> String
+    where
+        F: FnOnce(WebSocketStream<TcpStream>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let ws = tokio_tungstenite::accept_hdr_async(socket, accept_janus_protocol)
+                .await
+                .unwrap();
+            on_open(ws).await;
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn it_should_close_silent_connections() {
+        // The server never reads, so pings are never answered
+        let url = server(|ws| async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            drop(ws);
+        })
+        .await;
+        let params = WebSocketParams {
+            heartbeat: Some(WebSocketHeartbeat {
+                interval: Duration::from_millis(50),
+                timeout: Duration::from_millis(50),
+            }),
+            ..Default::default()
+        };
+        let mut client = WebSocketClient::new();
+        let mut receiver = client.open(&url, &params).await.unwrap();
+        let closed = tokio::time::timeout(Duration::from_secs(2), receiver.recv()).await;
+        assert!(matches!(closed, Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn it_should_close_connections_whose_peer_stops_reading() {
+        // The server never reads, so the socket fills up and the writes block
+        let url = server(|ws| async move {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            drop(ws);
+        })
+        .await;
+        let params = WebSocketParams {
+            heartbeat: Some(WebSocketHeartbeat {
+                interval: Duration::from_millis(100),
+                timeout: Duration::from_millis(100),
+            }),
+            ..Default::default()
+        };
+        let mut client = WebSocketClient::new();
+        let mut receiver = client.open(&url, &params).await.unwrap();
+        let writer = tokio::spawn(async move {
+            let data = vec![0; 1 << 20];
+            while MessageTransport::send(&mut client, &data, "janus")
+                .await
+                .is_ok()
+            {}
+        });
+
+        let closed = tokio::time::timeout(Duration::from_secs(3), receiver.recv()).await;
+        assert!(matches!(closed, Ok(None)));
+        writer.abort();
+    }
+
+    #[tokio::test]
+    async fn it_should_end_on_close_frames_while_a_write_is_blocked() {
+        // The server never reads, then closes once the socket is full
+        let url = server(|mut ws| async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            ws.send(Message::Close(None)).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        })
+        .await;
+        let params = WebSocketParams {
+            heartbeat: Some(WebSocketHeartbeat {
+                interval: Duration::from_secs(10),
+                timeout: Duration::from_millis(100),
+            }),
+            ..Default::default()
+        };
+        let mut client = WebSocketClient::new();
+        let mut receiver = client.open(&url, &params).await.unwrap();
+        let writer = tokio::spawn(async move {
+            let data = vec![0; 1 << 20];
+            while MessageTransport::send(&mut client, &data, "janus")
+                .await
+                .is_ok()
+            {}
+        });
+
+        let closed = tokio::time::timeout(Duration::from_secs(3), receiver.recv()).await;
+        assert!(matches!(closed, Ok(None)));
+        writer.abort();
+    }
+
+    #[tokio::test]
+    async fn it_should_end_on_close_frames() {
+        let url = server(|mut ws| async move {
+            ws.send(Message::text(r#"{"janus":"ack"}"#)).await.unwrap();
+            ws.close(Some(CloseFrame {
+                code: CloseCode::Away,
+                reason: "restarting".into(),
+            }))
+            .await
+            .unwrap();
+        })
+        .await;
+        let mut client = WebSocketClient::new();
+        let mut receiver = client
+            .open(&url, &WebSocketParams::default())
+            .await
+            .unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), r#"{"janus":"ack"}"#);
+        assert!(receiver.recv().await.is_none());
+    }
+}
@@ -2,4 +2,5 @@ mod websocket_client;
 
 pub mod websocket_interface;
 
+pub use websocket_client::WebSocketClient;
 pub use websocket_interface::WebSocketInterface;
@@ -1,13 +1,17 @@
+use crate::janus_interface::ConnectionParams;
+use crate::streaming::MessageTransport;
 use crate::Error;
 use bytes::Bytes;
 use futures_util::stream::SplitSink;
 use futures_util::stream::StreamExt;
 use futures_util::SinkExt;
+use jarust_rt::sync::mpsc;
 use jarust_rt::JaTask;
-use tokio::sync::mpsc;
 use tokio_tungstenite_wasm::Message;
 use tokio_tungstenite_wasm::WebSocketStream;
 
+/// [`MessageTransport`] over a browser WebSocket connection, only the subprotocol of the
+/// [`WebSocketParams`](crate::janus_interface::WebSocketParams) applies.
 pub struct WebSocketClient {
     sender: Option<SplitSink<WebSocketStream, Message>>,
     task: Option<JaTask>,
@@ -26,12 +30,19 @@ impl WebSocketClient {
             task: None,
         }
     }
+}
 
+#[async_trait::async_trait(?Send)]
+impl MessageTransport for WebSocketClient {
     #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
-    pub async fn connect(&mut self, url: &str) -> Result<mpsc::UnboundedReceiver<Bytes>, Error> {
+    async fn connect(
+        &mut self,
+        conn_params: &ConnectionParams,
+    ) -> Result<mpsc::UnboundedReceiver<Bytes>, Error> {
+        let url = &conn_params.url;
         tracing::debug!("Connecting to {url}");
-        let stream =
-            tokio_tungstenite_wasm::connect_with_protocols(url, &["janus-protocol"]).await?;
+        let protocol = conn_params.websocket.protocol.as_str();
+        let stream = tokio_tungstenite_wasm::connect_with_protocols(url, &[protocol]).await?;
 
         let (sender, mut receiver) = stream.split();
         let (tx, rx) = mpsc::unbounded_channel();
@@ -49,7 +60,7 @@ impl WebSocketClient {
         Ok(rx)
     }
 
-    pub async fn send(&mut self, data: &[u8], _: &str) -> Result<(), Error> {
+    async fn send(&mut self, data: &[u8], _: &str) -> Result<(), Error> {
         let text = String::from_utf8_lossy(data).into_owned();
         let item = Message::Text(text.into());
         if let Some(sender) = &mut self.sender {
@@ -60,6 +71,21 @@ impl WebSocketClient {
         }
         Ok(())
     }
+
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    async fn close(&mut self) -> Result<(), Error> {
+        if let Some(task) = self.task.take() {
+            task.cancel();
+        }
+        if let Some(mut sender) = self.sender.take() {
+            sender.close().await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> Box<str> {
+        "WebSocket WASM".to_string().into_boxed_str()
+    }
 }
 
 impl Drop for WebSocketClient {
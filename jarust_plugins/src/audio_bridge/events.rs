@@ -7,7 +7,6 @@ use jarust_interface::japrotocol::Jsep;
 use jarust_interface::japrotocol::PluginInnerData;
 use jarust_interface::japrotocol::ResponseType;
 use serde::Deserialize;
-use serde_json::from_value;
 use serde_json::Value;
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Deserialize)]
@@ -137,100 +136,114 @@ pub enum AudioBridgeEvent {
     Other(Value),
 }
 
-impl TryFrom<JaResponse> for PluginEvent {
+impl From<GenericEvent> for PluginEvent {
+    fn from(value: GenericEvent) -> Self {
+        Self::GenericEvent(value)
+    }
+}
+
+impl TryFrom<&JaResponse> for PluginEvent {
     type Error = jarust_interface::Error;
 
-    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
-        match value.janus {
+    fn try_from(value: &JaResponse) -> Result<Self, Self::Error> {
+        match &value.janus {
             ResponseType::Event(JaHandleEvent::PluginEvent { plugin_data }) => {
-                let audiobridge_event = match plugin_data.data {
-                    PluginInnerData::Error { error_code, error } => {
-                        AudioBridgeEvent::Error { error_code, error }
-                    }
-                    PluginInnerData::Data(data) => {
-                        match from_value::<AudioBridgeEventDto>(data.clone()) {
-                            Ok(event) => match event {
-                                AudioBridgeEventDto::Event(AudioBridgeEventEventType::Result {
-                                    result,
-                                }) => match (value.transaction, value.jsep) {
-                                    (None, _) => return Err(Self::Error::IncompletePacket),
-                                    (Some(transaction), Some(jsep)) => {
-                                        AudioBridgeEvent::ResultWithJsep {
-                                            transaction,
-                                            result,
-                                            jsep,
-                                        }
-                                    }
-                                    (Some(transaction), None) => AudioBridgeEvent::Result {
+                let audiobridge_event = match &plugin_data.data {
+                    PluginInnerData::Error { error_code, error } => AudioBridgeEvent::Error {
+                        error_code: *error_code,
+                        error: error.clone(),
+                    },
+                    PluginInnerData::Data(data) => match AudioBridgeEventDto::deserialize(data) {
+                        Ok(event) => match event {
+                            AudioBridgeEventDto::Event(AudioBridgeEventEventType::Result {
+                                result,
+                            }) => match (value.transaction.clone(), value.jsep.clone()) {
+                                (None, _) => return Err(Self::Error::IncompletePacket),
+                                (Some(transaction), Some(jsep)) => {
+                                    AudioBridgeEvent::ResultWithJsep {
                                         transaction,
                                         result,
-                                    },
+                                        jsep,
+                                    }
+                                }
+                                (Some(transaction), None) => AudioBridgeEvent::Result {
+                                    transaction,
+                                    result,
                                 },
-                                AudioBridgeEventDto::Joined(AudioBridgeJoinedEventDto::Room {
+                            },
+                            AudioBridgeEventDto::Joined(AudioBridgeJoinedEventDto::Room {
+                                id,
+                                room,
+                                participants,
+                            }) => match value.jsep.clone() {
+                                Some(jsep) => AudioBridgeEvent::RoomJoinedWithJsep {
                                     id,
                                     room,
                                     participants,
-                                }) => match value.jsep {
-                                    Some(jsep) => AudioBridgeEvent::RoomJoinedWithJsep {
-                                        id,
-                                        room,
-                                        participants,
-                                        jsep,
-                                    },
-                                    None => AudioBridgeEvent::RoomJoined {
-                                        id,
-                                        room,
-                                        participants,
-                                    },
+                                    jsep,
                                 },
-                                AudioBridgeEventDto::Joined(
-                                    AudioBridgeJoinedEventDto::Participant { room, participants },
-                                ) => AudioBridgeEvent::ParticipantsJoined { room, participants },
-                                AudioBridgeEventDto::RoomLeft { id, room } => {
-                                    AudioBridgeEvent::RoomLeft { id, room }
-                                }
-                                AudioBridgeEventDto::RoomChanged {
+                                None => AudioBridgeEvent::RoomJoined {
                                     id,
                                     room,
                                     participants,
-                                } => AudioBridgeEvent::RoomChanged {
-                                    id,
+                                },
+                            },
+                            AudioBridgeEventDto::Joined(
+                                AudioBridgeJoinedEventDto::Participant { room, participants },
+                            ) => AudioBridgeEvent::ParticipantsJoined { room, participants },
+                            AudioBridgeEventDto::RoomLeft { id, room } => {
+                                AudioBridgeEvent::RoomLeft { id, room }
+                            }
+                            AudioBridgeEventDto::RoomChanged {
+                                id,
+                                room,
+                                participants,
+                            } => AudioBridgeEvent::RoomChanged {
+                                id,
+                                room,
+                                participants,
+                            },
+                            AudioBridgeEventDto::Event(
+                                AudioBridgeEventEventType::ParticipantsUpdated {
                                     room,
                                     participants,
                                 },
-                                AudioBridgeEventDto::Event(
-                                    AudioBridgeEventEventType::ParticipantsUpdated {
-                                        room,
-                                        participants,
-                                    },
-                                ) => AudioBridgeEvent::ParticipantsUpdated { room, participants },
-                                AudioBridgeEventDto::Event(
-                                    AudioBridgeEventEventType::RoomMuteUpdated { room, muted },
-                                ) => AudioBridgeEvent::RoomMuteUpdated { room, muted },
-                                AudioBridgeEventDto::Event(
-                                    AudioBridgeEventEventType::ParticipantKicked { room, kicked },
-                                ) => AudioBridgeEvent::ParticipantKicked { room, kicked },
-                                AudioBridgeEventDto::Event(
-                                    AudioBridgeEventEventType::ParticipantLeft { room, leaving },
-                                ) => AudioBridgeEvent::ParticipantLeft { room, leaving },
-                                AudioBridgeEventDto::Event(
-                                    AudioBridgeEventEventType::KickedAll { room, kicked_all },
-                                ) => AudioBridgeEvent::KickedAll { room, kicked_all },
-                            },
-                            Err(_) => AudioBridgeEvent::Other(data),
-                        }
-                    }
+                            ) => AudioBridgeEvent::ParticipantsUpdated { room, participants },
+                            AudioBridgeEventDto::Event(
+                                AudioBridgeEventEventType::RoomMuteUpdated { room, muted },
+                            ) => AudioBridgeEvent::RoomMuteUpdated { room, muted },
+                            AudioBridgeEventDto::Event(
+                                AudioBridgeEventEventType::ParticipantKicked { room, kicked },
+                            ) => AudioBridgeEvent::ParticipantKicked { room, kicked },
+                            AudioBridgeEventDto::Event(
+                                AudioBridgeEventEventType::ParticipantLeft { room, leaving },
+                            ) => AudioBridgeEvent::ParticipantLeft { room, leaving },
+                            AudioBridgeEventDto::Event(AudioBridgeEventEventType::KickedAll {
+                                room,
+                                kicked_all,
+                            }) => AudioBridgeEvent::KickedAll { room, kicked_all },
+                        },
+                        Err(_) => AudioBridgeEvent::Other(data.clone()),
+                    },
                 };
                 Ok(PluginEvent::AudioBridgeEvent(audiobridge_event))
             }
             ResponseType::Event(JaHandleEvent::GenericEvent(event)) => {
-                Ok(PluginEvent::GenericEvent(event))
+                Ok(PluginEvent::GenericEvent(event.clone()))
             }
             _ => Err(Self::Error::IncompletePacket),
         }
     }
 }
 
+impl TryFrom<JaResponse> for PluginEvent {
+    type Error = jarust_interface::Error;
+
+    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
+        Self::try_from(&value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::PluginEvent;
@@ -294,6 +307,11 @@ mod tests {
                 jsep_type: JsepType::Answer,
                 trickle: Some(false),
                 sdp: "test_sdp".to_string(),
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             }),
             transaction: None,
             session_id: None,
@@ -310,6 +328,11 @@ mod tests {
                     jsep_type: JsepType::Answer,
                     trickle: Some(false),
                     sdp: "test_sdp".to_string(),
+                    e2ee: None,
+                    simulcast: None,
+                    svc: None,
+                    update: None,
+                    extra: Default::default(),
                 },
             })
         );
@@ -570,6 +593,11 @@ mod tests {
                 jsep_type: JsepType::Answer,
                 trickle: Some(false),
                 sdp: "test_sdp".to_string(),
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             }),
             transaction: Some("test_transaction".to_string()),
             session_id: None,
@@ -585,6 +613,11 @@ mod tests {
                     jsep_type: JsepType::Answer,
                     trickle: Some(false),
                     sdp: "test_sdp".to_string(),
+                    e2ee: None,
+                    simulcast: None,
+                    svc: None,
+                    update: None,
+                    extra: Default::default(),
                 }
             })
         );
@@ -627,6 +660,11 @@ mod tests {
                 jsep_type: JsepType::Answer,
                 trickle: Some(false),
                 sdp: "test_sdp".to_string(),
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             }),
             transaction: None,
             session_id: None,
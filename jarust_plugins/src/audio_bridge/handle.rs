@@ -1,9 +1,12 @@
+use super::events::AudioBridgeEvent;
+use super::events::PluginEvent;
 use super::params::*;
 use super::responses::*;
 use crate::JanusId;
+use jarust_core::jasubscribers::JaSubscribers;
 use jarust_core::prelude::*;
 use jarust_interface::japrotocol::Jsep;
-use jarust_rt::JaTask;
+use jarust_rt::GroupTask;
 use serde_json::json;
 use serde_json::Value;
 use std::ops::Deref;
@@ -11,7 +14,8 @@ use std::time::Duration;
 
 pub struct AudioBridgeHandle {
     handle: JaHandle,
-    task: Option<JaTask>,
+    task: Option<GroupTask>,
+    subscribers: JaSubscribers<PluginEvent>,
 }
 
 // sync
@@ -230,6 +234,35 @@ impl AudioBridgeHandle {
         }
     }
 
+    /// Join an audio room and wait for the outcome, i.e. [`AudioBridgeEvent::RoomJoined`]
+    /// (or [`AudioBridgeEvent::RoomJoinedWithJsep`] when janus answers with a jsep)
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip_all)]
+    pub async fn join_room_and_wait(
+        &self,
+        params: AudioBridgeJoinParams,
+        jsep: Option<Jsep>,
+        timeout: Duration,
+    ) -> Result<AudioBridgeEvent, jarust_interface::Error> {
+        tracing::info!(
+            plugin = "audiobridge",
+            "Sending join room and waiting for its outcome"
+        );
+        let mut message: Value = params.try_into()?;
+        message["request"] = "join".into();
+        let response = match jsep {
+            Some(protocol) => {
+                self.handle
+                    .send_waiton_event_with_jsep(message, protocol, timeout)
+                    .await?
+            }
+            None => self.handle.send_waiton_event(message, timeout).await?,
+        };
+        match PluginEvent::try_from(response)? {
+            PluginEvent::AudioBridgeEvent(event) => Ok(event),
+            PluginEvent::GenericEvent(_) => Err(jarust_interface::Error::UnexpectedResponse),
+        }
+    }
+
     /// Configure the media related settings of the participant
     #[tracing::instrument(level = tracing::Level::DEBUG, skip_all)]
     pub async fn configure(
@@ -324,7 +357,7 @@ impl AudioBridgeHandle {
 }
 
 impl PluginTask for AudioBridgeHandle {
-    fn assign_task(&mut self, task: JaTask) {
+    fn assign_task(&mut self, task: GroupTask) {
         self.task = Some(task);
     }
 
@@ -335,9 +368,19 @@ impl PluginTask for AudioBridgeHandle {
     }
 }
 
+impl PluginSubscribe<PluginEvent> for AudioBridgeHandle {
+    fn subscribers(&self) -> &JaSubscribers<PluginEvent> {
+        &self.subscribers
+    }
+}
+
 impl From<JaHandle> for AudioBridgeHandle {
     fn from(handle: JaHandle) -> Self {
-        Self { handle, task: None }
+        Self {
+            handle,
+            task: None,
+            subscribers: JaSubscribers::default(),
+        }
     }
 }
 
@@ -5,7 +5,6 @@ use jarust_interface::japrotocol::Jsep;
 use jarust_interface::japrotocol::PluginInnerData;
 use jarust_interface::japrotocol::ResponseType;
 use serde::Deserialize;
-use serde_json::from_value;
 use serde_json::Value;
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Deserialize)]
@@ -38,40 +37,55 @@ pub enum EchoTestEvent {
     Other(Value),
 }
 
-impl TryFrom<JaResponse> for PluginEvent {
+impl From<GenericEvent> for PluginEvent {
+    fn from(value: GenericEvent) -> Self {
+        Self::GenericEvent(value)
+    }
+}
+
+impl TryFrom<&JaResponse> for PluginEvent {
     type Error = jarust_interface::Error;
 
-    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
-        match value.janus {
+    fn try_from(value: &JaResponse) -> Result<Self, Self::Error> {
+        match &value.janus {
             ResponseType::Event(JaHandleEvent::PluginEvent { plugin_data }) => {
-                let echotest_event = match plugin_data.data {
-                    PluginInnerData::Error { error_code, error } => {
-                        EchoTestEvent::Error { error_code, error }
-                    }
-                    PluginInnerData::Data(data) => {
-                        match from_value::<EchoTestEventDto>(data.clone()) {
-                            Ok(EchoTestEventDto::Result { echotest, result }) => match value.jsep {
+                let echotest_event = match &plugin_data.data {
+                    PluginInnerData::Error { error_code, error } => EchoTestEvent::Error {
+                        error_code: *error_code,
+                        error: error.clone(),
+                    },
+                    PluginInnerData::Data(data) => match EchoTestEventDto::deserialize(data) {
+                        Ok(EchoTestEventDto::Result { echotest, result }) => {
+                            match value.jsep.clone() {
                                 Some(jsep) => EchoTestEvent::ResultWithJsep {
                                     echotest,
                                     result,
                                     jsep,
                                 },
                                 None => EchoTestEvent::Result { echotest, result },
-                            },
-                            Err(_) => EchoTestEvent::Other(data),
+                            }
                         }
-                    }
+                        Err(_) => EchoTestEvent::Other(data.clone()),
+                    },
                 };
                 Ok(PluginEvent::EchoTestEvent(echotest_event))
             }
             ResponseType::Event(JaHandleEvent::GenericEvent(event)) => {
-                Ok(PluginEvent::GenericEvent(event))
+                Ok(PluginEvent::GenericEvent(event.clone()))
             }
             _ => Err(Self::Error::IncompletePacket),
         }
     }
 }
 
+impl TryFrom<JaResponse> for PluginEvent {
+    type Error = jarust_interface::Error;
+
+    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
+        Self::try_from(&value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::PluginEvent;
@@ -128,6 +142,11 @@ mod tests {
                 jsep_type: JsepType::Answer,
                 trickle: Some(false),
                 sdp: "test_sdp".to_string(),
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             }),
             transaction: None,
             session_id: None,
@@ -142,7 +161,12 @@ mod tests {
                 jsep: Jsep {
                     jsep_type: JsepType::Answer,
                     trickle: Some(false),
-                    sdp: "test_sdp".to_string()
+                    sdp: "test_sdp".to_string(),
+                    e2ee: None,
+                    simulcast: None,
+                    svc: None,
+                    update: None,
+                    extra: Default::default(),
                 }
             })
         );
@@ -164,6 +188,11 @@ mod tests {
                 jsep_type: JsepType::Answer,
                 trickle: Some(false),
                 sdp: "test_sdp".to_string(),
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             }),
             transaction: None,
             session_id: None,
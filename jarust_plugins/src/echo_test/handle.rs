@@ -1,13 +1,17 @@
+use super::events::EchoTestEvent;
+use super::events::PluginEvent;
 use super::params::EchoTestStartParams;
+use jarust_core::jasubscribers::JaSubscribers;
 use jarust_core::prelude::*;
 use jarust_interface::japrotocol::Jsep;
-use jarust_rt::JaTask;
+use jarust_rt::GroupTask;
 use std::ops::Deref;
 use std::time::Duration;
 
 pub struct EchoTestHandle {
     handle: JaHandle,
-    task: Option<JaTask>,
+    task: Option<GroupTask>,
+    subscribers: JaSubscribers<PluginEvent>,
 }
 
 impl EchoTestHandle {
@@ -33,10 +37,49 @@ impl EchoTestHandle {
         self.send_waiton_ack_with_jsep(params.try_into()?, jsep, timeout)
             .await
     }
+
+    /// Start/update an echotest session and wait for its result
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip_all)]
+    pub async fn start_and_wait(
+        &self,
+        params: EchoTestStartParams,
+        timeout: Duration,
+    ) -> Result<EchoTestEvent, jarust_interface::Error> {
+        tracing::info!(
+            plugin = "echotest",
+            "Sending start and waiting for its result"
+        );
+        let response = self.send_waiton_event(params.try_into()?, timeout).await?;
+        match PluginEvent::try_from(response)? {
+            PluginEvent::EchoTestEvent(event) => Ok(event),
+            PluginEvent::GenericEvent(_) => Err(jarust_interface::Error::UnexpectedResponse),
+        }
+    }
+
+    /// Start/update an echotest session with jsep and wait for its result, carrying the answer
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip_all)]
+    pub async fn start_with_jsep_and_wait(
+        &self,
+        params: EchoTestStartParams,
+        jsep: Jsep,
+        timeout: Duration,
+    ) -> Result<EchoTestEvent, jarust_interface::Error> {
+        tracing::info!(
+            plugin = "echotest",
+            "Sending start with jsep and waiting for its result"
+        );
+        let response = self
+            .send_waiton_event_with_jsep(params.try_into()?, jsep, timeout)
+            .await?;
+        match PluginEvent::try_from(response)? {
+            PluginEvent::EchoTestEvent(event) => Ok(event),
+            PluginEvent::GenericEvent(_) => Err(jarust_interface::Error::UnexpectedResponse),
+        }
+    }
 }
 
 impl PluginTask for EchoTestHandle {
-    fn assign_task(&mut self, task: JaTask) {
+    fn assign_task(&mut self, task: GroupTask) {
         self.task = Some(task);
     }
 
@@ -47,9 +90,19 @@ impl PluginTask for EchoTestHandle {
     }
 }
 
+impl PluginSubscribe<PluginEvent> for EchoTestHandle {
+    fn subscribers(&self) -> &JaSubscribers<PluginEvent> {
+        &self.subscribers
+    }
+}
+
 impl From<JaHandle> for EchoTestHandle {
     fn from(handle: JaHandle) -> Self {
-        Self { handle, task: None }
+        Self {
+            handle,
+            task: None,
+            subscribers: JaSubscribers::default(),
+        }
     }
 }
 
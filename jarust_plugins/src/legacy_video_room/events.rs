@@ -119,18 +119,25 @@ pub enum PluginEvent {
     LegacyVideoRoomEvent(LegacyVideoRoomEvent),
 }
 
-impl TryFrom<JaResponse> for PluginEvent {
+impl From<GenericEvent> for PluginEvent {
+    fn from(value: GenericEvent) -> Self {
+        Self::GenericEvent(value)
+    }
+}
+
+impl TryFrom<&JaResponse> for PluginEvent {
     type Error = jarust_interface::Error;
 
-    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
-        match value.janus {
+    fn try_from(value: &JaResponse) -> Result<Self, Self::Error> {
+        match &value.janus {
             ResponseType::Event(JaHandleEvent::PluginEvent { plugin_data }) => {
-                Ok(PluginEvent::LegacyVideoRoomEvent(match plugin_data.data {
-                    PluginInnerData::Error { error_code, error } => {
-                        LegacyVideoRoomEvent::Error { error_code, error }
-                    }
+                Ok(PluginEvent::LegacyVideoRoomEvent(match &plugin_data.data {
+                    PluginInnerData::Error { error_code, error } => LegacyVideoRoomEvent::Error {
+                        error_code: *error_code,
+                        error: error.clone(),
+                    },
                     PluginInnerData::Data(data) => {
-                        match serde_json::from_value::<LegacyVideoRoomEventDto>(data.clone()) {
+                        match LegacyVideoRoomEventDto::deserialize(data) {
                             Ok(event) => match event {
                                 LegacyVideoRoomEventDto::Joined {
                                     id,
@@ -144,14 +151,14 @@ impl TryFrom<JaResponse> for PluginEvent {
                                     private_id,
                                     description,
                                     publishers,
-                                    jsep: value.jsep,
+                                    jsep: value.jsep.clone(),
                                 },
                                 LegacyVideoRoomEventDto::SubscriberAttached {
                                     id,
                                     room,
                                     display,
                                 } => {
-                                    if let Some(jsep) = value.jsep {
+                                    if let Some(jsep) = value.jsep.clone() {
                                         LegacyVideoRoomEvent::SubscriberAttached {
                                             id,
                                             room,
@@ -159,7 +166,7 @@ impl TryFrom<JaResponse> for PluginEvent {
                                             jsep,
                                         }
                                     } else {
-                                        LegacyVideoRoomEvent::Other(data)
+                                        LegacyVideoRoomEvent::Other(data.clone())
                                     }
                                 }
                                 LegacyVideoRoomEventDto::SlowLink => LegacyVideoRoomEvent::SlowLink,
@@ -167,7 +174,7 @@ impl TryFrom<JaResponse> for PluginEvent {
                                     InnerLegacyVideoRoomEvent::Configured { room, .. } => {
                                         LegacyVideoRoomEvent::Configured {
                                             room,
-                                            jsep: value.jsep,
+                                            jsep: value.jsep.clone(),
                                         }
                                     }
                                     InnerLegacyVideoRoomEvent::NewPublishers {
@@ -192,19 +199,27 @@ impl TryFrom<JaResponse> for PluginEvent {
                                     }
                                 },
                             },
-                            Err(_) => LegacyVideoRoomEvent::Other(data),
+                            Err(_) => LegacyVideoRoomEvent::Other(data.clone()),
                         }
                     }
                 }))
             }
             ResponseType::Event(JaHandleEvent::GenericEvent(event)) => {
-                Ok(PluginEvent::GenericEvent(event))
+                Ok(PluginEvent::GenericEvent(event.clone()))
             }
             _ => Err(Self::Error::IncompletePacket),
         }
     }
 }
 
+impl TryFrom<JaResponse> for PluginEvent {
+    type Error = jarust_interface::Error;
+
+    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
+        Self::try_from(&value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -247,7 +262,12 @@ mod tests {
                 jsep: Some(Jsep {
                     jsep_type: JsepType::Answer,
                     sdp: "test_sdp".to_string(),
-                    trickle: None
+                    trickle: None,
+                    e2ee: None,
+                    simulcast: None,
+                    svc: None,
+                    update: None,
+                    extra: Default::default(),
                 })
             })
         )
@@ -360,7 +380,12 @@ mod tests {
                 jsep: Jsep {
                     jsep_type: JsepType::Offer,
                     sdp: "test_subscriber_sdp".to_string(),
-                    trickle: None
+                    trickle: None,
+                    e2ee: None,
+                    simulcast: None,
+                    svc: None,
+                    update: None,
+                    extra: Default::default(),
                 }
             })
         );
@@ -419,7 +444,12 @@ mod tests {
                 jsep: Some(Jsep {
                     jsep_type: JsepType::Answer,
                     sdp: "test_configured_sdp".to_string(),
-                    trickle: None
+                    trickle: None,
+                    e2ee: None,
+                    simulcast: None,
+                    svc: None,
+                    update: None,
+                    extra: Default::default(),
                 })
             })
         );
@@ -1,3 +1,5 @@
+use super::events::LegacyVideoRoomEvent;
+use super::events::PluginEvent;
 use super::params::LegacyVideoRoomCreateParams;
 use super::params::LegacyVideoRoomExistsParams;
 use super::params::LegacyVideoRoomKickParams;
@@ -8,9 +10,10 @@ use super::params::LegacyVideoRoomSubscriberConfigureParams;
 use super::params::LegacyVideoRoomSubscriberJoinParams;
 use super::responses::LegacyVideoRoomCreatedRsp;
 use crate::legacy_video_room::responses::LegacyVideoRoomExistsRsp;
+use jarust_core::jasubscribers::JaSubscribers;
 use jarust_core::prelude::*;
 use jarust_interface::japrotocol::Jsep;
-use jarust_rt::JaTask;
+use jarust_rt::GroupTask;
 use serde_json::json;
 use serde_json::Value;
 use std::ops::Deref;
@@ -18,7 +21,8 @@ use std::time::Duration;
 
 pub struct LegacyVideoRoomHandle {
     handle: JaHandle,
-    task: Option<JaTask>,
+    task: Option<GroupTask>,
+    subscribers: JaSubscribers<PluginEvent>,
 }
 
 // sync
@@ -97,6 +101,31 @@ impl LegacyVideoRoomHandle {
         }
     }
 
+    /// Same as [`publisher_join`](Self::publisher_join), resolving to the plugin event answering it
+    pub async fn publisher_join_and_wait(
+        &self,
+        params: LegacyVideoRoomPublisherJoinParams,
+        jsep: Option<Jsep>,
+        timeout: Duration,
+    ) -> Result<LegacyVideoRoomEvent, jarust_interface::Error> {
+        let mut message: Value = params.try_into()?;
+        message["request"] = "join".into();
+        message["ptype"] = "publisher".into();
+
+        let response = match jsep {
+            None => self.handle.send_waiton_event(message, timeout).await?,
+            Some(jsep) => {
+                self.handle
+                    .send_waiton_event_with_jsep(message, jsep, timeout)
+                    .await?
+            }
+        };
+        match PluginEvent::try_from(response)? {
+            PluginEvent::LegacyVideoRoomEvent(event) => Ok(event),
+            PluginEvent::GenericEvent(_) => Err(jarust_interface::Error::UnexpectedResponse),
+        }
+    }
+
     pub async fn publisher_configure(
         &self,
         params: LegacyVideoRoomPublisherConfigureParams,
@@ -163,7 +192,7 @@ impl LegacyVideoRoomHandle {
 }
 
 impl PluginTask for LegacyVideoRoomHandle {
-    fn assign_task(&mut self, task: JaTask) {
+    fn assign_task(&mut self, task: GroupTask) {
         self.task = Some(task);
     }
 
@@ -174,9 +203,19 @@ impl PluginTask for LegacyVideoRoomHandle {
     }
 }
 
+impl PluginSubscribe<PluginEvent> for LegacyVideoRoomHandle {
+    fn subscribers(&self) -> &JaSubscribers<PluginEvent> {
+        &self.subscribers
+    }
+}
+
 impl From<JaHandle> for LegacyVideoRoomHandle {
     fn from(handle: JaHandle) -> Self {
-        Self { handle, task: None }
+        Self {
+            handle,
+            task: None,
+            subscribers: JaSubscribers::default(),
+        }
     }
 }
 
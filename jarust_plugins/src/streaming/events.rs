@@ -5,7 +5,6 @@ use jarust_interface::japrotocol::JaHandleEvent;
 use jarust_interface::japrotocol::PluginInnerData;
 use jarust_interface::japrotocol::ResponseType;
 use serde::Deserialize;
-use serde_json::from_value;
 use serde_json::Value;
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Deserialize)]
@@ -45,42 +44,55 @@ pub enum StreamingEvent {
     Other(Value),
 }
 
-impl TryFrom<JaResponse> for PluginEvent {
+impl From<GenericEvent> for PluginEvent {
+    fn from(value: GenericEvent) -> Self {
+        Self::GenericEvent(value)
+    }
+}
+
+impl TryFrom<&JaResponse> for PluginEvent {
     type Error = jarust_interface::Error;
 
-    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
-        match value.janus {
+    fn try_from(value: &JaResponse) -> Result<Self, Self::Error> {
+        match &value.janus {
             ResponseType::Event(JaHandleEvent::PluginEvent { plugin_data }) => {
-                let streaming_event = match plugin_data.data {
-                    PluginInnerData::Error { error_code, error } => {
-                        StreamingEvent::Error { error_code, error }
-                    }
-                    PluginInnerData::Data(data) => {
-                        match from_value::<StreamingEventDto>(data.clone()) {
-                            Ok(StreamingEventDto::CreateMountpoint {
-                                id,
-                                mountpoint_type,
-                            }) => StreamingEvent::MountpointCreated {
-                                id,
-                                mountpoint_type,
-                            },
-                            Ok(StreamingEventDto::DestroyMountpoint { id }) => {
-                                StreamingEvent::MountpointDestroyed { id }
-                            }
-                            Err(_) => StreamingEvent::Other(data),
+                let streaming_event = match &plugin_data.data {
+                    PluginInnerData::Error { error_code, error } => StreamingEvent::Error {
+                        error_code: *error_code,
+                        error: error.clone(),
+                    },
+                    PluginInnerData::Data(data) => match StreamingEventDto::deserialize(data) {
+                        Ok(StreamingEventDto::CreateMountpoint {
+                            id,
+                            mountpoint_type,
+                        }) => StreamingEvent::MountpointCreated {
+                            id,
+                            mountpoint_type,
+                        },
+                        Ok(StreamingEventDto::DestroyMountpoint { id }) => {
+                            StreamingEvent::MountpointDestroyed { id }
                         }
-                    }
+                        Err(_) => StreamingEvent::Other(data.clone()),
+                    },
                 };
                 Ok(PluginEvent::StreamingEvent(streaming_event))
             }
             ResponseType::Event(JaHandleEvent::GenericEvent(event)) => {
-                Ok(PluginEvent::GenericEvent(event))
+                Ok(PluginEvent::GenericEvent(event.clone()))
             }
             _ => Err(Self::Error::IncompletePacket),
         }
     }
 }
 
+impl TryFrom<JaResponse> for PluginEvent {
+    type Error = jarust_interface::Error;
+
+    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
+        Self::try_from(&value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::PluginEvent;
@@ -1,8 +1,10 @@
+use crate::streaming::events::PluginEvent;
 use crate::streaming::params::*;
 use crate::streaming::responses::*;
 use crate::JanusId;
+use jarust_core::jasubscribers::JaSubscribers;
 use jarust_core::prelude::*;
-use jarust_rt::JaTask;
+use jarust_rt::GroupTask;
 use serde_json::json;
 use serde_json::Value;
 use std::ops::Deref;
@@ -10,7 +12,8 @@ use std::time::Duration;
 
 pub struct StreamingHandle {
     handle: JaHandle,
-    task: Option<JaTask>,
+    task: Option<GroupTask>,
+    subscribers: JaSubscribers<PluginEvent>,
 }
 
 //
@@ -98,7 +101,7 @@ impl StreamingHandle {
 // TODO: async methods
 
 impl PluginTask for StreamingHandle {
-    fn assign_task(&mut self, task: JaTask) {
+    fn assign_task(&mut self, task: GroupTask) {
         self.task = Some(task);
     }
 
@@ -109,9 +112,19 @@ impl PluginTask for StreamingHandle {
     }
 }
 
+impl PluginSubscribe<PluginEvent> for StreamingHandle {
+    fn subscribers(&self) -> &JaSubscribers<PluginEvent> {
+        &self.subscribers
+    }
+}
+
 impl From<JaHandle> for StreamingHandle {
     fn from(handle: JaHandle) -> Self {
-        Self { handle, task: None }
+        Self {
+            handle,
+            task: None,
+            subscribers: JaSubscribers::default(),
+        }
     }
 }
 
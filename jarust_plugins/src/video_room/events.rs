@@ -10,7 +10,6 @@ use jarust_interface::japrotocol::Jsep;
 use jarust_interface::japrotocol::PluginInnerData;
 use jarust_interface::japrotocol::ResponseType;
 use serde::Deserialize;
-use serde_json::from_value;
 use serde_json::Value;
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Deserialize)]
@@ -245,21 +244,28 @@ pub enum VideoRoomEvent {
     Other(Value),
 }
 
-impl TryFrom<JaResponse> for PluginEvent {
+impl From<GenericEvent> for PluginEvent {
+    fn from(value: GenericEvent) -> Self {
+        Self::GenericEvent(value)
+    }
+}
+
+impl TryFrom<&JaResponse> for PluginEvent {
     type Error = jarust_interface::Error;
 
-    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
+    fn try_from(value: &JaResponse) -> Result<Self, Self::Error> {
         use VideoRoomEventDto as EventDto;
         use VideoRoomEventEventType as Event;
 
-        match value.janus {
+        match &value.janus {
             ResponseType::Event(JaHandleEvent::PluginEvent { plugin_data }) => {
-                let videoroom_event = match plugin_data.data {
-                    PluginInnerData::Error { error_code, error } => {
-                        VideoRoomEvent::Error { error_code, error }
-                    }
+                let videoroom_event = match &plugin_data.data {
+                    PluginInnerData::Error { error_code, error } => VideoRoomEvent::Error {
+                        error_code: *error_code,
+                        error: error.clone(),
+                    },
                     PluginInnerData::Data(data) => {
-                        let val = from_value::<EventDto>(data.clone());
+                        let val = EventDto::deserialize(data);
                         match val {
                             Ok(event) => match event {
                                 EventDto::DestroyRoom { room } => {
@@ -276,7 +282,7 @@ impl TryFrom<JaResponse> for PluginEvent {
                                     publishers,
                                     ..
                                 } => {
-                                    if let Some(jsep) = value.jsep {
+                                    if let Some(jsep) = value.jsep.clone() {
                                         VideoRoomEvent::RoomJoinedWithJsep {
                                             room,
                                             description,
@@ -296,7 +302,7 @@ impl TryFrom<JaResponse> for PluginEvent {
                                     }
                                 }
                                 EventDto::SubscriberAttached { room, streams } => {
-                                    if let Some(jsep) = value.jsep {
+                                    if let Some(jsep) = value.jsep.clone() {
                                         VideoRoomEvent::SubscriberAttachedWithJsep {
                                             room,
                                             streams,
@@ -346,7 +352,7 @@ impl TryFrom<JaResponse> for PluginEvent {
                                     streams,
                                     ..
                                 }) => {
-                                    if let Some(jsep) = value.jsep {
+                                    if let Some(jsep) = value.jsep.clone() {
                                         VideoRoomEvent::ConfiguredWithJsep {
                                             room,
                                             audio_codec,
@@ -392,20 +398,28 @@ impl TryFrom<JaResponse> for PluginEvent {
                                     VideoRoomEvent::LeftAsyncRsp { room }
                                 }
                             },
-                            Err(_) => VideoRoomEvent::Other(data),
+                            Err(_) => VideoRoomEvent::Other(data.clone()),
                         }
                     }
                 };
                 Ok(PluginEvent::VideoRoomEvent(videoroom_event))
             }
             ResponseType::Event(JaHandleEvent::GenericEvent(event)) => {
-                Ok(PluginEvent::GenericEvent(event))
+                Ok(PluginEvent::GenericEvent(event.clone()))
             }
             _ => Err(Self::Error::IncompletePacket),
         }
     }
 }
 
+impl TryFrom<JaResponse> for PluginEvent {
+    type Error = jarust_interface::Error;
+
+    fn try_from(value: JaResponse) -> Result<Self, Self::Error> {
+        Self::try_from(&value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::PluginEvent;
@@ -547,7 +561,12 @@ mod tests {
                 jsep: Jsep {
                     jsep_type: JsepType::Answer,
                     sdp: "test_sdp".to_string(),
-                    trickle: None
+                    trickle: None,
+                    e2ee: None,
+                    simulcast: None,
+                    svc: None,
+                    update: None,
+                    extra: Default::default(),
                 }
             })
         )
@@ -674,6 +693,11 @@ mod tests {
                 jsep_type: JsepType::Answer,
                 trickle: Some(false),
                 sdp: "test_sdp".to_string(),
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             }),
         };
         let event: PluginEvent = rsp.try_into().unwrap();
@@ -706,6 +730,11 @@ mod tests {
                     jsep_type: JsepType::Answer,
                     trickle: Some(false),
                     sdp: "test_sdp".to_string(),
+                    e2ee: None,
+                    simulcast: None,
+                    svc: None,
+                    update: None,
+                    extra: Default::default(),
                 }
             })
         )
@@ -804,6 +833,11 @@ mod tests {
                 jsep_type: JsepType::Answer,
                 trickle: Some(false),
                 sdp: "test_sdp".to_string(),
+                e2ee: None,
+                simulcast: None,
+                svc: None,
+                update: None,
+                extra: Default::default(),
             }),
             sender: None,
             session_id: None,
@@ -894,6 +928,11 @@ mod tests {
                     jsep_type: JsepType::Answer,
                     trickle: Some(false),
                     sdp: "test_sdp".to_string(),
+                    e2ee: None,
+                    simulcast: None,
+                    svc: None,
+                    update: None,
+                    extra: Default::default(),
                 }
             })
         );
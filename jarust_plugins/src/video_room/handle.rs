@@ -1,9 +1,12 @@
+use crate::video_room::events::PluginEvent;
+use crate::video_room::events::VideoRoomEvent;
 use crate::video_room::params::*;
 use crate::video_room::responses::*;
 use crate::JanusId;
+use jarust_core::jasubscribers::JaSubscribers;
 use jarust_core::prelude::*;
 use jarust_interface::japrotocol::Jsep;
-use jarust_rt::JaTask;
+use jarust_rt::GroupTask;
 use serde_json::json;
 use serde_json::Value;
 use std::ops::Deref;
@@ -11,7 +14,8 @@ use std::time::Duration;
 
 pub struct VideoRoomHandle {
     handle: JaHandle,
-    task: Option<JaTask>,
+    task: Option<GroupTask>,
+    subscribers: JaSubscribers<PluginEvent>,
 }
 
 //
@@ -289,6 +293,34 @@ impl VideoRoomHandle {
         Ok(())
     }
 
+    /// Join a room as a publisher and wait for the outcome
+    ///
+    /// Same as [`VideoRoomHandle::join_as_publisher`], resolving to the [`VideoRoomEvent::RoomJoined`] event
+    /// or to the plugin error.
+    pub async fn join_as_publisher_and_wait(
+        &self,
+        params: VideoRoomPublisherJoinParams,
+        jsep: Option<Jsep>,
+        timeout: Duration,
+    ) -> Result<VideoRoomEvent, jarust_interface::Error> {
+        let mut message: Value = params.try_into()?;
+        message["request"] = "join".into();
+        message["ptype"] = "publisher".into();
+
+        let response = match jsep {
+            None => self.handle.send_waiton_event(message, timeout).await?,
+            Some(jsep) => {
+                self.handle
+                    .send_waiton_event_with_jsep(message, jsep, timeout)
+                    .await?
+            }
+        };
+        match PluginEvent::try_from(response)? {
+            PluginEvent::VideoRoomEvent(event) => Ok(event),
+            PluginEvent::GenericEvent(_) => Err(jarust_interface::Error::UnexpectedResponse),
+        }
+    }
+
     /// Join a room as a subscriber
     ///
     /// In a VideoRoom, subscribers are NOT participants, but simply handles that will be used exclusively to
@@ -486,7 +518,7 @@ impl VideoRoomHandle {
 }
 
 impl PluginTask for VideoRoomHandle {
-    fn assign_task(&mut self, task: JaTask) {
+    fn assign_task(&mut self, task: GroupTask) {
         self.task = Some(task);
     }
 
@@ -497,9 +529,19 @@ impl PluginTask for VideoRoomHandle {
     }
 }
 
+impl PluginSubscribe<PluginEvent> for VideoRoomHandle {
+    fn subscribers(&self) -> &JaSubscribers<PluginEvent> {
+        &self.subscribers
+    }
+}
+
 impl From<JaHandle> for VideoRoomHandle {
     fn from(handle: JaHandle) -> Self {
-        Self { handle, task: None }
+        Self {
+            handle,
+            task: None,
+            subscribers: JaSubscribers::default(),
+        }
     }
 }
 
@@ -1,32 +1,47 @@
 use super::events::PluginEvent;
 use super::handle::VideoRoomHandle;
 use jarust_core::prelude::*;
+use jarust_interface::japrotocol::GenericEvent;
+use jarust_rt::sync::mpsc;
 use std::ops::Deref;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
 
 #[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
 #[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
 pub trait VideoRoom: Attach {
-    type Event: TryFrom<JaResponse, Error = jarust_interface::Error> + Send + Sync + 'static;
-    type Handle: From<JaHandle> + Deref<Target = JaHandle> + PluginTask;
+    type Event: for<'a> TryFrom<&'a JaResponse, Error = jarust_interface::Error>
+        + From<GenericEvent>
+        + Clone
+        + Send
+        + Sync
+        + 'static;
+    type Handle: From<JaHandle>
+        + Deref<Target = JaHandle>
+        + PluginTask
+        + PluginSubscribe<Self::Event>;
 
     async fn attach_video_room(
         &self,
         timeout: Duration,
-    ) -> Result<(Self::Handle, mpsc::UnboundedReceiver<Self::Event>), jarust_interface::Error> {
+    ) -> Result<(Self::Handle, mpsc::UnboundedReceiver<Arc<Self::Event>>), jarust_interface::Error>
+    {
         let (handle, mut receiver) = self
             .attach("janus.plugin.videoroom".to_string(), timeout)
             .await?;
-        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let task = jarust_rt::spawn("videoroom listener", async move {
-            while let Some(rsp) = receiver.recv().await {
-                if let Ok(event) = rsp.try_into() {
-                    let _ = tx.send(event);
-                };
-            }
-        });
+        let (tx, rx) = mpsc::unbounded_channel();
+        let parsing = handle.parsing().clone();
         let mut handle: Self::Handle = handle.into();
+        let subscribers = handle.subscribers().clone();
+        let task = handle
+            .tasks()
+            .spawn_owned("videoroom listener", async move {
+                while let Some(rsp) = receiver.recv().await {
+                    if let Some(event) = parsing.convert(rsp) {
+                        let _ = tx.send(subscribers.publish(event));
+                    };
+                }
+            });
         handle.assign_task(task);
         Ok((handle, rx))
     }
@@ -2,23 +2,57 @@
 //!
 //! A runtime abstraction crate for jarust.
 //!
+//! - `tokio-rt`: tasks and timers on tokio (default).
+//! - `smol-rt`: tasks on smol's global executor and `async-io` timers.
+//!
+//! Note that the built-in WebSocket and restful transports of `jarust_interface` are built on
+//! tokio IO types, under smol they need a tokio reactor (e.g. through `async-compat`) unless a
+//! custom `MessageTransport` is used.
+//!
+//! The [`sync`] primitives don't depend on any runtime.
+//!
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(not(any(feature = "tokio-rt", feature = "smol-rt")))]
+compile_error!("Either feature \"tokio-rt\" or \"smol-rt\" must be enabled for this crate.");
 
 #[cfg(not(target_family = "wasm"))]
-#[cfg(not(any(feature = "tokio-rt")))]
-compile_error!("Feature \"tokio-rt\" must be enabled for this crate.");
+#[cfg(all(feature = "tokio-rt", feature = "smol-rt"))]
+compile_error!("Feature \"tokio-rt\" and feature \"smol-rt\" cannot be enabled at the same time");
 
 #[cfg(not(target_family = "wasm"))]
 #[cfg(feature = "tokio-rt")]
 #[path = "tokio_rt.rs"]
 pub mod jatask;
 
+#[cfg(not(target_family = "wasm"))]
+#[cfg(feature = "smol-rt")]
+#[path = "smol_rt.rs"]
+pub mod jatask;
+
 #[cfg(target_family = "wasm")]
 #[path = "wasm_rt.rs"]
 pub mod jatask;
 
+pub mod sync;
+pub mod task_group;
+
 use futures_util::Future;
 pub use jatask::JaTask;
 use std::time::Duration;
+pub use task_group::GroupTask;
+pub use task_group::JaTaskGroup;
+
+/// `Send` on native, where tasks may move across threads, and nothing on WASM.
+#[cfg(not(target_family = "wasm"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_family = "wasm"))]
+impl<T: Send> MaybeSend for T {}
+
+#[cfg(target_family = "wasm")]
+pub trait MaybeSend {}
+#[cfg(target_family = "wasm")]
+impl<T> MaybeSend for T {}
 
 /// Spawns a new task. The name field is for debugging purposes only.
 #[cfg(not(target_family = "wasm"))]
@@ -43,28 +77,18 @@ where
     jatask::spawn(name, future)
 }
 
-/// Sleeps for the given duration. Uses `tokio::time::sleep` on native and
-/// `gloo_timers` on WASM (which does not support `std::time::Instant`).
+/// Sleeps for the given duration. Uses `tokio::time::sleep` on tokio, `async-io` timers
+/// on smol and `gloo_timers` on WASM (which does not support `std::time::Instant`).
 pub async fn sleep(duration: Duration) {
     jatask::sleep(duration).await;
 }
 
-/// Runs `future` with a deadline. Uses `tokio::time::timeout` on native and
-/// a `gloo_timers`-based race on WASM (which does not support `std::time::Instant`).
-#[cfg(target_family = "wasm")]
+/// Runs `future` with a deadline. Uses `tokio::time::timeout` on tokio, an `async-io`
+/// timer race on smol and a `gloo_timers`-based race on WASM (which does not support
+/// `std::time::Instant`).
 pub async fn timeout<F, T>(duration: Duration, future: F) -> Result<T, ()>
 where
     F: Future<Output = T>,
 {
-    use futures_util::future::Either;
-    use futures_util::pin_mut;
-
-    let timer = gloo_timers::future::sleep(duration);
-    pin_mut!(future);
-    pin_mut!(timer);
-
-    match futures_util::future::select(future, timer).await {
-        Either::Left((output, _)) => Ok(output),
-        Either::Right((_, _)) => Err(()),
-    }
+    jatask::timeout(duration, future).await
 }
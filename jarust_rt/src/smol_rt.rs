@@ -0,0 +1,94 @@
+use futures_util::future::AbortHandle;
+use futures_util::future::Either;
+use futures_util::pin_mut;
+use futures_util::Future;
+use smol::Timer;
+use std::time::Duration;
+
+pub async fn sleep(duration: Duration) {
+    Timer::after(duration).await;
+}
+
+pub async fn timeout<F, T>(duration: Duration, future: F) -> Result<T, ()>
+where
+    F: Future<Output = T>,
+{
+    let timer = Timer::after(duration);
+    pin_mut!(future);
+    pin_mut!(timer);
+
+    match futures_util::future::select(future, timer).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right((_, _)) => Err(()),
+    }
+}
+
+pub fn spawn<F>(name: &str, future: F) -> JaTask
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    // Detached so the task outlives its `smol::Task`, cancellation goes through the abort handle
+    let (future, handle) = futures_util::future::abortable(future);
+    smol::spawn(future).detach();
+    JaTask {
+        inner: handle,
+        task_name: name.to_owned(),
+    }
+}
+
+#[derive(Debug)]
+pub struct JaTask {
+    inner: AbortHandle,
+    pub task_name: String,
+}
+
+impl JaTask {
+    pub fn cancel(&self) {
+        self.inner.abort();
+    }
+}
+
+impl Drop for JaTask {
+    #[tracing::instrument(level = tracing::Level::TRACE, skip_all)]
+    fn drop(&mut self) {
+        tracing::trace!(task_name = self.task_name, "Dropping task");
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sleep;
+    use super::spawn;
+    use super::timeout;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn it_should_not_run_cancelled_tasks() {
+        smol::block_on(async {
+            let ran = Arc::new(AtomicBool::new(false));
+            let task = spawn("cancelled", {
+                let ran = ran.clone();
+                async move {
+                    sleep(Duration::from_millis(50)).await;
+                    ran.store(true, Ordering::SeqCst);
+                }
+            });
+            task.cancel();
+            sleep(Duration::from_millis(100)).await;
+            assert!(!ran.load(Ordering::SeqCst));
+        });
+    }
+
+    #[test]
+    fn it_should_time_out() {
+        smol::block_on(async {
+            let result = timeout(Duration::from_millis(10), sleep(Duration::from_secs(1))).await;
+            assert!(result.is_err());
+        });
+    }
+}
@@ -0,0 +1,15 @@
+//! Runtime-agnostic synchronization primitives, usable from any executor.
+//!
+//! The API mirrors the subset of `tokio::sync` jarust relies on.
+
+pub mod mpsc;
+pub mod watch;
+
+pub use async_lock::Mutex;
+pub use async_lock::MutexGuard;
+pub use async_lock::RwLock;
+pub use async_lock::RwLockReadGuard;
+pub use async_lock::RwLockWriteGuard;
+pub use event_listener::Event;
+pub use event_listener::EventListener;
+pub use futures_channel::oneshot;
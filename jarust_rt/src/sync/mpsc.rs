@@ -0,0 +1,162 @@
+//! Unbounded multi-producer, single-consumer channel.
+
+use futures_util::Stream;
+use futures_util::StreamExt;
+use std::fmt;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+pub use futures_channel::mpsc::TryRecvError;
+
+/// Creates an unbounded channel, sending never waits.
+pub fn unbounded_channel<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    let (sender, receiver) = futures_channel::mpsc::unbounded();
+    (
+        UnboundedSender { inner: sender },
+        UnboundedReceiver { inner: receiver },
+    )
+}
+
+/// Error returned by [`UnboundedSender::send`] when the receiver is gone, holding the message.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+pub struct UnboundedSender<T> {
+    inner: futures_channel::mpsc::UnboundedSender<T>,
+}
+
+impl<T> UnboundedSender<T> {
+    /// Sends a message, failing only when the receiver is gone.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        self.inner
+            .unbounded_send(message)
+            .map_err(|err| SendError(err.into_inner()))
+    }
+
+    /// Returns whether the receiver is gone.
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+impl<T> Clone for UnboundedSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for UnboundedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnboundedSender")
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+pub struct UnboundedReceiver<T> {
+    inner: futures_channel::mpsc::UnboundedReceiver<T>,
+}
+
+impl<T> UnboundedReceiver<T> {
+    /// Receives the next message, `None` once every sender is gone and the channel is drained.
+    pub async fn recv(&mut self) -> Option<T> {
+        self.inner.next().await
+    }
+
+    /// Waits for at least one message, then receives up to `limit` already queued messages
+    /// into `buffer`. Returns the number of received messages, `0` once the channel is closed.
+    pub async fn recv_many(&mut self, buffer: &mut Vec<T>, limit: usize) -> usize {
+        if limit == 0 {
+            return 0;
+        }
+        let Some(first) = self.recv().await else {
+            return 0;
+        };
+        buffer.push(first);
+        let mut received = 1;
+        while received < limit {
+            match self.inner.try_recv() {
+                Ok(message) => {
+                    buffer.push(message);
+                    received += 1;
+                }
+                Err(_) => break,
+            }
+        }
+        received
+    }
+
+    /// Receives a queued message without waiting.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    /// Closes the channel, queued messages can still be received.
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+}
+
+impl<T> Stream for UnboundedReceiver<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl<T> fmt::Debug for UnboundedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnboundedReceiver").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unbounded_channel;
+
+    #[tokio::test]
+    async fn it_should_receive_queued_messages_up_to_the_limit() {
+        let (tx, mut rx) = unbounded_channel();
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        let mut buffer = vec![];
+        assert_eq!(rx.recv_many(&mut buffer, 3).await, 3);
+        assert_eq!(buffer, vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn it_should_end_when_every_sender_is_gone() {
+        let (tx, mut rx) = unbounded_channel();
+        tx.send(1).unwrap();
+        drop(tx);
+        assert_eq!(rx.recv().await, Some(1));
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test]
+    fn it_should_give_back_the_message_when_the_receiver_is_gone() {
+        let (tx, rx) = unbounded_channel();
+        drop(rx);
+        assert!(tx.is_closed());
+        assert_eq!(tx.send(1).unwrap_err().0, 1);
+    }
+}
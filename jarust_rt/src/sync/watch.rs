@@ -0,0 +1,280 @@
+//! Single-value channel, receivers observe the latest value and wait for it to change.
+//!
+//! Unlike `tokio::sync::watch` the sender is cloneable, the channel closes once every sender is
+//! dropped or explicitly with [`Sender::close`].
+
+use event_listener::Event;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+/// Creates a channel holding `init`.
+pub fn channel<T>(init: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            value: init,
+            version: 0,
+            closed: false,
+        }),
+        changed: Event::new(),
+        senders: AtomicUsize::new(1),
+    });
+    let receiver = Receiver {
+        shared: shared.clone(),
+        seen: 0,
+    };
+    (Sender { shared }, receiver)
+}
+
+/// Error returned when waiting on a closed channel.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+struct State<T> {
+    value: T,
+    version: u64,
+    closed: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    changed: Event,
+    senders: AtomicUsize,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        // The value is replaced or modified in place by the user, a panic can't be observed
+        // midway through the channel bookkeeping
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.changed.notify(usize::MAX);
+    }
+}
+
+/// A borrow of the value held by the channel, which can't change while it's held, so it
+/// shouldn't be held across an `.await`.
+pub struct Ref<'a, T> {
+    guard: MutexGuard<'a, State<T>>,
+}
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard.value
+    }
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Replaces the value and notifies the receivers.
+    pub fn send(&self, value: T) {
+        self.send_if_modified(|current| {
+            *current = value;
+            true
+        });
+    }
+
+    /// Modifies the value in place, notifying the receivers only when `modify` returns `true`.
+    pub fn send_if_modified(&self, modify: impl FnOnce(&mut T) -> bool) -> bool {
+        let mut state = self.shared.lock();
+        if state.closed || !modify(&mut state.value) {
+            return false;
+        }
+        state.version += 1;
+        drop(state);
+        self.shared.changed.notify(usize::MAX);
+        true
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.lock(),
+        }
+    }
+
+    /// Creates a receiver that has seen the current value.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let seen = self.shared.lock().version;
+        Receiver {
+            shared: self.shared.clone(),
+            seen,
+        }
+    }
+
+    /// Closes the channel, the value can't change anymore.
+    pub fn close(&self) {
+        self.shared.close();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.shared.lock().closed
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.close();
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("value", &*self.borrow())
+            .finish()
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    seen: u64,
+}
+
+impl<T> Receiver<T> {
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.lock(),
+        }
+    }
+
+    /// Borrows the value, marking it as seen.
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        let guard = self.shared.lock();
+        self.seen = guard.version;
+        Ref { guard }
+    }
+
+    /// Returns whether the value changed since it was last seen.
+    pub fn has_changed(&self) -> bool {
+        self.shared.lock().version != self.seen
+    }
+
+    /// Waits for a value that wasn't seen yet, failing once the channel is closed.
+    pub async fn changed(&mut self) -> Result<(), RecvError> {
+        loop {
+            // Listening before checking, so a change in between isn't missed
+            let listener = self.shared.changed.listen();
+            {
+                let state = self.shared.lock();
+                if state.version != self.seen {
+                    self.seen = state.version;
+                    return Ok(());
+                }
+                if state.closed {
+                    return Err(RecvError);
+                }
+            }
+            listener.await;
+        }
+    }
+
+    /// Waits for a value matching the predicate (possibly the current one), failing once the
+    /// channel is closed without such a value.
+    pub async fn wait_for(&mut self, mut predicate: impl FnMut(&T) -> bool) -> Result<T, RecvError>
+    where
+        T: Clone,
+    {
+        loop {
+            let listener = self.shared.changed.listen();
+            {
+                let state = self.shared.lock();
+                self.seen = state.version;
+                if predicate(&state.value) {
+                    return Ok(state.value.clone());
+                }
+                if state.closed {
+                    return Err(RecvError);
+                }
+            }
+            listener.await;
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("value", &*self.borrow())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::channel;
+    use super::RecvError;
+
+    #[tokio::test]
+    async fn it_should_notify_changes_once() {
+        let (tx, mut rx) = channel(0);
+        assert!(!rx.has_changed());
+        tx.send(1);
+        tx.send(2);
+        rx.changed().await.unwrap();
+        assert_eq!(*rx.borrow(), 2);
+        assert!(!tx.send_if_modified(|_| false));
+        assert!(!rx.has_changed());
+    }
+
+    #[tokio::test]
+    async fn it_should_wait_for_a_matching_value() {
+        let (tx, mut rx) = channel(0);
+        let waiter = tokio::spawn(async move { rx.wait_for(|value| *value >= 2).await });
+        for value in 1..=3 {
+            tx.send(value);
+        }
+        assert!(waiter.await.unwrap().unwrap() >= 2);
+    }
+
+    #[tokio::test]
+    async fn it_should_close_when_the_senders_are_gone() {
+        let (tx, mut rx) = channel(0);
+        let other = tx.clone();
+        drop(tx);
+        assert!(!other.is_closed());
+        drop(other);
+        assert_eq!(rx.changed().await, Err(RecvError));
+        assert_eq!(rx.wait_for(|value| *value == 1).await, Err(RecvError));
+        assert_eq!(rx.wait_for(|value| *value == 0).await, Ok(0));
+    }
+}
@@ -0,0 +1,420 @@
+//! Supervision of related tasks: failures are surfaced as [`TaskEvent`]s and the whole
+//! group can be shut down gracefully.
+
+use crate::sync::mpsc;
+use crate::sync::Event;
+use crate::JaTask;
+use crate::MaybeSend;
+use futures_util::future::Either;
+use futures_util::pin_mut;
+use futures_util::Future;
+use futures_util::FutureExt;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::time::Duration;
+
+/// Identifies a task within its [`JaTaskGroup`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TaskId(u64);
+
+/// A task spawned with [`JaTaskGroup::spawn_owned`], cancelled on its own like a
+/// [`JaTask`] while still supervised by its group.
+#[derive(Debug)]
+pub struct GroupTask {
+    group: JaTaskGroup,
+    id: TaskId,
+}
+
+impl GroupTask {
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// Cancels the task, it's removed from its group.
+    pub fn cancel(self) {
+        self.group.cancel(self.id);
+    }
+}
+
+/// A task failure, reported to the subscribers of the group.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TaskEvent {
+    /// The task returned an error.
+    Failed {
+        group: String,
+        task: String,
+        error: String,
+    },
+    /// The task panicked.
+    Panicked {
+        group: String,
+        task: String,
+        message: String,
+    },
+}
+
+/// The outcome of [`JaTaskGroup::shutdown`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Shutdown {
+    /// The tasks still running at the deadline, which were aborted.
+    pub aborted: Vec<String>,
+}
+
+impl Shutdown {
+    /// Whether every task finished before the deadline.
+    pub fn is_drained(&self) -> bool {
+        self.aborted.is_empty()
+    }
+
+    /// Combines the outcomes of several groups.
+    pub fn merge(mut self, other: Shutdown) -> Shutdown {
+        self.aborted.extend(other.aborted);
+        self
+    }
+}
+
+/// Resolves once the shutdown of its group was requested, so long-running tasks can stop
+/// on their own instead of being aborted at the deadline.
+///
+/// It doesn't keep the group alive.
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownSignal {
+    requested: Arc<AtomicBool>,
+    event: Arc<Event>,
+}
+
+impl ShutdownSignal {
+    /// Whether the shutdown was requested.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the shutdown is requested.
+    pub async fn requested(&self) {
+        loop {
+            let listener = self.event.listen();
+            if self.is_requested() {
+                return;
+            }
+            listener.await;
+        }
+    }
+
+    /// Runs `future` until it completes or the shutdown is requested, whichever comes first.
+    pub async fn until<F: Future>(&self, future: F) -> Option<F::Output> {
+        let requested = self.requested();
+        pin_mut!(future);
+        pin_mut!(requested);
+        match futures_util::future::select(future, requested).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }
+    }
+
+    fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.event.notify(usize::MAX);
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_id: u64,
+    tasks: HashMap<TaskId, JaTask>,
+    subscribers: Vec<mpsc::UnboundedSender<TaskEvent>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    name: String,
+    state: Mutex<State>,
+    drained: Event,
+    signal: ShutdownSignal,
+}
+
+impl Inner {
+    fn finish(&self, id: TaskId, event: Option<TaskEvent>) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.tasks.remove(&id);
+        if let Some(event) = event {
+            state
+                .subscribers
+                .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+        if state.tasks.is_empty() {
+            self.drained.notify(usize::MAX);
+        }
+    }
+}
+
+/// Tracks a group of named tasks.
+///
+/// Errors returned by [`spawn_fallible`](Self::spawn_fallible) tasks and panics are reported
+/// as [`TaskEvent`]s, [`shutdown`](Self::shutdown) waits for the tasks up to a deadline and
+/// aborts the remaining ones. The tasks are cancelled once every clone of the group is dropped.
+#[derive(Clone, Debug)]
+pub struct JaTaskGroup {
+    inner: Arc<Inner>,
+}
+
+impl JaTaskGroup {
+    pub fn new(name: &str) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                name: name.to_owned(),
+                state: Mutex::new(State::default()),
+                drained: Event::new(),
+                signal: ShutdownSignal::default(),
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Spawns a task in the group.
+    pub fn spawn<F>(&self, name: &str, future: F) -> TaskId
+    where
+        F: Future<Output = ()> + MaybeSend + 'static,
+    {
+        self.spawn_fallible(name, future.map(Ok::<(), std::convert::Infallible>))
+    }
+
+    /// Spawns a task in the group, an error it returns is reported as [`TaskEvent::Failed`].
+    pub fn spawn_fallible<F, E>(&self, name: &str, future: F) -> TaskId
+    where
+        F: Future<Output = Result<(), E>> + MaybeSend + 'static,
+        E: Display,
+    {
+        // Held while spawning, so the task can't finish before it's tracked
+        let mut state = self.lock_state();
+        let id = TaskId(state.next_id);
+        state.next_id += 1;
+        if self.inner.signal.is_requested() {
+            tracing::warn!(
+                group = self.inner.name,
+                task = name,
+                "Group is shutting down, task not spawned"
+            );
+            return id;
+        }
+
+        let inner = Arc::downgrade(&self.inner);
+        let task_name = name.to_owned();
+        let task = crate::spawn(name, async move {
+            let outcome = AssertUnwindSafe(future).catch_unwind().await;
+            report(inner, id, task_name, outcome);
+        });
+        state.tasks.insert(id, task);
+        id
+    }
+
+    /// Spawns a task in the group, returning a [`GroupTask`] to cancel it with.
+    pub fn spawn_owned<F>(&self, name: &str, future: F) -> GroupTask
+    where
+        F: Future<Output = ()> + MaybeSend + 'static,
+    {
+        GroupTask {
+            group: self.clone(),
+            id: self.spawn(name, future),
+        }
+    }
+
+    /// Cancels a task of the group.
+    pub fn cancel(&self, id: TaskId) {
+        let task = self.lock_state().tasks.remove(&id);
+        if let Some(task) = task {
+            task.cancel();
+        }
+        self.notify_if_drained();
+    }
+
+    /// Returns the number of running tasks.
+    pub fn len(&self) -> usize {
+        self.lock_state().tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the signal resolving once the shutdown is requested.
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        self.inner.signal.clone()
+    }
+
+    /// Subscribes to the failures of the tasks.
+    pub fn events(&self) -> mpsc::UnboundedReceiver<TaskEvent> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.subscribe(sender);
+        receiver
+    }
+
+    /// Forwards the failures of the tasks to `sender`, e.g. to merge the events of several groups.
+    pub fn subscribe(&self, sender: mpsc::UnboundedSender<TaskEvent>) {
+        self.lock_state().subscribers.push(sender);
+    }
+
+    /// Requests the shutdown, waits for the tasks to finish up to `deadline` then aborts the
+    /// remaining ones. New tasks aren't spawned afterwards.
+    #[tracing::instrument(level = tracing::Level::DEBUG, skip(self), fields(group = self.inner.name))]
+    pub async fn shutdown(&self, deadline: Duration) -> Shutdown {
+        self.inner.signal.request();
+        if crate::timeout(deadline, self.drained()).await.is_ok() {
+            tracing::debug!("Task group drained");
+            return Shutdown::default();
+        }
+
+        let tasks = std::mem::take(&mut self.lock_state().tasks);
+        let aborted = tasks
+            .into_values()
+            .map(|task| {
+                task.cancel();
+                task.task_name.clone()
+            })
+            .collect::<Vec<_>>();
+        tracing::warn!("Aborted tasks at the shutdown deadline: {aborted:?}");
+        self.inner.drained.notify(usize::MAX);
+        Shutdown { aborted }
+    }
+
+    async fn drained(&self) {
+        loop {
+            let listener = self.inner.drained.listen();
+            if self.is_empty() {
+                return;
+            }
+            listener.await;
+        }
+    }
+
+    fn notify_if_drained(&self) {
+        if self.is_empty() {
+            self.inner.drained.notify(usize::MAX);
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, State> {
+        // Task failures are caught, a poisoned lock still holds consistent state
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+fn report<E: Display>(
+    inner: Weak<Inner>,
+    id: TaskId,
+    task: String,
+    outcome: Result<Result<(), E>, Box<dyn Any + Send>>,
+) {
+    let Some(inner) = inner.upgrade() else {
+        return;
+    };
+    let group = inner.name.clone();
+    let event = match outcome {
+        Ok(Ok(())) => None,
+        Ok(Err(error)) => {
+            let error = error.to_string();
+            tracing::error!(group, task, "Task failed: {error}");
+            Some(TaskEvent::Failed { group, task, error })
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            tracing::error!(group, task, "Task panicked: {message}");
+            Some(TaskEvent::Panicked {
+                group,
+                task,
+                message,
+            })
+        }
+    };
+    inner.finish(id, event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JaTaskGroup;
+    use super::TaskEvent;
+    use crate::sleep;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn it_should_report_failed_and_panicked_tasks() {
+        let group = JaTaskGroup::new("group");
+        let mut events = group.events();
+        group.spawn_fallible("failing", async { Err("boom") });
+        let event = events.recv().await.unwrap();
+        assert_eq!(
+            event,
+            TaskEvent::Failed {
+                group: "group".to_string(),
+                task: "failing".to_string(),
+                error: "boom".to_string()
+            }
+        );
+
+        group.spawn("panicking", async { panic!("oops") });
+        let event = events.recv().await.unwrap();
+        assert_eq!(
+            event,
+            TaskEvent::Panicked {
+                group: "group".to_string(),
+                task: "panicking".to_string(),
+                message: "oops".to_string()
+            }
+        );
+        assert!(group.is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_should_drain_cooperative_tasks() {
+        let group = JaTaskGroup::new("group");
+        let signal = group.shutdown_signal();
+        group.spawn("cooperative", async move {
+            signal.until(sleep(Duration::from_secs(10))).await;
+        });
+        assert_eq!(group.len(), 1);
+
+        let shutdown = group.shutdown(Duration::from_secs(1)).await;
+        assert!(shutdown.is_drained());
+        assert!(group.is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_should_abort_tasks_at_the_deadline() {
+        let group = JaTaskGroup::new("group");
+        group.spawn("stubborn", sleep(Duration::from_secs(10)));
+
+        let shutdown = group.shutdown(Duration::from_millis(20)).await;
+        assert_eq!(shutdown.aborted, vec!["stubborn".to_string()]);
+        assert!(group.is_empty());
+
+        group.spawn("late", sleep(Duration::from_secs(10)));
+        assert!(group.is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_should_cancel_owned_tasks_on_their_own() {
+        let group = JaTaskGroup::new("group");
+        let owned = group.spawn_owned("owned", sleep(Duration::from_secs(10)));
+        group.spawn("other", sleep(Duration::from_secs(10)));
+        assert_eq!(group.len(), 2);
+
+        owned.cancel();
+        assert_eq!(group.len(), 1);
+    }
+}
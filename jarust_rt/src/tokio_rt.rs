@@ -6,6 +6,13 @@ pub async fn sleep(duration: Duration) {
     tokio::time::sleep(duration).await;
 }
 
+pub async fn timeout<F, T>(duration: Duration, future: F) -> Result<T, ()>
+where
+    F: Future<Output = T>,
+{
+    tokio::time::timeout(duration, future).await.map_err(|_| ())
+}
+
 pub fn spawn<F>(name: &str, future: F) -> JaTask
 where
     F: Future + Send + 'static,
@@ -6,6 +6,23 @@ pub async fn sleep(duration: Duration) {
     gloo_timers::future::sleep(duration).await;
 }
 
+pub async fn timeout<F, T>(duration: Duration, future: F) -> Result<T, ()>
+where
+    F: Future<Output = T>,
+{
+    use futures_util::future::Either;
+    use futures_util::pin_mut;
+
+    let timer = gloo_timers::future::sleep(duration);
+    pin_mut!(future);
+    pin_mut!(timer);
+
+    match futures_util::future::select(future, timer).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right((_, _)) => Err(()),
+    }
+}
+
 pub fn spawn<F>(name: &str, future: F) -> JaTask
 where
     F: Future + 'static,